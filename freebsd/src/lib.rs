@@ -1,6 +1,8 @@
+#[cfg(target_os = "freebsd")]
 pub mod kernel;
 pub mod system;
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+#[cfg(target_os = "freebsd")]
 pub use kernel::*;
@@ -1,15 +1,41 @@
-use byteorder::{NativeEndian, ReadBytesExt};
-use freebsd_sys::{elfhints_hdr, ELFHINTS_MAGIC, _PATH_ELF_HINTS};
+use byteorder::{NativeEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(target_os = "freebsd")]
+use freebsd_sys::{elfhints_hdr, _PATH_ELF_HINTS, ELFHINTS_MAGIC};
 use std::error;
 use std::ffi::{CStr, CString, FromBytesWithNulError, IntoStringError, NulError};
 use std::fmt::{self, Debug, Display, Formatter};
 use std::fs::File;
-use std::io::{self, Read, Seek, SeekFrom};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::mem::size_of;
+use std::os::unix::ffi::OsStrExt;
 use std::path::{Path, PathBuf};
 use std::str::Utf8Error;
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// The layout of `<elf-hints.h>`, so that hints of a FreeBSD root can be written from other
+/// systems as well.
+#[cfg(not(target_os = "freebsd"))]
+#[allow(non_camel_case_types)]
+#[repr(C)]
+struct elfhints_hdr {
+    magic: u32,
+    version: u32,
+    strtab: u32,
+    strsize: u32,
+    dirlist: u32,
+    dirlistlen: u32,
+    spare: [u32; ELF_HINTS_SPARE_SIZE],
+}
+
+#[cfg(not(target_os = "freebsd"))]
+const ELFHINTS_MAGIC: u32 = 0x746e6845;
+
+#[cfg(not(target_os = "freebsd"))]
+const _PATH_ELF_HINTS: &[u8] = b"/var/run/ld-elf.so.hints\0";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 pub enum ReadElfHintsError {
     InvalidMagicNumber,
     Io(io::Error),
@@ -70,6 +96,51 @@ impl From<ReadDefaultElfHintsPathError> for ReadElfHintsError {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+pub enum WriteElfHintsError {
+    InvalidPath(PathBuf),
+    TooLarge,
+    Io(io::Error),
+    ReadDefaultElfHintsPath(ReadDefaultElfHintsPathError),
+}
+
+impl Debug for WriteElfHintsError {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Self::InvalidPath(path) => write!(formatter, "Invalid path {}", path.display()),
+            Self::TooLarge => write!(formatter, "Directory list is too large"),
+            Self::Io(error) => Debug::fmt(error, formatter),
+            Self::ReadDefaultElfHintsPath(error) => Debug::fmt(error, formatter),
+        }
+    }
+}
+
+impl Display for WriteElfHintsError {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Self::InvalidPath(path) => write!(formatter, "Invalid path {}", path.display()),
+            Self::TooLarge => write!(formatter, "Directory list is too large"),
+            Self::Io(error) => Display::fmt(error, formatter),
+            Self::ReadDefaultElfHintsPath(error) => Display::fmt(error, formatter),
+        }
+    }
+}
+
+impl error::Error for WriteElfHintsError {}
+
+impl From<io::Error> for WriteElfHintsError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<ReadDefaultElfHintsPathError> for WriteElfHintsError {
+    fn from(error: ReadDefaultElfHintsPathError) -> Self {
+        Self::ReadDefaultElfHintsPath(error)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 pub enum ReadDefaultElfHintsPathError {
     Utf8(Utf8Error),
     FromBytesWithNul(FromBytesWithNulError),
@@ -121,6 +192,14 @@ const ELF_HINTS_ENTRY_SEPARATOR: &str = ":";
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+const ELF_HINTS_VERSION: u32 = 1;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const ELF_HINTS_SPARE_SIZE: usize = 26;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug, Default)]
 pub struct ElfHintsEntry {
     path: PathBuf,
@@ -164,8 +243,6 @@ impl ElfHints {
     where
         T: Seek + Read + ReadBytesExt,
     {
-        const SPARE_SIZE: usize = 26;
-
         let mut header = elfhints_hdr {
             magic: reader.read_u32::<NativeEndian>()?,
             version: reader.read_u32::<NativeEndian>()?,
@@ -173,10 +250,10 @@ impl ElfHints {
             strsize: reader.read_u32::<NativeEndian>()?,
             dirlist: reader.read_u32::<NativeEndian>()?,
             dirlistlen: reader.read_u32::<NativeEndian>()?,
-            spare: [0; SPARE_SIZE],
+            spare: [0; ELF_HINTS_SPARE_SIZE],
         };
 
-        for i in 0..SPARE_SIZE {
+        for i in 0..ELF_HINTS_SPARE_SIZE {
             header.spare[i] = reader.read_u32::<NativeEndian>()?;
         }
 
@@ -199,11 +276,65 @@ impl ElfHints {
             CString::new(string)?
                 .into_string()?
                 .split(ELF_HINTS_ENTRY_SEPARATOR)
+                .filter(|x| !x.is_empty())
                 .map(|x| ElfHintsEntry::new(PathBuf::from(x)))
                 .collect(),
         ))
     }
 
+    pub fn write_to_default_elf_hints_path(&self) -> Result<(), WriteElfHintsError> {
+        self.write_to_file(&default_elf_hints_path()?)
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> Result<(), WriteElfHintsError> {
+        self.write(File::create(path)?)
+    }
+
+    /// Writes the hints the same way `ldconfig` does, with the string table following the header
+    /// directly and the directory list at its beginning.
+    pub fn write<T>(&self, mut writer: T) -> Result<(), WriteElfHintsError>
+    where
+        T: Write + WriteBytesExt,
+    {
+        let mut dirlist = Vec::new();
+
+        for (index, entry) in self.entries.iter().enumerate() {
+            let path = entry.path().as_os_str().as_bytes();
+
+            if path.is_empty() || path.contains(&0) || path.contains(&b':') {
+                return Err(WriteElfHintsError::InvalidPath(entry.path().clone()));
+            }
+
+            if index > 0 {
+                dirlist.extend(ELF_HINTS_ENTRY_SEPARATOR.as_bytes());
+            }
+
+            dirlist.extend(path);
+        }
+
+        let dirlistlen = u32::try_from(dirlist.len()).map_err(|_| WriteElfHintsError::TooLarge)?;
+        let strsize = dirlistlen
+            .checked_add(1)
+            .ok_or(WriteElfHintsError::TooLarge)?;
+
+        writer.write_u32::<NativeEndian>(ELFHINTS_MAGIC)?;
+        writer.write_u32::<NativeEndian>(ELF_HINTS_VERSION)?;
+        writer.write_u32::<NativeEndian>(size_of::<elfhints_hdr>() as u32)?;
+        writer.write_u32::<NativeEndian>(strsize)?;
+        writer.write_u32::<NativeEndian>(0)?;
+        writer.write_u32::<NativeEndian>(dirlistlen)?;
+
+        for _ in 0..ELF_HINTS_SPARE_SIZE {
+            writer.write_u32::<NativeEndian>(0)?;
+        }
+
+        writer.write_all(&dirlist)?;
+        writer.write_u8(0)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
     pub fn new(entries: Vec<ElfHintsEntry>) -> Self {
         Self { entries }
     }
@@ -220,3 +351,106 @@ impl ElfHints {
         self.entries = entries
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn round_trip(entries: &[&str]) -> Vec<PathBuf> {
+        let mut buffer = Vec::new();
+
+        ElfHints::new(
+            entries
+                .iter()
+                .map(|x| ElfHintsEntry::new(PathBuf::from(x)))
+                .collect(),
+        )
+        .write(&mut buffer)
+        .unwrap();
+
+        ElfHints::read(Cursor::new(buffer))
+            .unwrap()
+            .entries()
+            .iter()
+            .map(|x| x.path().clone())
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_entries() {
+        let entries = ["/lib", "/usr/lib", "/usr/local/lib"];
+
+        assert_eq!(
+            round_trip(&entries),
+            entries.iter().map(PathBuf::from).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn round_trips_empty_entries() {
+        assert!(round_trip(&[]).is_empty());
+    }
+
+    #[test]
+    fn writes_header_layout() {
+        let mut buffer = Vec::new();
+
+        ElfHints::new(vec![
+            ElfHintsEntry::new(PathBuf::from("/lib")),
+            ElfHintsEntry::new(PathBuf::from("/usr/lib")),
+        ])
+        .write(&mut buffer)
+        .unwrap();
+
+        let header_size = size_of::<elfhints_hdr>();
+        let mut reader = Cursor::new(&buffer);
+
+        assert_eq!(reader.read_u32::<NativeEndian>().unwrap(), ELFHINTS_MAGIC);
+        assert_eq!(
+            reader.read_u32::<NativeEndian>().unwrap(),
+            ELF_HINTS_VERSION
+        );
+        assert_eq!(
+            reader.read_u32::<NativeEndian>().unwrap(),
+            header_size as u32
+        );
+        assert_eq!(reader.read_u32::<NativeEndian>().unwrap(), 14);
+        assert_eq!(reader.read_u32::<NativeEndian>().unwrap(), 0);
+        assert_eq!(reader.read_u32::<NativeEndian>().unwrap(), 13);
+        assert_eq!(buffer.len(), header_size + 14);
+        assert_eq!(&buffer[header_size..], b"/lib:/usr/lib\0");
+    }
+
+    #[test]
+    fn rejects_invalid_paths() {
+        for path in ["", "/a:/b", "/a\0"] {
+            let result =
+                ElfHints::new(vec![ElfHintsEntry::new(PathBuf::from(path))]).write(&mut Vec::new());
+
+            assert!(matches!(result, Err(WriteElfHintsError::InvalidPath(_))));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_magic_number() {
+        let mut buffer = Vec::new();
+        ElfHints::default().write(&mut buffer).unwrap();
+        buffer[0] ^= 0xff;
+
+        assert!(matches!(
+            ElfHints::read(Cursor::new(buffer)),
+            Err(ReadElfHintsError::InvalidMagicNumber)
+        ));
+    }
+
+    #[test]
+    fn uses_default_path_of_ld_elf_so() {
+        assert_eq!(
+            default_elf_hints_path().unwrap(),
+            PathBuf::from("/var/run/ld-elf.so.hints")
+        );
+    }
+}
@@ -1 +1,2 @@
 pub mod ld;
+pub mod rc;
@@ -0,0 +1,627 @@
+use std::error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::fs::{metadata, remove_file, rename, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::string::FromUtf8Error;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub const RC_CONF_DEFAULT_PATH: &str = "/etc/rc.conf";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub enum ReadRcConfError {
+    Io(io::Error),
+    FromUtf8(FromUtf8Error),
+}
+
+impl Debug for ReadRcConfError {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Self::Io(error) => Debug::fmt(error, formatter),
+            Self::FromUtf8(error) => Debug::fmt(error, formatter),
+        }
+    }
+}
+
+impl Display for ReadRcConfError {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Self::Io(error) => Display::fmt(error, formatter),
+            Self::FromUtf8(error) => Display::fmt(error, formatter),
+        }
+    }
+}
+
+impl error::Error for ReadRcConfError {}
+
+impl From<io::Error> for ReadRcConfError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<FromUtf8Error> for ReadRcConfError {
+    fn from(error: FromUtf8Error) -> Self {
+        Self::FromUtf8(error)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub enum WriteRcConfError {
+    Io(io::Error),
+}
+
+impl Debug for WriteRcConfError {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Self::Io(error) => Debug::fmt(error, formatter),
+        }
+    }
+}
+
+impl Display for WriteRcConfError {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Self::Io(error) => Display::fmt(error, formatter),
+        }
+    }
+}
+
+impl error::Error for WriteRcConfError {}
+
+impl From<io::Error> for WriteRcConfError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub fn default_rc_conf_path() -> PathBuf {
+    PathBuf::from(RC_CONF_DEFAULT_PATH)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RcConfQuote {
+    None,
+    Single,
+    Double,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A single `name=value` line. Lines are written back exactly as they were read until their value
+/// is changed. Parameter expansions within values are kept as is and never evaluated.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RcConfAssignment {
+    name: String,
+    value: String,
+    quote: RcConfQuote,
+    indentation: String,
+    suffix: String,
+    raw: Option<String>,
+}
+
+impl RcConfAssignment {
+    pub fn new(name: String, value: String) -> Self {
+        Self {
+            name,
+            value,
+            quote: RcConfQuote::Double,
+            indentation: String::default(),
+            suffix: String::default(),
+            raw: None,
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn value(&self) -> &String {
+        &self.value
+    }
+
+    pub fn set_value(&mut self, value: String) {
+        if self.value != value {
+            self.value = value;
+            self.raw = None;
+        }
+    }
+
+    pub fn quote(&self) -> RcConfQuote {
+        self.quote
+    }
+
+    fn parse(lines: &[&str]) -> Option<(Self, usize)> {
+        let first = lines.first()?;
+        let trimmed = first.trim_start();
+        let indentation = &first[..first.len() - trimmed.len()];
+
+        let name_length = trimmed
+            .find(|character: char| !(character.is_ascii_alphanumeric() || character == '_'))
+            .unwrap_or(trimmed.len());
+        let name = &trimmed[..name_length];
+
+        if name.is_empty() || name.starts_with(|character: char| character.is_ascii_digit()) {
+            return None;
+        }
+
+        let mut text = trimmed[name_length..].strip_prefix('=')?.to_string();
+        let mut consumed = 1;
+
+        loop {
+            match parse_value(&text) {
+                RcConfValue::Complete(value, quote, length) => {
+                    let suffix = &text[length..];
+                    let comment = suffix.trim_start();
+
+                    if !comment.is_empty() && !comment.starts_with('#') {
+                        return None;
+                    }
+
+                    return Some((
+                        Self {
+                            name: name.to_string(),
+                            value,
+                            quote,
+                            indentation: indentation.to_string(),
+                            suffix: suffix.to_string(),
+                            raw: Some(lines[..consumed].join("\n")),
+                        },
+                        consumed,
+                    ));
+                }
+                RcConfValue::Incomplete => {
+                    text.push('\n');
+                    text.push_str(lines.get(consumed)?);
+                    consumed += 1;
+                }
+                RcConfValue::Invalid => return None,
+            }
+        }
+    }
+}
+
+impl Display for RcConfAssignment {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), fmt::Error> {
+        match &self.raw {
+            Some(raw) => write!(formatter, "{}", raw),
+            None => write!(
+                formatter,
+                "{}{}={}{}",
+                self.indentation,
+                self.name,
+                quote_value(&self.value, self.quote),
+                self.suffix
+            ),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RcConfLine {
+    Assignment(RcConfAssignment),
+    Other(String),
+}
+
+impl Display for RcConfLine {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Self::Assignment(assignment) => Display::fmt(assignment, formatter),
+            Self::Other(line) => write!(formatter, "{}", line),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// An editable `rc.conf` like `sysrc(8)` edits it. Comments, blank lines and lines which are not
+/// plain assignments are preserved.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RcConf {
+    lines: Vec<RcConfLine>,
+}
+
+impl RcConf {
+    pub fn new(lines: Vec<RcConfLine>) -> Self {
+        Self { lines }
+    }
+
+    pub fn lines(&self) -> &Vec<RcConfLine> {
+        &self.lines
+    }
+
+    pub fn parse(input: &str) -> Self {
+        let mut pieces = input.split('\n').collect::<Vec<_>>();
+
+        if input.ends_with('\n') {
+            pieces.pop();
+        }
+
+        let mut lines = Vec::default();
+        let mut index = 0;
+
+        while index < pieces.len() {
+            match RcConfAssignment::parse(&pieces[index..]) {
+                Some((assignment, consumed)) => {
+                    lines.push(RcConfLine::Assignment(assignment));
+                    index += consumed;
+                }
+                None => {
+                    lines.push(RcConfLine::Other(pieces[index].to_string()));
+                    index += 1;
+                }
+            }
+        }
+
+        Self::new(lines)
+    }
+
+    pub fn read_from_default_rc_conf_path() -> Result<Self, ReadRcConfError> {
+        Self::read_from_file(&default_rc_conf_path())
+    }
+
+    pub fn read_from_file(path: &Path) -> Result<Self, ReadRcConfError> {
+        Self::read(File::open(path)?)
+    }
+
+    pub fn read<T>(mut reader: T) -> Result<Self, ReadRcConfError>
+    where
+        T: Read,
+    {
+        let mut buffer = Vec::default();
+        reader.read_to_end(&mut buffer)?;
+
+        Ok(Self::parse(&String::from_utf8(buffer)?))
+    }
+
+    /// Writes into a temporary file next to `path` first and renames it afterwards, so that
+    /// `path` is never left partially written. The permissions of an existing file are kept.
+    pub fn write_to_file(&self, path: &Path) -> Result<(), WriteRcConfError> {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let temporary_path = path.with_file_name(format!(".{}.{}", file_name, process::id()));
+
+        let result = (|| {
+            let file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&temporary_path)?;
+
+            match metadata(path) {
+                Ok(metadata) => file.set_permissions(metadata.permissions())?,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+                Err(error) => return Err(error.into()),
+            }
+
+            self.write(&file)?;
+            file.sync_all()?;
+
+            Ok(rename(&temporary_path, path)?)
+        })();
+
+        if result.is_err() {
+            let _ = remove_file(&temporary_path);
+        }
+
+        result
+    }
+
+    pub fn write<T>(&self, mut writer: T) -> Result<(), WriteRcConfError>
+    where
+        T: Write,
+    {
+        Ok(writer.write_all(self.to_string().as_bytes())?)
+    }
+
+    fn assignment(&self, name: &str) -> Option<&RcConfAssignment> {
+        self.lines.iter().rev().find_map(|line| match line {
+            RcConfLine::Assignment(assignment) if assignment.name == name => Some(assignment),
+            _ => None,
+        })
+    }
+
+    fn assignment_mut(&mut self, name: &str) -> Option<&mut RcConfAssignment> {
+        self.lines.iter_mut().rev().find_map(|line| match line {
+            RcConfLine::Assignment(assignment) if assignment.name == name => Some(assignment),
+            _ => None,
+        })
+    }
+
+    /// Returns the value of the last assignment of `name`, which is the one the shell would use.
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.assignment(name).map(|assignment| assignment.value())
+    }
+
+    /// Changes the last assignment of `name` in place or appends a new one.
+    pub fn set(&mut self, name: &str, value: &str) {
+        match self.assignment_mut(name) {
+            Some(assignment) => assignment.set_value(value.to_string()),
+            None => self
+                .lines
+                .push(RcConfLine::Assignment(RcConfAssignment::new(
+                    name.to_string(),
+                    value.to_string(),
+                ))),
+        }
+    }
+
+    /// Adds the words of `value` which are not part of the value of `name` yet, like
+    /// `sysrc name+=value` does.
+    pub fn append(&mut self, name: &str, value: &str) {
+        let current = self.get(name).cloned().unwrap_or_default();
+        let mut words = current.split_whitespace().collect::<Vec<_>>();
+
+        for word in value.split_whitespace() {
+            if !words.contains(&word) {
+                words.push(word);
+            }
+        }
+
+        self.set(name, &words.join(" "))
+    }
+
+    /// Removes all assignments of `name` and returns if any existed.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let length = self.lines.len();
+
+        self.lines.retain(|line| match line {
+            RcConfLine::Assignment(assignment) => assignment.name != name,
+            RcConfLine::Other(_) => true,
+        });
+
+        length != self.lines.len()
+    }
+}
+
+impl Display for RcConf {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), fmt::Error> {
+        for line in &self.lines {
+            writeln!(formatter, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+enum RcConfValue {
+    Complete(String, RcConfQuote, usize),
+    Incomplete,
+    Invalid,
+}
+
+/// Parses a shell word like `"a"'b'c`, whose quoted and unquoted parts are joined. A `#` is only
+/// the beginning of a comment after whitespace, so it is part of the value otherwise.
+fn parse_value(text: &str) -> RcConfValue {
+    let mut characters = text.char_indices().peekable();
+    let mut value = String::default();
+    let mut quote = None;
+
+    loop {
+        let character = match characters.peek() {
+            None => {
+                return RcConfValue::Complete(value, quote.unwrap_or(RcConfQuote::None), text.len())
+            }
+            Some((index, character)) if character.is_whitespace() => {
+                return RcConfValue::Complete(value, quote.unwrap_or(RcConfQuote::None), *index)
+            }
+            Some((_, character)) => *character,
+        };
+
+        characters.next();
+
+        let part_quote = match character {
+            '"' => loop {
+                match characters.next() {
+                    Some((_, '"')) => break RcConfQuote::Double,
+                    Some((_, '\\')) => match characters.next() {
+                        Some((_, '\n')) => {}
+                        Some((_, escaped @ ('"' | '\\' | '$' | '`'))) => value.push(escaped),
+                        Some((_, escaped)) => {
+                            value.push('\\');
+                            value.push(escaped);
+                        }
+                        None => return RcConfValue::Incomplete,
+                    },
+                    Some((_, character)) => value.push(character),
+                    None => return RcConfValue::Incomplete,
+                }
+            },
+            '\'' => loop {
+                match characters.next() {
+                    Some((_, '\'')) => break RcConfQuote::Single,
+                    Some((_, character)) => value.push(character),
+                    None => return RcConfValue::Incomplete,
+                }
+            },
+            '\\' => match characters.next() {
+                Some((_, '\n')) | None => return RcConfValue::Invalid,
+                Some((_, escaped)) => {
+                    value.push(escaped);
+                    RcConfQuote::None
+                }
+            },
+            '`' | '$' | ';' | '&' | '|' | '(' | ')' | '<' | '>' => return RcConfValue::Invalid,
+            character => {
+                value.push(character);
+                RcConfQuote::None
+            }
+        };
+
+        // Values made of differently quoted parts are written back double quoted.
+        quote = match (quote, part_quote) {
+            (None, part_quote) => Some(part_quote),
+            (Some(quote), part_quote) if quote == part_quote => Some(quote),
+            (Some(_), _) => Some(RcConfQuote::Double),
+        };
+    }
+}
+
+fn quote_value(value: &str, quote: RcConfQuote) -> String {
+    let unquoted = !value.is_empty()
+        && value
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || "_-.,:/+=@%".contains(character));
+
+    match quote {
+        RcConfQuote::None if unquoted => value.to_string(),
+        RcConfQuote::Single if !value.contains('\'') => format!("'{}'", value),
+        RcConfQuote::None | RcConfQuote::Single | RcConfQuote::Double => {
+            let mut quoted = String::from("\"");
+
+            for character in value.chars() {
+                if matches!(character, '"' | '\\' | '$' | '`') {
+                    quoted.push('\\');
+                }
+
+                quoted.push(character);
+            }
+
+            quoted.push('"');
+            quoted
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env::temp_dir;
+    use std::fs::{
+        create_dir_all, read_dir, read_to_string, remove_dir_all, set_permissions, write,
+    };
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn preserves_untouched_lines() {
+        let input = "# comment\n\nhostname=\"zone\" # name\n  sshd_enable=YES\nif [ -f x ]; then\n\tfoo=bar\nfi\n";
+
+        assert_eq!(RcConf::parse(input).to_string(), input);
+    }
+
+    #[test]
+    fn reads_quoted_values() {
+        let rc_conf =
+            RcConf::parse("a=\"x \\\"y\\\" \\$z\"\nb='c d'\nc=e\\ f\nd=\"multi\nline\"\ne=\"\"\n");
+
+        assert_eq!(rc_conf.get("a").map(String::as_str), Some("x \"y\" $z"));
+        assert_eq!(rc_conf.get("b").map(String::as_str), Some("c d"));
+        assert_eq!(rc_conf.get("c").map(String::as_str), Some("e f"));
+        assert_eq!(rc_conf.get("d").map(String::as_str), Some("multi\nline"));
+        assert_eq!(rc_conf.get("e").map(String::as_str), Some(""));
+        assert_eq!(rc_conf.get("f"), None);
+    }
+
+    #[test]
+    fn keeps_unsupported_lines() {
+        let rc_conf = RcConf::parse("a=$b\nc=d; e=f\n1x=y\nunterminated=\"value\n");
+
+        assert!(rc_conf
+            .lines()
+            .iter()
+            .all(|line| matches!(line, RcConfLine::Other(_))));
+    }
+
+    #[test]
+    fn uses_last_assignment() {
+        let mut rc_conf = RcConf::parse("a=\"1\"\na=\"2\"\n");
+        assert_eq!(rc_conf.get("a").map(String::as_str), Some("2"));
+
+        rc_conf.set("a", "3");
+        assert_eq!(rc_conf.to_string(), "a=\"1\"\na=\"3\"\n");
+    }
+
+    #[test]
+    fn sets_values_in_place() {
+        let mut rc_conf = RcConf::parse(
+            "# header\nsshd_enable=NO # ssh\nhostname='old'\nsendmail_enable=\"YES\"\n",
+        );
+
+        rc_conf.set("sshd_enable", "YES");
+        rc_conf.set("hostname", "it's");
+        rc_conf.set("sendmail_enable", "YES");
+        rc_conf.set("ifconfig_em0", "inet 10.0.0.2/24");
+
+        assert_eq!(
+            rc_conf.to_string(),
+            "# header\nsshd_enable=YES # ssh\nhostname=\"it's\"\nsendmail_enable=\"YES\"\nifconfig_em0=\"inet 10.0.0.2/24\"\n",
+        );
+    }
+
+    #[test]
+    fn appends_missing_words() {
+        let mut rc_conf = RcConf::parse("cloned_interfaces=\"lo1\"\n");
+
+        rc_conf.append("cloned_interfaces", "lo1 bridge0");
+        rc_conf.append("kld_list", "pf");
+
+        assert_eq!(
+            rc_conf.to_string(),
+            "cloned_interfaces=\"lo1 bridge0\"\nkld_list=\"pf\"\n",
+        );
+    }
+
+    #[test]
+    fn removes_all_assignments() {
+        let mut rc_conf = RcConf::parse("a=1\n# a=2\nb=2\na=3\n");
+
+        assert!(rc_conf.remove("a"));
+        assert!(!rc_conf.remove("a"));
+        assert_eq!(rc_conf.to_string(), "# a=2\nb=2\n");
+    }
+
+    #[test]
+    fn escapes_special_characters() {
+        let mut rc_conf = RcConf::default();
+        rc_conf.set("a", "\"$x\" `y` \\");
+
+        let output = rc_conf.to_string();
+        assert_eq!(output, "a=\"\\\"\\$x\\\" \\`y\\` \\\\\"\n");
+        assert_eq!(
+            RcConf::parse(&output).get("a").map(String::as_str),
+            Some("\"$x\" `y` \\")
+        );
+    }
+
+    #[test]
+    fn treats_hash_as_comment_after_whitespace_only() {
+        let rc_conf = RcConf::parse("a=\"x\"#c\nb=\"x\" #c\nc=x#c\nd=\"x\"'y'z\n");
+
+        assert_eq!(rc_conf.get("a").map(String::as_str), Some("x#c"));
+        assert_eq!(rc_conf.get("b").map(String::as_str), Some("x"));
+        assert_eq!(rc_conf.get("c").map(String::as_str), Some("x#c"));
+        assert_eq!(rc_conf.get("d").map(String::as_str), Some("xyz"));
+    }
+
+    #[test]
+    fn replaces_files_keeping_permissions() {
+        let directory = temp_dir().join(format!("freebsd-rc-conf-{}", process::id()));
+        let path = directory.join("rc.conf");
+
+        create_dir_all(&directory).unwrap();
+        write(&path, "a=1\n").unwrap();
+        set_permissions(&path, PermissionsExt::from_mode(0o640)).unwrap();
+
+        let mut rc_conf = RcConf::read_from_file(&path).unwrap();
+        rc_conf.set("a", "2");
+        rc_conf.write_to_file(&path).unwrap();
+
+        assert_eq!(read_to_string(&path).unwrap(), "a=2\n");
+        assert_eq!(metadata(&path).unwrap().permissions().mode() & 0o777, 0o640);
+        assert_eq!(read_dir(&directory).unwrap().count(), 1);
+
+        remove_dir_all(&directory).unwrap();
+    }
+}
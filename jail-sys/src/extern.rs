@@ -49,7 +49,7 @@ pub struct jailparam {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////
 
-#[link(name = "jail")]
+#[cfg_attr(target_os = "freebsd", link(name = "jail"))]
 extern "C" {
     pub fn jail_getid(name: *const c_char) -> c_int;
     pub fn jail_getname(jid: c_int) -> *mut c_char;
@@ -1,20 +1,235 @@
+#[cfg(target_os = "freebsd")]
+mod login;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(target_os = "freebsd")]
 use jail_sys::{jail_attach, AttachJailError};
 use nix::errno::Errno;
-use nix::sys::wait::waitpid;
-use nix::unistd::{execve, fork, ForkResult};
+use nix::fcntl::{fcntl, FcntlArg, OFlag};
+use nix::poll::{poll, PollFd, PollFlags};
+use nix::sys::signal::{killpg, Signal};
+use nix::sys::stat::{umask, Mode};
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+use nix::unistd::{
+    chdir, dup2, execve, fork, getuid, initgroups, pipe2, setgid, setgroups, setpgid, setuid,
+    write, ForkResult, Group, Pid, User,
+};
+use std::convert::Infallible;
+use std::env::{remove_var, set_var, vars_os};
 use std::error;
 use std::ffi::CString;
 use std::ffi::NulError;
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
+use std::fs::File;
+use std::io::{self, Read, Write};
 use std::iter::once;
+use std::mem::size_of;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::panic::resume_unwind;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{sleep, spawn, JoinHandle};
+use std::time::{Duration, Instant};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const JAIL_EXECUTE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const JAIL_EXECUTE_CHUNK_SIZE: usize = 4096;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const JAIL_EXECUTE_FAILURE_EXIT_CODE: i32 = 127;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const JAIL_EXECUTE_DEFAULT_PATH: &str = "/bin:/usr/bin";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const JAIL_EXECUTE_DEFAULT_SHELL: &str = "/bin/sh";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const JAIL_EXECUTE_CHILD_ERROR_ERRNO: i32 = 0;
+const JAIL_EXECUTE_CHILD_ERROR_UNKNOWN_USER: i32 = 1;
+const JAIL_EXECUTE_CHILD_ERROR_UNKNOWN_GROUP: i32 = 2;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExecuteJailStatus {
+    Exited(i32),
+    Signaled(i32),
+}
+
+impl ExecuteJailStatus {
+    pub fn success(&self) -> bool {
+        matches!(self, Self::Exited(0))
+    }
+
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            Self::Exited(code) => Some(*code),
+            Self::Signaled(_) => None,
+        }
+    }
+
+    pub fn signal(&self) -> Option<i32> {
+        match self {
+            Self::Exited(_) => None,
+            Self::Signaled(signal) => Some(*signal),
+        }
+    }
+
+    pub fn exit_ok(&self) -> Result<(), ExecuteJailError> {
+        match self.success() {
+            true => Ok(()),
+            false => Err(ExecuteJailError::Unsuccessful(*self)),
+        }
+    }
+}
+
+impl Display for ExecuteJailStatus {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Exited(code) => write!(formatter, "exit code {}", code),
+            Self::Signaled(signal) => write!(formatter, "signal {}", signal),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub struct ExecuteJailOutput {
+    status: ExecuteJailStatus,
+    stdout: Option<Vec<u8>>,
+    stderr: Option<Vec<u8>>,
+}
+
+impl ExecuteJailOutput {
+    pub fn new(
+        status: ExecuteJailStatus,
+        stdout: Option<Vec<u8>>,
+        stderr: Option<Vec<u8>>,
+    ) -> Self {
+        Self {
+            status,
+            stdout,
+            stderr,
+        }
+    }
+
+    pub fn status(&self) -> &ExecuteJailStatus {
+        &self.status
+    }
+
+    pub fn stdout(&self) -> &Option<Vec<u8>> {
+        &self.stdout
+    }
+
+    pub fn stderr(&self) -> &Option<Vec<u8>> {
+        &self.stderr
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Default)]
+pub struct ExecuteJailOptions {
+    timeout: Option<Duration>,
+    capture: bool,
+    echo: bool,
+    user: Option<String>,
+    group: Option<String>,
+    working_directory: Option<String>,
+    umask: Option<u32>,
+    inherit_login_environment: bool,
+}
+
+impl ExecuteJailOptions {
+    pub fn timeout(&self) -> &Option<Duration> {
+        &self.timeout
+    }
+
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout
+    }
+
+    pub fn capture(&self) -> bool {
+        self.capture
+    }
+
+    pub fn set_capture(&mut self, capture: bool) {
+        self.capture = capture
+    }
+
+    pub fn echo(&self) -> bool {
+        self.echo
+    }
+
+    /// Writes captured output to the standard output and error of the current process as well.
+    pub fn set_echo(&mut self, echo: bool) {
+        self.echo = echo
+    }
+
+    pub fn user(&self) -> &Option<String> {
+        &self.user
+    }
+
+    pub fn set_user(&mut self, user: Option<String>) {
+        self.user = user
+    }
+
+    pub fn group(&self) -> &Option<String> {
+        &self.group
+    }
+
+    pub fn set_group(&mut self, group: Option<String>) {
+        self.group = group
+    }
+
+    pub fn working_directory(&self) -> &Option<String> {
+        &self.working_directory
+    }
+
+    pub fn set_working_directory(&mut self, working_directory: Option<String>) {
+        self.working_directory = working_directory
+    }
+
+    pub fn umask(&self) -> &Option<u32> {
+        &self.umask
+    }
+
+    pub fn set_umask(&mut self, umask: Option<u32>) {
+        self.umask = umask
+    }
+
+    pub fn inherit_login_environment(&self) -> bool {
+        self.inherit_login_environment
+    }
+
+    pub fn set_inherit_login_environment(&mut self, inherit_login_environment: bool) {
+        self.inherit_login_environment = inherit_login_environment
+    }
+}
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 pub enum ExecuteJailError {
     Errno(Errno),
-    AttachJailError(AttachJailError),
+    IoError(io::Error),
     NulError(NulError),
+    Timeout(Duration),
+    Unsuccessful(ExecuteJailStatus),
+    UnknownUser(String),
+    UnknownGroup(String),
 }
 
 impl error::Error for ExecuteJailError {}
@@ -23,8 +238,18 @@ impl Debug for ExecuteJailError {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         match self {
             Self::Errno(errno) => Debug::fmt(errno, formatter),
-            Self::AttachJailError(error) => Debug::fmt(error, formatter),
+            Self::IoError(error) => Debug::fmt(error, formatter),
             Self::NulError(error) => Debug::fmt(error, formatter),
+            Self::Timeout(timeout) => write!(
+                formatter,
+                "Execution timed out after {} seconds",
+                timeout.as_secs()
+            ),
+            Self::Unsuccessful(status) => {
+                write!(formatter, "Execution failed with {}", status)
+            }
+            Self::UnknownUser(user) => write!(formatter, "User {} does not exist", user),
+            Self::UnknownGroup(group) => write!(formatter, "Group {} does not exist", group),
         }
     }
 }
@@ -33,8 +258,18 @@ impl Display for ExecuteJailError {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         match self {
             Self::Errno(errno) => Display::fmt(errno, formatter),
-            Self::AttachJailError(error) => Display::fmt(error, formatter),
+            Self::IoError(error) => Display::fmt(error, formatter),
             Self::NulError(error) => Display::fmt(error, formatter),
+            Self::Timeout(timeout) => write!(
+                formatter,
+                "Execution timed out after {} seconds",
+                timeout.as_secs()
+            ),
+            Self::Unsuccessful(status) => {
+                write!(formatter, "Execution failed with {}", status)
+            }
+            Self::UnknownUser(user) => write!(formatter, "User {} does not exist", user),
+            Self::UnknownGroup(group) => write!(formatter, "Group {} does not exist", group),
         }
     }
 }
@@ -45,9 +280,9 @@ impl From<Errno> for ExecuteJailError {
     }
 }
 
-impl From<AttachJailError> for ExecuteJailError {
-    fn from(error: AttachJailError) -> Self {
-        Self::AttachJailError(error)
+impl From<io::Error> for ExecuteJailError {
+    fn from(error: io::Error) -> Self {
+        Self::IoError(error)
     }
 }
 
@@ -59,40 +294,538 @@ impl From<NulError> for ExecuteJailError {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+#[cfg(target_os = "freebsd")]
 pub fn jail_execute<S, T>(
     jid: usize,
     program: &str,
     arguments: &[T],
     environment_variables: &[S],
-) -> Result<(), ExecuteJailError>
+    options: &ExecuteJailOptions,
+) -> Result<ExecuteJailOutput, ExecuteJailError>
 where
     S: AsRef<str>,
     T: AsRef<str>,
 {
+    fork_execute(
+        || match jail_attach(jid) {
+            Ok(()) => Ok(()),
+            Err(AttachJailError::Errno(errno)) => Err(errno),
+            Err(AttachJailError::TryFromIntError(_)) => Err(Errno::EINVAL),
+        },
+        program,
+        arguments,
+        environment_variables,
+        options,
+    )
+}
+
+/// Jails exist on FreeBSD only.
+#[cfg(not(target_os = "freebsd"))]
+pub fn jail_execute<S, T>(
+    _jid: usize,
+    _program: &str,
+    _arguments: &[T],
+    _environment_variables: &[S],
+    _options: &ExecuteJailOptions,
+) -> Result<ExecuteJailOutput, ExecuteJailError>
+where
+    S: AsRef<str>,
+    T: AsRef<str>,
+{
+    Err(ExecuteJailError::Errno(Errno::ENOSYS))
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Forks, runs `prepare` inside the child, switches to the user and group of `options` and
+/// replaces the child with `program`. Failures inside the child are reported back to the parent
+/// through a close-on-exec pipe. Captured output is read until the child exits only, since
+/// processes left running in the background may keep the pipes open forever.
+pub fn fork_execute<F, S, T>(
+    prepare: F,
+    program: &str,
+    arguments: &[T],
+    environment_variables: &[S],
+    options: &ExecuteJailOptions,
+) -> Result<ExecuteJailOutput, ExecuteJailError>
+where
+    F: FnOnce() -> Result<(), Errno>,
+    S: AsRef<str>,
+    T: AsRef<str>,
+{
+    let program_string = CString::new(program)?;
+    let argument_strings = once(program)
+        .chain(arguments.iter().map(|x| x.as_ref()))
+        .map(CString::new)
+        .collect::<Result<Vec<CString>, _>>()?;
+    let environment_variable_strings = environment_variables
+        .iter()
+        .map(|x| CString::new(x.as_ref()))
+        .collect::<Result<Vec<CString>, _>>()?;
+
+    let (error_reader, error_writer) = pipe()?;
+
+    let (stdout, stderr) = match options.capture() {
+        true => (Some(pipe()?), Some(pipe()?)),
+        false => (None, None),
+    };
+
     match unsafe { fork()? } {
-        ForkResult::Parent { child, .. } => {
-            waitpid(child, None)?;
+        ForkResult::Parent { child } => {
+            drop(error_writer);
+
+            let exited = Arc::new(AtomicBool::new(false));
+
+            let stdout = stdout.map(|(reader, writer)| {
+                drop(writer);
+                read_until_exited(
+                    reader,
+                    options
+                        .echo()
+                        .then(|| Box::new(io::stdout()) as Box<dyn Write + Send>),
+                    exited.clone(),
+                )
+            });
+            let stderr = stderr.map(|(reader, writer)| {
+                drop(writer);
+                read_until_exited(
+                    reader,
+                    options
+                        .echo()
+                        .then(|| Box::new(io::stderr()) as Box<dyn Write + Send>),
+                    exited.clone(),
+                )
+            });
+
+            // The child may not have moved into its own process group yet
+            let _ = setpgid(child, child);
+
+            let mut error = Vec::default();
+            let mut error_reader = error_reader;
+            let error_result = error_reader.read_to_end(&mut error);
+
+            let status = match options.timeout() {
+                None => wait(child),
+                Some(timeout) => wait_with_timeout(child, *timeout),
+            };
+
+            exited.store(true, Ordering::SeqCst);
+
+            let stdout = stdout.map(join).transpose();
+            let stderr = stderr.map(join).transpose();
+
+            error_result?;
+
+            if error.len() >= 2 * size_of::<i32>() {
+                let mut kind = [0; size_of::<i32>()];
+                kind.copy_from_slice(&error[..size_of::<i32>()]);
+                let mut code = [0; size_of::<i32>()];
+                code.copy_from_slice(&error[size_of::<i32>()..2 * size_of::<i32>()]);
+
+                return Err(match i32::from_ne_bytes(kind) {
+                    JAIL_EXECUTE_CHILD_ERROR_UNKNOWN_USER => {
+                        ExecuteJailError::UnknownUser(options.user().clone().unwrap_or_default())
+                    }
+                    JAIL_EXECUTE_CHILD_ERROR_UNKNOWN_GROUP => {
+                        ExecuteJailError::UnknownGroup(options.group().clone().unwrap_or_default())
+                    }
+                    _ => Errno::from_i32(i32::from_ne_bytes(code)).into(),
+                });
+            }
+
+            Ok(ExecuteJailOutput::new(status?, stdout?, stderr?))
         }
         ForkResult::Child => {
-            unsafe {
-                jail_attach(jid)?;
-
-                execve(
-                    &CString::new(program)?,
-                    &once(program)
-                        .chain(arguments.iter().map(|x| x.as_ref()))
-                        .map(CString::new)
-                        .collect::<Result<Vec<CString>, _>>()?,
-                    &environment_variables
-                        .iter()
-                        .map(|x| CString::new(x.as_ref()))
-                        .collect::<Result<Vec<CString>, _>>()?,
-                )?;
-
-                libc::_exit(0);
+            let result = (move || -> Result<Infallible, ExecuteJailChildError> {
+                setpgid(Pid::from_raw(0), Pid::from_raw(0))?;
+
+                if let Some((_, writer)) = &stdout {
+                    dup2(writer.as_raw_fd(), 1)?;
+                }
+
+                if let Some((_, writer)) = &stderr {
+                    dup2(writer.as_raw_fd(), 2)?;
+                }
+
+                prepare()?;
+
+                let environment_variable_strings =
+                    switch_context(options, environment_variable_strings)?;
+
+                Ok(execve(
+                    &program_string,
+                    &argument_strings,
+                    &environment_variable_strings,
+                )?)
+            })();
+
+            let Err(error) = result;
+            let (kind, errno) = match error {
+                ExecuteJailChildError::Errno(errno) => (JAIL_EXECUTE_CHILD_ERROR_ERRNO, errno),
+                ExecuteJailChildError::UnknownUser => {
+                    (JAIL_EXECUTE_CHILD_ERROR_UNKNOWN_USER, Errno::UnknownErrno)
+                }
+                ExecuteJailChildError::UnknownGroup => {
+                    (JAIL_EXECUTE_CHILD_ERROR_UNKNOWN_GROUP, Errno::UnknownErrno)
+                }
             };
+
+            let mut message = kind.to_ne_bytes().to_vec();
+            message.extend((errno as i32).to_ne_bytes());
+            let _ = write(error_writer.as_raw_fd(), &message);
+
+            unsafe { libc::_exit(JAIL_EXECUTE_FAILURE_EXIT_CODE) }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+enum ExecuteJailChildError {
+    Errno(Errno),
+    UnknownUser,
+    UnknownGroup,
+}
+
+impl From<Errno> for ExecuteJailChildError {
+    fn from(errno: Errno) -> Self {
+        Self::Errno(errno)
+    }
+}
+
+impl From<NulError> for ExecuteJailChildError {
+    fn from(_: NulError) -> Self {
+        Self::Errno(Errno::EINVAL)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Applies the user, group, umask and working directory of `options` to the current process and
+/// returns the environment the program should be executed with. Like `jexec -l -U`, all names are
+/// resolved after attaching, so they refer to the databases inside the jail.
+fn switch_context(
+    options: &ExecuteJailOptions,
+    environment_variables: Vec<CString>,
+) -> Result<Vec<CString>, ExecuteJailChildError> {
+    let user = match options.user() {
+        Some(name) => Some(User::from_name(name)?.ok_or(ExecuteJailChildError::UnknownUser)?),
+        None if options.inherit_login_environment() => {
+            Some(User::from_uid(getuid())?.ok_or(ExecuteJailChildError::UnknownUser)?)
         }
+        None => None,
+    };
+
+    let gid = match options.group() {
+        Some(name) => Some(
+            Group::from_name(name)?
+                .ok_or(ExecuteJailChildError::UnknownGroup)?
+                .gid,
+        ),
+        None => options
+            .user()
+            .as_ref()
+            .and(user.as_ref())
+            .map(|user| user.gid),
     };
 
-    Ok(())
+    let environment_variables = match (&user, options.inherit_login_environment()) {
+        (Some(user), true) => login_environment(user, environment_variables)?,
+        _ => environment_variables,
+    };
+
+    if let Some(gid) = gid {
+        match (options.user(), &user) {
+            (Some(_), Some(user)) => initgroups(&CString::new(user.name.as_str())?, gid)?,
+            _ => setgroups(&[gid])?,
+        }
+
+        setgid(gid)?;
+    }
+
+    if let (Some(_), Some(user)) = (options.user(), &user) {
+        setuid(user.uid)?;
+    }
+
+    if let Some(value) = options.umask() {
+        umask(Mode::from_bits_truncate(*value as _));
+    }
+
+    match (options.working_directory(), &user) {
+        (Some(working_directory), _) => chdir(working_directory.as_str())?,
+        (None, Some(user)) if options.inherit_login_environment() => chdir(&user.dir)?,
+        (None, _) => {}
+    }
+
+    Ok(environment_variables)
+}
+
+/// Builds a login environment for `user` which is overridden by `environment_variables`.
+fn login_environment(
+    user: &User,
+    environment_variables: Vec<CString>,
+) -> Result<Vec<CString>, ExecuteJailChildError> {
+    for (key, _) in vars_os() {
+        remove_var(key);
+    }
+
+    set_var("PATH", JAIL_EXECUTE_DEFAULT_PATH);
+
+    #[cfg(target_os = "freebsd")]
+    login::set_user_context(user.uid)?;
+
+    set_var("HOME", &user.dir);
+    set_var(
+        "SHELL",
+        match user.shell.as_os_str().is_empty() {
+            true => JAIL_EXECUTE_DEFAULT_SHELL.as_ref(),
+            false => user.shell.as_os_str(),
+        },
+    );
+    set_var("USER", &user.name);
+
+    let overridden = environment_variables
+        .iter()
+        .filter_map(|variable| variable.as_bytes().split(|byte| *byte == b'=').next())
+        .map(Vec::from)
+        .collect::<Vec<_>>();
+
+    let mut login_environment_variables = vars_os()
+        .filter(|(key, _)| !overridden.iter().any(|x| x == key.as_bytes()))
+        .map(|(key, value)| {
+            let mut variable = Vec::from(key.as_bytes());
+            variable.push(b'=');
+            variable.extend(value.as_bytes());
+
+            CString::new(variable)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    login_environment_variables.extend(environment_variables);
+
+    Ok(login_environment_variables)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn pipe() -> Result<(File, File), Errno> {
+    let (reader, writer) = pipe2(OFlag::O_CLOEXEC)?;
+
+    Ok(unsafe { (File::from_raw_fd(reader), File::from_raw_fd(writer)) })
+}
+
+/// Reads `file` until its end or until `exited` is set. Output which is already buffered when
+/// `exited` is set is read as well, and the pipe is closed afterwards.
+fn read_until_exited(
+    mut file: File,
+    mut echo: Option<Box<dyn Write + Send>>,
+    exited: Arc<AtomicBool>,
+) -> JoinHandle<io::Result<Vec<u8>>> {
+    spawn(move || {
+        fcntl(file.as_raw_fd(), FcntlArg::F_SETFL(OFlag::O_NONBLOCK)).map_err(io::Error::from)?;
+
+        let mut buffer = Vec::default();
+
+        loop {
+            // The flag is loaded before draining, so that nothing written before the exit is lost.
+            let stop = exited.load(Ordering::SeqCst);
+
+            if drain(&mut file, &mut echo, &mut buffer)? || stop {
+                break;
+            }
+
+            let mut descriptors = [PollFd::new(file.as_raw_fd(), PollFlags::POLLIN)];
+
+            match poll(
+                &mut descriptors,
+                JAIL_EXECUTE_POLL_INTERVAL.as_millis() as _,
+            ) {
+                Ok(_) | Err(Errno::EINTR) => {}
+                Err(errno) => return Err(errno.into()),
+            }
+        }
+
+        Ok(buffer)
+    })
+}
+
+/// Reads everything currently available from the non-blocking `file` and returns whether its end
+/// has been reached.
+fn drain(
+    file: &mut File,
+    echo: &mut Option<Box<dyn Write + Send>>,
+    buffer: &mut Vec<u8>,
+) -> io::Result<bool> {
+    let mut chunk = [0; JAIL_EXECUTE_CHUNK_SIZE];
+
+    loop {
+        let length = match file.read(&mut chunk) {
+            Ok(0) => return Ok(true),
+            Ok(length) => length,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(false),
+            Err(error) => return Err(error),
+        };
+
+        if let Some(echo) = echo {
+            echo.write_all(&chunk[..length])?;
+            echo.flush()?;
+        }
+
+        buffer.extend_from_slice(&chunk[..length]);
+    }
+}
+
+fn join(handle: JoinHandle<io::Result<Vec<u8>>>) -> Result<Vec<u8>, ExecuteJailError> {
+    match handle.join() {
+        Ok(result) => Ok(result?),
+        Err(error) => resume_unwind(error),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn status(wait_status: WaitStatus) -> Option<ExecuteJailStatus> {
+    match wait_status {
+        WaitStatus::Exited(_, code) => Some(ExecuteJailStatus::Exited(code)),
+        WaitStatus::Signaled(_, signal, _) => Some(ExecuteJailStatus::Signaled(signal as i32)),
+        _ => None,
+    }
+}
+
+fn wait(child: Pid) -> Result<ExecuteJailStatus, ExecuteJailError> {
+    loop {
+        match waitpid(child, None) {
+            Err(Errno::EINTR) => continue,
+            Err(errno) => return Err(errno.into()),
+            Ok(wait_status) => {
+                if let Some(status) = status(wait_status) {
+                    return Ok(status);
+                }
+            }
+        }
+    }
+}
+
+fn wait_with_timeout(child: Pid, timeout: Duration) -> Result<ExecuteJailStatus, ExecuteJailError> {
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        match waitpid(child, Some(WaitPidFlag::WNOHANG)) {
+            Err(Errno::EINTR) => {}
+            Err(errno) => return Err(errno.into()),
+            Ok(wait_status) => {
+                if let Some(status) = status(wait_status) {
+                    return Ok(status);
+                }
+            }
+        }
+
+        if Instant::now() >= deadline {
+            killpg(child, Signal::SIGKILL)?;
+            wait(child)?;
+
+            return Err(ExecuteJailError::Timeout(timeout));
+        }
+
+        sleep(JAIL_EXECUTE_POLL_INTERVAL);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env::temp_dir;
+    use std::fs::{read_to_string, remove_file};
+    use std::path::PathBuf;
+    use std::process;
+
+    fn execute_shell(
+        script: &str,
+        options: &ExecuteJailOptions,
+    ) -> Result<ExecuteJailOutput, ExecuteJailError> {
+        fork_execute(
+            || Ok(()),
+            "/bin/sh",
+            &["-c", script],
+            &[] as &[&str],
+            options,
+        )
+    }
+
+    fn capture_options() -> ExecuteJailOptions {
+        let mut options = ExecuteJailOptions::default();
+        options.set_capture(true);
+
+        options
+    }
+
+    fn temporary_path(name: &str) -> PathBuf {
+        temp_dir().join(format!("jail-utility-sys-{}-{}", process::id(), name))
+    }
+
+    #[test]
+    fn returns_exit_code() {
+        let output = execute_shell("exit 3", &ExecuteJailOptions::default()).unwrap();
+
+        assert_eq!(output.status().code(), Some(3));
+        assert!(!output.status().success());
+        assert!(output.stdout().is_none());
+        assert!(output.stderr().is_none());
+    }
+
+    #[test]
+    fn reports_missing_program() {
+        let result = fork_execute(
+            || Ok(()),
+            "/nonexistent/program",
+            &[] as &[&str],
+            &[] as &[&str],
+            &ExecuteJailOptions::default(),
+        );
+
+        assert!(matches!(
+            result,
+            Err(ExecuteJailError::Errno(Errno::ENOENT))
+        ));
+    }
+
+    #[test]
+    fn captures_stdout_and_stderr() {
+        let output = execute_shell("echo out; echo err >&2", &capture_options()).unwrap();
+
+        assert!(output.status().success());
+        assert_eq!(output.stdout().as_deref(), Some(&b"out\n"[..]));
+        assert_eq!(output.stderr().as_deref(), Some(&b"err\n"[..]));
+    }
+
+    #[test]
+    fn kills_process_group_on_timeout() {
+        let path = temporary_path("timeout");
+        let _ = remove_file(&path);
+
+        let mut options = ExecuteJailOptions::default();
+        options.set_timeout(Some(Duration::from_millis(200)));
+
+        let script = format!("(sleep 1; echo alive > {}) & wait", path.display());
+        let result = execute_shell(&script, &options);
+
+        assert!(matches!(result, Err(ExecuteJailError::Timeout(_))));
+
+        sleep(Duration::from_millis(1500));
+
+        assert!(read_to_string(&path).is_err());
+    }
+
+    #[test]
+    fn returns_without_waiting_for_background_processes() {
+        let start = Instant::now();
+        let output = execute_shell("sleep 5 & echo started", &capture_options()).unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(4));
+        assert!(output.status().success());
+        assert_eq!(output.stdout().as_deref(), Some(&b"started\n"[..]));
+    }
 }
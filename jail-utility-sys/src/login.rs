@@ -0,0 +1,62 @@
+use libc::{c_int, c_uint, passwd, uid_t};
+use nix::errno::Errno;
+use nix::unistd::Uid;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const LOGIN_SETPATH: c_uint = 0x0004;
+const LOGIN_SETPRIORITY: c_uint = 0x0008;
+const LOGIN_SETRESOURCES: c_uint = 0x0010;
+const LOGIN_SETUMASK: c_uint = 0x0020;
+const LOGIN_SETENV: c_uint = 0x0080;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[allow(non_camel_case_types)]
+#[repr(C)]
+struct login_cap_t {
+    _private: [u8; 0],
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[link(name = "util")]
+extern "C" {
+    fn login_getpwclass(pwd: *const passwd) -> *mut login_cap_t;
+    fn login_close(lc: *mut login_cap_t);
+    fn setusercontext(lc: *mut login_cap_t, pwd: *const passwd, uid: uid_t, flags: c_uint)
+        -> c_int;
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Applies path, environment, umask, resource limits and priority of the login class of `uid`
+/// from `login.conf` to the current process.
+pub fn set_user_context(uid: Uid) -> Result<(), Errno> {
+    let pwd = unsafe { libc::getpwuid(uid.as_raw()) };
+
+    if pwd.is_null() {
+        return Err(Errno::ENOENT);
+    }
+
+    let result = unsafe {
+        let lc = login_getpwclass(pwd);
+
+        let result = setusercontext(
+            lc,
+            pwd,
+            uid.as_raw(),
+            LOGIN_SETPATH | LOGIN_SETPRIORITY | LOGIN_SETRESOURCES | LOGIN_SETUMASK | LOGIN_SETENV,
+        );
+
+        login_close(lc);
+
+        result
+    };
+
+    if result != 0 {
+        return Err(Errno::last());
+    }
+
+    Ok(())
+}
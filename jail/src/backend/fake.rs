@@ -0,0 +1,286 @@
+use crate::backend::Backend;
+use crate::{
+    CreateJailError, DestroyJailError, ExecuteJailError, ExecuteJailOptions, ExecuteJailOutput,
+    ExecuteJailStatus, GetJailIdError, JailId, JailName, JailParameter, JailParameterKey,
+    JailParameterValue,
+};
+use errno::Errno;
+use jail_sys::wrapper::RemoveJailError;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A call received by the fake backend.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FakeJailCall {
+    Create {
+        parameters: Vec<(JailParameterKey, JailParameterValue)>,
+    },
+    Execute {
+        id: usize,
+        program: String,
+        arguments: Vec<String>,
+    },
+    HostExecute {
+        program: String,
+        arguments: Vec<String>,
+    },
+    Destroy {
+        id: usize,
+    },
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A backend recording its calls and keeping jails in memory. Programs exit successfully without
+/// output unless a failure or an output has been injected.
+#[derive(Debug, Default)]
+pub struct FakeBackend {
+    calls: RefCell<Vec<FakeJailCall>>,
+    jails: RefCell<BTreeMap<usize, String>>,
+    next_id: Cell<usize>,
+    create_failure: Cell<Option<i32>>,
+    destroy_failure: Cell<Option<i32>>,
+    execute_failures: RefCell<HashMap<String, ExecuteJailStatus>>,
+    execute_stdouts: RefCell<HashMap<String, Vec<u8>>>,
+}
+
+impl FakeBackend {
+    pub fn calls(&self) -> Vec<FakeJailCall> {
+        self.calls.borrow().clone()
+    }
+
+    /// Returns the names of the jails which have been created but not destroyed yet.
+    pub fn running(&self) -> Vec<String> {
+        self.jails.borrow().values().cloned().collect()
+    }
+
+    /// Lets every following creation fail with `errno`.
+    pub fn fail_create(&self, errno: i32) {
+        self.create_failure.set(Some(errno))
+    }
+
+    /// Lets every following destruction fail with `errno`.
+    pub fn fail_destroy(&self, errno: i32) {
+        self.destroy_failure.set(Some(errno))
+    }
+
+    /// Lets every following execution of `program`, inside of jails or on the host, finish with
+    /// `status`.
+    pub fn fail_execute(&self, program: &str, status: ExecuteJailStatus) {
+        self.execute_failures
+            .borrow_mut()
+            .insert(program.to_string(), status);
+    }
+
+    /// Lets every following execution of `program` with captured output print `stdout`.
+    pub fn set_stdout(&self, program: &str, stdout: &[u8]) {
+        self.execute_stdouts
+            .borrow_mut()
+            .insert(program.to_string(), stdout.to_vec());
+    }
+
+    fn output(&self, program: &str, options: &ExecuteJailOptions) -> ExecuteJailOutput {
+        let status = self
+            .execute_failures
+            .borrow()
+            .get(program)
+            .copied()
+            .unwrap_or(ExecuteJailStatus::Exited(0));
+
+        if !options.capture() {
+            return ExecuteJailOutput::new(status, None, None);
+        }
+
+        let stdout = self
+            .execute_stdouts
+            .borrow()
+            .get(program)
+            .cloned()
+            .unwrap_or_default();
+
+        ExecuteJailOutput::new(status, Some(stdout), Some(Vec::default()))
+    }
+}
+
+impl Backend for FakeBackend {
+    fn id(&self, name: &JailName) -> Result<Option<JailId>, GetJailIdError> {
+        let name = name.to_string();
+
+        Ok(self
+            .jails
+            .borrow()
+            .iter()
+            .find(|(_, jail_name)| **jail_name == name)
+            .map(|(id, _)| JailId::new(*id)))
+    }
+
+    fn create(&self, parameters: Vec<JailParameter>) -> Result<JailId, CreateJailError> {
+        let parameters = parameters
+            .iter()
+            .map(|parameter| (parameter.key().clone(), parameter.value().clone()))
+            .collect::<Vec<_>>();
+
+        self.calls.borrow_mut().push(FakeJailCall::Create {
+            parameters: parameters.clone(),
+        });
+
+        if let Some(errno) = self.create_failure.get() {
+            return Err(CreateJailError::Errno(Errno(errno)));
+        }
+
+        let name = parameters
+            .into_iter()
+            .find(|(key, _)| key == "name")
+            .map(|(_, value)| value)
+            .unwrap_or_default();
+
+        let id = self.next_id.get() + 1;
+        self.next_id.set(id);
+        self.jails.borrow_mut().insert(id, name);
+
+        Ok(JailId::new(id))
+    }
+
+    fn execute(
+        &self,
+        id: JailId,
+        program: &str,
+        arguments: &[String],
+        _environment_variables: &HashMap<String, String>,
+        options: &ExecuteJailOptions,
+    ) -> Result<ExecuteJailOutput, ExecuteJailError> {
+        self.calls.borrow_mut().push(FakeJailCall::Execute {
+            id: id.into(),
+            program: program.to_string(),
+            arguments: arguments.to_vec(),
+        });
+
+        if !self.jails.borrow().contains_key(&usize::from(id)) {
+            return Err(ExecuteJailError::Errno(nix::errno::Errno::ESRCH));
+        }
+
+        Ok(self.output(program, options))
+    }
+
+    fn host_execute(
+        &self,
+        program: &str,
+        arguments: &[String],
+        _environment_variables: &HashMap<String, String>,
+        options: &ExecuteJailOptions,
+    ) -> Result<ExecuteJailOutput, ExecuteJailError> {
+        self.calls.borrow_mut().push(FakeJailCall::HostExecute {
+            program: program.to_string(),
+            arguments: arguments.to_vec(),
+        });
+
+        Ok(self.output(program, options))
+    }
+
+    fn destroy(&self, id: JailId) -> Result<(), DestroyJailError> {
+        self.calls
+            .borrow_mut()
+            .push(FakeJailCall::Destroy { id: id.into() });
+
+        if let Some(errno) = self.destroy_failure.get() {
+            return Err(RemoveJailError::Errno(nix::errno::Errno::from_i32(errno)).into());
+        }
+
+        match self.jails.borrow_mut().remove(&usize::from(id)) {
+            Some(_) => Ok(()),
+            None => Err(RemoveJailError::Errno(nix::errno::Errno::ESRCH).into()),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_calls_in_order() {
+        let backend = FakeBackend::default();
+        let options = ExecuteJailOptions::default();
+
+        let id = backend
+            .create(vec![JailParameter::new("name", "zone")])
+            .unwrap();
+        backend
+            .execute(
+                id,
+                "/bin/echo",
+                &[String::from("hello")],
+                &HashMap::default(),
+                &options,
+            )
+            .unwrap();
+        backend.destroy(id).unwrap();
+
+        assert_eq!(
+            backend.calls(),
+            vec![
+                FakeJailCall::Create {
+                    parameters: vec![(String::from("name"), String::from("zone"))],
+                },
+                FakeJailCall::Execute {
+                    id: 1,
+                    program: String::from("/bin/echo"),
+                    arguments: vec![String::from("hello")],
+                },
+                FakeJailCall::Destroy { id: 1 },
+            ]
+        );
+        assert!(backend.running().is_empty());
+    }
+
+    #[test]
+    fn finds_running_jails_by_name() {
+        let backend = FakeBackend::default();
+
+        let id = backend
+            .create(vec![JailParameter::new("name", "zone")])
+            .unwrap();
+
+        assert_eq!(
+            backend
+                .id(&JailName::new(String::from("zone")))
+                .unwrap()
+                .map(usize::from),
+            Some(usize::from(id))
+        );
+        assert!(backend
+            .id(&JailName::new(String::from("other")))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn injects_failures() {
+        let backend = FakeBackend::default();
+        let options = ExecuteJailOptions::default();
+
+        backend.fail_execute("/usr/bin/false", ExecuteJailStatus::Exited(1));
+
+        let id = backend
+            .create(vec![JailParameter::new("name", "zone")])
+            .unwrap();
+
+        assert_eq!(
+            *backend
+                .execute(id, "/usr/bin/false", &[], &HashMap::default(), &options)
+                .unwrap()
+                .status(),
+            ExecuteJailStatus::Exited(1)
+        );
+
+        backend.fail_create(1);
+        backend.fail_destroy(1);
+
+        assert!(backend.create(Vec::default()).is_err());
+        assert!(backend.destroy(id).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+#[cfg(feature = "fake")]
+mod fake;
+mod system;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(feature = "fake")]
+pub use fake::*;
+pub use system::*;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+use crate::{
+    CreateJailError, DestroyJailError, ExecuteJailError, ExecuteJailOptions, ExecuteJailOutput,
+    GetJailIdError, JailId, JailName, JailParameter,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Creates jails, runs programs inside of them or on the host and removes them again. Jails are
+/// only known by their ids, since the fake hands out ids without creating any jail.
+pub trait Backend {
+    /// Returns the id of the jail named `name`, or `None` if it is not running.
+    fn id(&self, name: &JailName) -> Result<Option<JailId>, GetJailIdError>;
+
+    fn create(&self, parameters: Vec<JailParameter>) -> Result<JailId, CreateJailError>;
+
+    fn execute(
+        &self,
+        id: JailId,
+        program: &str,
+        arguments: &[String],
+        environment_variables: &HashMap<String, String>,
+        options: &ExecuteJailOptions,
+    ) -> Result<ExecuteJailOutput, ExecuteJailError>;
+
+    /// Executes `program` on the host with the same options as `execute`.
+    fn host_execute(
+        &self,
+        program: &str,
+        arguments: &[String],
+        environment_variables: &HashMap<String, String>,
+        options: &ExecuteJailOptions,
+    ) -> Result<ExecuteJailOutput, ExecuteJailError>;
+
+    fn destroy(&self, id: JailId) -> Result<(), DestroyJailError>;
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+thread_local! {
+    static BACKEND: RefCell<Rc<dyn Backend>> = RefCell::new(Rc::new(SystemBackend));
+}
+
+/// Returns the backend of the current thread, which creates real jails unless it has been replaced.
+pub fn current() -> Rc<dyn Backend> {
+    BACKEND.with(|backend| backend.borrow().clone())
+}
+
+/// Replaces the backend of the current thread, e.g. with a `FakeBackend` in tests.
+pub fn replace(backend: Rc<dyn Backend>) {
+    BACKEND.with(|current| *current.borrow_mut() = backend)
+}
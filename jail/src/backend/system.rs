@@ -0,0 +1,47 @@
+use crate::backend::Backend;
+use crate::{
+    host_execute, CreateJailError, DestroyJailError, ExecuteJailError, ExecuteJailOptions,
+    ExecuteJailOutput, GetJailIdError, Jail, JailId, JailName, JailParameter,
+};
+use std::collections::HashMap;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The backend operating on the jails of the system.
+#[derive(Debug, Default)]
+pub struct SystemBackend;
+
+impl Backend for SystemBackend {
+    fn id(&self, name: &JailName) -> Result<Option<JailId>, GetJailIdError> {
+        name.clone().try_into()
+    }
+
+    fn create(&self, parameters: Vec<JailParameter>) -> Result<JailId, CreateJailError> {
+        Ok(Jail::create(parameters)?.id())
+    }
+
+    fn execute(
+        &self,
+        id: JailId,
+        program: &str,
+        arguments: &[String],
+        environment_variables: &HashMap<String, String>,
+        options: &ExecuteJailOptions,
+    ) -> Result<ExecuteJailOutput, ExecuteJailError> {
+        Jail::new(id).execute(program, arguments, environment_variables, options)
+    }
+
+    fn host_execute(
+        &self,
+        program: &str,
+        arguments: &[String],
+        environment_variables: &HashMap<String, String>,
+        options: &ExecuteJailOptions,
+    ) -> Result<ExecuteJailOutput, ExecuteJailError> {
+        host_execute(program, arguments, environment_variables, options)
+    }
+
+    fn destroy(&self, id: JailId) -> Result<(), DestroyJailError> {
+        Jail::new(id).destroy()
+    }
+}
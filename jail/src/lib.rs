@@ -2,12 +2,16 @@
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+pub mod backend;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 use errno::Errno;
 use jail_sys::wrapper::{
     jail_attach, jail_getid, jail_remove, jailparam_set, GetIdJailError, JailFlag, Jailparam,
     NewJailparamError, RemoveJailError, SetJailparamError,
 };
-use jail_utility_sys::jail_execute;
+use jail_utility_sys::{fork_execute, jail_execute};
 use serde::{Deserialize, Serialize};
 use std::error;
 use std::fmt;
@@ -164,6 +168,18 @@ pub type ExecuteJailError = jail_utility_sys::ExecuteJailError;
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+pub type ExecuteJailStatus = jail_utility_sys::ExecuteJailStatus;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub type ExecuteJailOutput = jail_utility_sys::ExecuteJailOutput;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub type ExecuteJailOptions = jail_utility_sys::ExecuteJailOptions;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 pub enum NewJailParameterError {
     NewJailparamError(NewJailparamError),
 }
@@ -268,7 +284,8 @@ impl Jail {
         program: &str,
         arguments: &[T],
         environment_variables: I,
-    ) -> Result<(), ExecuteJailError>
+        options: &ExecuteJailOptions,
+    ) -> Result<ExecuteJailOutput, ExecuteJailError>
     where
         I: IntoIterator<Item = (K, V)>,
         K: AsRef<str>,
@@ -283,6 +300,7 @@ impl Jail {
                 .into_iter()
                 .map(|(key, value)| format!("{}={}", key.as_ref(), value.as_ref()))
                 .collect::<Vec<_>>(),
+            options,
         )?)
     }
 
@@ -290,3 +308,30 @@ impl Jail {
         Ok(jail_remove(self.id.into())?)
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Executes `program` on the host with the same options as `Jail::execute`.
+pub fn host_execute<I, K, V, T>(
+    program: &str,
+    arguments: &[T],
+    environment_variables: I,
+    options: &ExecuteJailOptions,
+) -> Result<ExecuteJailOutput, ExecuteJailError>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+    T: AsRef<str>,
+{
+    fork_execute(
+        || Ok(()),
+        program,
+        arguments,
+        &environment_variables
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key.as_ref(), value.as_ref()))
+            .collect::<Vec<_>>(),
+        options,
+    )
+}
@@ -0,0 +1,661 @@
+use crate::backend::{Backend, BackendError};
+use crate::file_system::identifier::FileSystemIdentifier;
+use crate::file_system::property::{FileSystemKeyStatus, FileSystemProperty};
+use crate::pool::identifier::PoolIdentifier;
+use crate::pool::PoolHealth;
+use crate::snapshot::identifier::SnapshotIdentifier;
+use crate::snapshot::options::{ReceiveOptions, SendOptions};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::mem::ManuallyDrop;
+use std::os::unix::prelude::{FromRawFd, RawFd};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A file system of the fake backend.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FakeFileSystem {
+    snapshots: Vec<String>,
+    properties: BTreeMap<String, String>,
+    mounted: bool,
+    encrypted: bool,
+    key_loaded: bool,
+    jail_id: Option<i32>,
+}
+
+impl FakeFileSystem {
+    /// Returns the names of the snapshots, from the oldest to the most recent one.
+    pub fn snapshots(&self) -> &Vec<String> {
+        &self.snapshots
+    }
+
+    pub fn properties(&self) -> &BTreeMap<String, String> {
+        &self.properties
+    }
+
+    pub fn is_mounted(&self) -> bool {
+        self.mounted
+    }
+
+    /// Returns the jail the file system is attached to.
+    pub fn jail_id(&self) -> Option<i32> {
+        self.jail_id
+    }
+
+    pub fn key_status(&self) -> FileSystemKeyStatus {
+        match (self.encrypted, self.key_loaded) {
+            (false, _) => FileSystemKeyStatus::None,
+            (true, true) => FileSystemKeyStatus::Available,
+            (true, false) => FileSystemKeyStatus::Unavailable,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A backend keeping pools and file systems in memory. Streams sent by it only carry the base
+/// snapshot and whether they are raw and encrypted, and can be received by fake backends only.
+#[derive(Debug, Default)]
+pub struct FakeBackend {
+    pools: RefCell<BTreeMap<String, PoolHealth>>,
+    pool_capacities: RefCell<BTreeMap<String, u64>>,
+    file_systems: RefCell<BTreeMap<String, FakeFileSystem>>,
+}
+
+impl FakeBackend {
+    /// Adds a pool together with its root file system.
+    pub fn add_pool(&self, identifier: &PoolIdentifier, health: PoolHealth) {
+        self.pools
+            .borrow_mut()
+            .insert(identifier.to_string(), health);
+        self.file_systems.borrow_mut().insert(
+            identifier.to_string(),
+            FakeFileSystem {
+                mounted: true,
+                ..FakeFileSystem::default()
+            },
+        );
+    }
+
+    /// Sets the allocated space of the pool in percent, which is zero for added pools.
+    pub fn set_pool_capacity(&self, identifier: &PoolIdentifier, capacity: u64) {
+        self.pool_capacities
+            .borrow_mut()
+            .insert(identifier.to_string(), capacity);
+    }
+
+    pub fn file_system(&self, identifier: &FileSystemIdentifier) -> Option<FakeFileSystem> {
+        self.file_systems
+            .borrow()
+            .get(&identifier.to_string())
+            .cloned()
+    }
+
+    fn with_file_system<F, R>(
+        &self,
+        identifier: &FileSystemIdentifier,
+        function: F,
+    ) -> Result<R, BackendError>
+    where
+        F: FnOnce(&mut FakeFileSystem) -> Result<R, BackendError>,
+    {
+        match self
+            .file_systems
+            .borrow_mut()
+            .get_mut(&identifier.to_string())
+        {
+            Some(file_system) => function(file_system),
+            None => Err(BackendError::FileSystemNotExisting(identifier.to_string())),
+        }
+    }
+
+    fn snapshot_position(
+        file_system: &FakeFileSystem,
+        identifier: &SnapshotIdentifier,
+    ) -> Result<usize, BackendError> {
+        file_system
+            .snapshots
+            .iter()
+            .position(|name| name == identifier.name())
+            .ok_or_else(|| BackendError::SnapshotNotExisting(identifier.to_string()))
+    }
+}
+
+impl Backend for FakeBackend {
+    fn pool_health(&self, identifier: &PoolIdentifier) -> Result<Option<PoolHealth>, BackendError> {
+        Ok(self.pools.borrow().get(&identifier.to_string()).copied())
+    }
+
+    fn pool_capacity(&self, identifier: &PoolIdentifier) -> Result<Option<u64>, BackendError> {
+        if !self.pools.borrow().contains_key(&identifier.to_string()) {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            self.pool_capacities
+                .borrow()
+                .get(&identifier.to_string())
+                .copied()
+                .unwrap_or_default(),
+        ))
+    }
+
+    fn has_file_system(&self, identifier: &FileSystemIdentifier) -> Result<bool, BackendError> {
+        Ok(self
+            .file_systems
+            .borrow()
+            .contains_key(&identifier.to_string()))
+    }
+
+    fn children(
+        &self,
+        identifier: &FileSystemIdentifier,
+    ) -> Result<Vec<FileSystemIdentifier>, BackendError> {
+        let prefix = format!("{}/", identifier);
+
+        self.with_file_system(identifier, |_file_system| Ok(()))?;
+
+        Ok(self
+            .file_systems
+            .borrow()
+            .keys()
+            .filter_map(|key| key.strip_prefix(&prefix))
+            .filter(|name| !name.contains('/'))
+            .map(|name| {
+                let mut child = identifier.clone();
+                child.components_mut().push(name.to_string());
+                child
+            })
+            .collect())
+    }
+
+    fn create_file_system(
+        &self,
+        identifier: &FileSystemIdentifier,
+        properties: &[FileSystemProperty],
+    ) -> Result<(), BackendError> {
+        let parent = identifier.clone().parent();
+
+        if !self.has_file_system(&parent)? {
+            return Err(BackendError::FileSystemNotExisting(parent.to_string()));
+        }
+
+        if self.has_file_system(identifier)? {
+            return Err(BackendError::FileSystemAlreadyExisting(
+                identifier.to_string(),
+            ));
+        }
+
+        let encrypted = properties.iter().any(|property| match property {
+            FileSystemProperty::Encryption(encryption) => encryption.is_enabled(),
+            _ => false,
+        });
+
+        self.file_systems.borrow_mut().insert(
+            identifier.to_string(),
+            FakeFileSystem {
+                properties: properties
+                    .iter()
+                    .map(|property| (property.name().to_string(), property.value()))
+                    .collect(),
+                encrypted,
+                key_loaded: encrypted,
+                ..FakeFileSystem::default()
+            },
+        );
+
+        Ok(())
+    }
+
+    fn destroy_file_system(&self, identifier: &FileSystemIdentifier) -> Result<(), BackendError> {
+        let name = identifier.to_string();
+        let prefix = format!("{}/", name);
+
+        self.with_file_system(identifier, |file_system| {
+            if !file_system.snapshots.is_empty() {
+                return Err(BackendError::FileSystemHasSnapshots(name.clone()));
+            }
+
+            Ok(())
+        })?;
+
+        if self
+            .file_systems
+            .borrow()
+            .keys()
+            .any(|key| key.starts_with(&prefix))
+        {
+            return Err(BackendError::FileSystemHasChildren(name));
+        }
+
+        self.file_systems.borrow_mut().remove(&name);
+
+        Ok(())
+    }
+
+    fn mount_file_system(&self, identifier: &FileSystemIdentifier) -> Result<(), BackendError> {
+        self.with_file_system(identifier, |file_system| {
+            if file_system.key_status().is_unavailable() {
+                return Err(BackendError::FileSystemKeyNotLoaded(identifier.to_string()));
+            }
+
+            file_system.mounted = true;
+            Ok(())
+        })
+    }
+
+    fn unmount_all_file_system(
+        &self,
+        identifier: &FileSystemIdentifier,
+    ) -> Result<(), BackendError> {
+        let name = identifier.to_string();
+        let prefix = format!("{}/", name);
+
+        self.with_file_system(identifier, |file_system| {
+            file_system.mounted = false;
+            Ok(())
+        })?;
+
+        for (key, file_system) in self.file_systems.borrow_mut().iter_mut() {
+            if key.starts_with(&prefix) {
+                file_system.mounted = false;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_file_system_property(
+        &self,
+        identifier: &FileSystemIdentifier,
+        property: &FileSystemProperty,
+    ) -> Result<(), BackendError> {
+        self.with_file_system(identifier, |file_system| {
+            file_system
+                .properties
+                .insert(property.name().to_string(), property.value());
+            Ok(())
+        })
+    }
+
+    fn key_status(
+        &self,
+        identifier: &FileSystemIdentifier,
+    ) -> Result<FileSystemKeyStatus, BackendError> {
+        self.with_file_system(identifier, |file_system| Ok(file_system.key_status()))
+    }
+
+    fn load_key(&self, identifier: &FileSystemIdentifier) -> Result<(), BackendError> {
+        self.with_file_system(identifier, |file_system| {
+            if !file_system.encrypted {
+                return Err(BackendError::FileSystemNotEncrypted(identifier.to_string()));
+            }
+
+            file_system.key_loaded = true;
+            Ok(())
+        })
+    }
+
+    fn unload_key(&self, identifier: &FileSystemIdentifier) -> Result<(), BackendError> {
+        self.with_file_system(identifier, |file_system| {
+            if !file_system.encrypted {
+                return Err(BackendError::FileSystemNotEncrypted(identifier.to_string()));
+            }
+
+            if file_system.mounted {
+                return Err(BackendError::FileSystemMounted(identifier.to_string()));
+            }
+
+            file_system.key_loaded = false;
+            Ok(())
+        })
+    }
+
+    fn attach_file_system(
+        &self,
+        identifier: &FileSystemIdentifier,
+        jail_id: i32,
+    ) -> Result<(), BackendError> {
+        self.with_file_system(identifier, |file_system| {
+            if file_system.properties.get("jailed").map(String::as_str) != Some("on") {
+                return Err(BackendError::FileSystemNotJailed(identifier.to_string()));
+            }
+
+            file_system.jail_id = Some(jail_id);
+            Ok(())
+        })
+    }
+
+    fn detach_file_system(
+        &self,
+        identifier: &FileSystemIdentifier,
+        _jail_id: i32,
+    ) -> Result<(), BackendError> {
+        self.with_file_system(identifier, |file_system| {
+            file_system.jail_id = None;
+            Ok(())
+        })
+    }
+
+    fn snapshots(
+        &self,
+        identifier: &FileSystemIdentifier,
+    ) -> Result<Vec<SnapshotIdentifier>, BackendError> {
+        self.with_file_system(identifier, |file_system| {
+            Ok(file_system
+                .snapshots
+                .iter()
+                .map(|name| SnapshotIdentifier::new(identifier.clone(), name.clone()))
+                .collect())
+        })
+    }
+
+    fn has_snapshot(&self, identifier: &SnapshotIdentifier) -> Result<bool, BackendError> {
+        match self
+            .file_systems
+            .borrow()
+            .get(&identifier.file_system_identifier().to_string())
+        {
+            Some(file_system) => Ok(file_system.snapshots.contains(identifier.name())),
+            None => Ok(false),
+        }
+    }
+
+    fn create_snapshot(&self, identifier: &SnapshotIdentifier) -> Result<(), BackendError> {
+        self.with_file_system(identifier.file_system_identifier(), |file_system| {
+            if file_system.snapshots.contains(identifier.name()) {
+                return Err(BackendError::SnapshotAlreadyExisting(
+                    identifier.to_string(),
+                ));
+            }
+
+            file_system.snapshots.push(identifier.name().clone());
+
+            Ok(())
+        })
+    }
+
+    fn destroy_snapshot(&self, identifier: &SnapshotIdentifier) -> Result<(), BackendError> {
+        self.with_file_system(identifier.file_system_identifier(), |file_system| {
+            let position = Self::snapshot_position(file_system, identifier)?;
+            file_system.snapshots.remove(position);
+
+            Ok(())
+        })
+    }
+
+    fn rollback_snapshot(&self, identifier: &SnapshotIdentifier) -> Result<(), BackendError> {
+        self.with_file_system(identifier.file_system_identifier(), |file_system| {
+            let position = Self::snapshot_position(file_system, identifier)?;
+            file_system.snapshots.truncate(position + 1);
+
+            Ok(())
+        })
+    }
+
+    fn send_snapshot(
+        &self,
+        identifier: &SnapshotIdentifier,
+        base: Option<&SnapshotIdentifier>,
+        file_descriptor: RawFd,
+        options: &SendOptions,
+    ) -> Result<(), BackendError> {
+        let file_system_identifier = identifier.file_system_identifier();
+
+        // Encrypted file systems are decrypted by sends unless they are raw.
+        let encrypted = self.with_file_system(file_system_identifier, |file_system| {
+            let position = Self::snapshot_position(file_system, identifier)?;
+
+            if let Some(base) = base {
+                if Self::snapshot_position(file_system, base)? >= position {
+                    return Err(BackendError::SnapshotNotEarlier(base.to_string()));
+                }
+            }
+
+            match (file_system.key_status(), options.is_raw()) {
+                (FileSystemKeyStatus::None, _) => Ok(false),
+                (_, true) => Ok(true),
+                (FileSystemKeyStatus::Available, false) => Ok(false),
+                (FileSystemKeyStatus::Unavailable, false) => Err(
+                    BackendError::FileSystemKeyNotLoaded(file_system_identifier.to_string()),
+                ),
+            }
+        })?;
+
+        let payload = base.map(|base| base.name().clone()).unwrap_or_default();
+
+        // The descriptor is owned by the caller and must not be closed.
+        let mut file = ManuallyDrop::new(unsafe { File::from_raw_fd(file_descriptor) });
+        file.write_all(&(payload.len() as u64).to_be_bytes())?;
+        file.write_all(payload.as_bytes())?;
+        file.write_all(&[encrypted.into()])?;
+
+        Ok(())
+    }
+
+    fn receive_snapshot(
+        &self,
+        identifier: &SnapshotIdentifier,
+        file_descriptor: RawFd,
+        options: &ReceiveOptions,
+    ) -> Result<(), BackendError> {
+        // The descriptor is owned by the caller and must not be closed.
+        let mut file = ManuallyDrop::new(unsafe { File::from_raw_fd(file_descriptor) });
+
+        let mut length = [0; 8];
+        file.read_exact(&mut length)?;
+        let mut payload = vec![0; u64::from_be_bytes(length) as usize];
+        file.read_exact(&mut payload)?;
+        let base = String::from_utf8(payload)?;
+        let mut encrypted = [0; 1];
+        file.read_exact(&mut encrypted)?;
+
+        let file_system_identifier = identifier.file_system_identifier();
+
+        if base.is_empty() {
+            self.create_file_system(file_system_identifier, &[])?;
+
+            // Raw streams keep file systems encrypted without loading their key.
+            self.with_file_system(file_system_identifier, |file_system| {
+                file_system.encrypted = encrypted[0] != 0;
+                Ok(())
+            })?;
+        } else {
+            self.with_file_system(file_system_identifier, |file_system| {
+                let base = SnapshotIdentifier::new(file_system_identifier.clone(), base);
+                let position = Self::snapshot_position(file_system, &base)?;
+
+                if position + 1 != file_system.snapshots.len() {
+                    if !options.is_force() {
+                        return Err(BackendError::SnapshotNotMostRecent(base.to_string()));
+                    }
+
+                    file_system.snapshots.truncate(position + 1);
+                }
+
+                Ok(())
+            })?;
+        }
+
+        self.with_file_system(file_system_identifier, |file_system| {
+            file_system.snapshots.push(identifier.name().clone());
+            file_system.mounted =
+                !options.is_nomount() && !file_system.key_status().is_unavailable();
+
+            Ok(())
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_system::property::FileSystemEncryption;
+    use std::os::unix::net::UnixStream;
+    use std::os::unix::prelude::AsRawFd;
+
+    fn file_system_identifier(name: &str) -> FileSystemIdentifier {
+        FileSystemIdentifier::new(
+            PoolIdentifier::new(String::from("zroot")),
+            vec![String::from(name)],
+        )
+    }
+
+    fn backend() -> FakeBackend {
+        let backend = FakeBackend::default();
+        backend.add_pool(
+            &PoolIdentifier::new(String::from("zroot")),
+            PoolHealth::Online,
+        );
+
+        backend
+    }
+
+    #[test]
+    fn requires_parent_file_system() {
+        let backend = backend();
+        let identifier = FileSystemIdentifier::new(
+            PoolIdentifier::new(String::from("zroot")),
+            vec![String::from("zones"), String::from("zone")],
+        );
+
+        assert!(matches!(
+            backend.create_file_system(&identifier, &[]),
+            Err(BackendError::FileSystemNotExisting(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_destroying_file_systems_with_snapshots() {
+        let backend = backend();
+        let identifier = file_system_identifier("zone");
+
+        backend.create_file_system(&identifier, &[]).unwrap();
+        backend
+            .create_snapshot(&SnapshotIdentifier::new(
+                identifier.clone(),
+                String::from("first"),
+            ))
+            .unwrap();
+
+        assert!(matches!(
+            backend.destroy_file_system(&identifier),
+            Err(BackendError::FileSystemHasSnapshots(_))
+        ));
+    }
+
+    #[test]
+    fn rolls_back_snapshots() {
+        let backend = backend();
+        let identifier = file_system_identifier("zone");
+
+        backend.create_file_system(&identifier, &[]).unwrap();
+
+        for name in ["first", "second", "third"] {
+            backend
+                .create_snapshot(&SnapshotIdentifier::new(
+                    identifier.clone(),
+                    String::from(name),
+                ))
+                .unwrap();
+        }
+
+        backend
+            .rollback_snapshot(&SnapshotIdentifier::new(
+                identifier.clone(),
+                String::from("first"),
+            ))
+            .unwrap();
+
+        assert_eq!(
+            backend.file_system(&identifier).unwrap().snapshots(),
+            &vec![String::from("first")]
+        );
+    }
+
+    #[test]
+    fn sends_and_receives_incrementally() {
+        let sender = backend();
+        let receiver = backend();
+        let identifier = file_system_identifier("zone");
+        let first = SnapshotIdentifier::new(identifier.clone(), String::from("first"));
+        let second = SnapshotIdentifier::new(identifier.clone(), String::from("second"));
+        let (mut writer, mut reader) = UnixStream::pair().unwrap();
+
+        sender.create_file_system(&identifier, &[]).unwrap();
+        sender.create_snapshot(&first).unwrap();
+        sender.create_snapshot(&second).unwrap();
+
+        sender
+            .send_snapshot(&first, None, writer.as_raw_fd(), &SendOptions::default())
+            .unwrap();
+        receiver
+            .receive_snapshot(&first, reader.as_raw_fd(), &ReceiveOptions::default())
+            .unwrap();
+
+        sender
+            .send_snapshot(
+                &second,
+                Some(&first),
+                writer.as_raw_fd(),
+                &SendOptions::default(),
+            )
+            .unwrap();
+        receiver
+            .receive_snapshot(&second, reader.as_raw_fd(), &ReceiveOptions::default())
+            .unwrap();
+
+        writer.flush().unwrap();
+        drop(writer);
+        assert_eq!(reader.read(&mut [0; 1]).unwrap(), 0);
+
+        assert_eq!(
+            receiver.file_system(&identifier).unwrap().snapshots(),
+            &vec![String::from("first"), String::from("second")]
+        );
+    }
+
+    #[test]
+    fn keeps_encryption_by_raw_sends() {
+        let sender = backend();
+        let receiver = backend();
+        let identifier = file_system_identifier("zone");
+        let snapshot = SnapshotIdentifier::new(identifier.clone(), String::from("first"));
+        let (writer, reader) = UnixStream::pair().unwrap();
+
+        sender
+            .create_file_system(
+                &identifier,
+                &[FileSystemProperty::Encryption(FileSystemEncryption::On)],
+            )
+            .unwrap();
+        sender.create_snapshot(&snapshot).unwrap();
+
+        sender
+            .send_snapshot(
+                &snapshot,
+                None,
+                writer.as_raw_fd(),
+                &SendOptions::default().raw(true),
+            )
+            .unwrap();
+        receiver
+            .receive_snapshot(&snapshot, reader.as_raw_fd(), &ReceiveOptions::default())
+            .unwrap();
+
+        let file_system = receiver.file_system(&identifier).unwrap();
+        assert_eq!(file_system.key_status(), FileSystemKeyStatus::Unavailable);
+        assert!(!file_system.is_mounted());
+
+        assert!(matches!(
+            receiver.mount_file_system(&identifier),
+            Err(BackendError::FileSystemKeyNotLoaded(_))
+        ));
+        receiver.load_key(&identifier).unwrap();
+        receiver.mount_file_system(&identifier).unwrap();
+    }
+}
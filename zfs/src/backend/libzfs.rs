@@ -0,0 +1,195 @@
+use crate::backend::{Backend, BackendError};
+use crate::file_system::identifier::FileSystemIdentifier;
+use crate::file_system::property::{FileSystemKeyStatus, FileSystemProperty};
+use crate::file_system::FileSystem;
+use crate::pool::error::ReadPoolHealthError;
+use crate::pool::identifier::PoolIdentifier;
+use crate::pool::{Pool, PoolHealth};
+use crate::snapshot::identifier::SnapshotIdentifier;
+use crate::snapshot::options::{ReceiveOptions, SendOptions};
+use crate::snapshot::Snapshot;
+use std::os::unix::prelude::RawFd;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The backend operating on the pools of the system by libzfs.
+#[derive(Debug, Default)]
+pub struct LibzfsBackend;
+
+impl LibzfsBackend {
+    fn open_file_system(
+        &self,
+        identifier: &FileSystemIdentifier,
+    ) -> Result<FileSystem, BackendError> {
+        FileSystem::open(identifier)?
+            .ok_or_else(|| BackendError::FileSystemNotExisting(identifier.to_string()))
+    }
+
+    fn open_snapshot(&self, identifier: &SnapshotIdentifier) -> Result<Snapshot, BackendError> {
+        Snapshot::open(identifier)?
+            .ok_or_else(|| BackendError::SnapshotNotExisting(identifier.to_string()))
+    }
+}
+
+impl Backend for LibzfsBackend {
+    fn pool_health(&self, identifier: &PoolIdentifier) -> Result<Option<PoolHealth>, BackendError> {
+        let pool = match Pool::open(identifier)? {
+            Some(pool) => pool,
+            None => return Ok(None),
+        };
+
+        match pool.health() {
+            Ok(health) => Ok(Some(health)),
+            Err(ReadPoolHealthError::FromStrPoolHealthError(_)) => {
+                Ok(Some(PoolHealth::Unavailable))
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn pool_capacity(&self, identifier: &PoolIdentifier) -> Result<Option<u64>, BackendError> {
+        Ok(Pool::open(identifier)?.map(|pool| pool.capacity()))
+    }
+
+    fn has_file_system(&self, identifier: &FileSystemIdentifier) -> Result<bool, BackendError> {
+        Ok(FileSystem::open(identifier)?.is_some())
+    }
+
+    fn children(
+        &self,
+        identifier: &FileSystemIdentifier,
+    ) -> Result<Vec<FileSystemIdentifier>, BackendError> {
+        let file_system = self.open_file_system(identifier)?;
+        let mut identifiers = Vec::default();
+
+        for child in file_system.children().iter()? {
+            identifiers.push(child.identifier()?);
+        }
+
+        Ok(identifiers)
+    }
+
+    fn create_file_system(
+        &self,
+        identifier: &FileSystemIdentifier,
+        properties: &[FileSystemProperty],
+    ) -> Result<(), BackendError> {
+        Ok(FileSystem::create_with_properties(identifier, properties)?)
+    }
+
+    fn destroy_file_system(&self, identifier: &FileSystemIdentifier) -> Result<(), BackendError> {
+        Ok(self.open_file_system(identifier)?.destroy()?)
+    }
+
+    fn mount_file_system(&self, identifier: &FileSystemIdentifier) -> Result<(), BackendError> {
+        Ok(self.open_file_system(identifier)?.mount()?)
+    }
+
+    fn unmount_all_file_system(
+        &self,
+        identifier: &FileSystemIdentifier,
+    ) -> Result<(), BackendError> {
+        Ok(self.open_file_system(identifier)?.unmount_all()?)
+    }
+
+    fn set_file_system_property(
+        &self,
+        identifier: &FileSystemIdentifier,
+        property: &FileSystemProperty,
+    ) -> Result<(), BackendError> {
+        Ok(self
+            .open_file_system(identifier)?
+            .properties_mut()
+            .set(property)?)
+    }
+
+    fn key_status(
+        &self,
+        identifier: &FileSystemIdentifier,
+    ) -> Result<FileSystemKeyStatus, BackendError> {
+        Ok(self
+            .open_file_system(identifier)?
+            .properties()
+            .key_status()?)
+    }
+
+    fn load_key(&self, identifier: &FileSystemIdentifier) -> Result<(), BackendError> {
+        Ok(self.open_file_system(identifier)?.load_key()?)
+    }
+
+    fn unload_key(&self, identifier: &FileSystemIdentifier) -> Result<(), BackendError> {
+        Ok(self.open_file_system(identifier)?.unload_key()?)
+    }
+
+    fn attach_file_system(
+        &self,
+        identifier: &FileSystemIdentifier,
+        jail_id: i32,
+    ) -> Result<(), BackendError> {
+        Ok(self.open_file_system(identifier)?.attach(jail_id)?)
+    }
+
+    fn detach_file_system(
+        &self,
+        identifier: &FileSystemIdentifier,
+        jail_id: i32,
+    ) -> Result<(), BackendError> {
+        Ok(self.open_file_system(identifier)?.detach(jail_id)?)
+    }
+
+    fn snapshots(
+        &self,
+        identifier: &FileSystemIdentifier,
+    ) -> Result<Vec<SnapshotIdentifier>, BackendError> {
+        let file_system = self.open_file_system(identifier)?;
+        let mut identifiers = Vec::default();
+
+        for snapshot in file_system.snapshots().iter()? {
+            identifiers.push(snapshot.identifier()?);
+        }
+
+        Ok(identifiers)
+    }
+
+    fn has_snapshot(&self, identifier: &SnapshotIdentifier) -> Result<bool, BackendError> {
+        Ok(Snapshot::open(identifier)?.is_some())
+    }
+
+    fn create_snapshot(&self, identifier: &SnapshotIdentifier) -> Result<(), BackendError> {
+        Ok(Snapshot::create(identifier)?)
+    }
+
+    fn destroy_snapshot(&self, identifier: &SnapshotIdentifier) -> Result<(), BackendError> {
+        Ok(self.open_snapshot(identifier)?.destroy()?)
+    }
+
+    fn rollback_snapshot(&self, identifier: &SnapshotIdentifier) -> Result<(), BackendError> {
+        Ok(self.open_snapshot(identifier)?.rollback(false)?)
+    }
+
+    fn send_snapshot(
+        &self,
+        identifier: &SnapshotIdentifier,
+        base: Option<&SnapshotIdentifier>,
+        file_descriptor: RawFd,
+        options: &SendOptions,
+    ) -> Result<(), BackendError> {
+        let mut snapshot = self.open_snapshot(identifier)?;
+
+        match base {
+            Some(base) => snapshot.send_incremental(base, file_descriptor, options)?,
+            None => snapshot.send(file_descriptor, options)?,
+        };
+
+        Ok(())
+    }
+
+    fn receive_snapshot(
+        &self,
+        identifier: &SnapshotIdentifier,
+        file_descriptor: RawFd,
+        options: &ReceiveOptions,
+    ) -> Result<(), BackendError> {
+        Ok(Snapshot::receive(identifier, file_descriptor, options)?)
+    }
+}
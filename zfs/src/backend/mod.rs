@@ -0,0 +1,285 @@
+#[cfg(feature = "fake")]
+mod fake;
+#[cfg(feature = "libzfs")]
+mod libzfs;
+#[cfg(not(feature = "libzfs"))]
+mod unavailable;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(feature = "fake")]
+pub use fake::*;
+#[cfg(feature = "libzfs")]
+pub use libzfs::*;
+#[cfg(not(feature = "libzfs"))]
+pub use unavailable::*;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(not(any(feature = "libzfs", feature = "fake")))]
+compile_error!("Either the libzfs or the fake feature must be enabled");
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+use crate::file_system::error::{
+    AttachFileSystemError, CreateFileSystemError, DestroyFileSystemError, DetachFileSystemError,
+    LoadFileSystemKeyError, MountFileSystemError, OpenFileSystemChildIteratorError,
+    OpenFileSystemError, OpenFileSystemSnapshotIteratorError, ReadFileSystemIdentifierError,
+    ReadFileSystemPropertyError, UnloadFileSystemKeyError, UnmountAllFileSystemError,
+    WriteFileSystemPropertyError,
+};
+use crate::file_system::identifier::FileSystemIdentifier;
+use crate::file_system::property::{FileSystemKeyStatus, FileSystemProperty};
+use crate::file_system::DEFAULT_RANDOM_NAME_LENGTH;
+use crate::pool::error::{OpenPoolError, ReadPoolHealthError};
+use crate::pool::identifier::PoolIdentifier;
+use crate::pool::PoolHealth;
+use crate::snapshot::error::{
+    CreateSnapshotError, DestroySnapshotError, OpenSnapshotError, ReadSnapshotIdentifierError,
+    ReceiveSnapshotError, RollbackSnapshotError, SendSnapshotError,
+};
+use crate::snapshot::identifier::SnapshotIdentifier;
+use crate::snapshot::options::{ReceiveOptions, SendOptions};
+use rand::distributions::{Alphanumeric, DistString};
+use rand::thread_rng;
+use std::cell::RefCell;
+use std::io;
+use std::os::unix::prelude::RawFd;
+use std::rc::Rc;
+use std::string::FromUtf8Error;
+use ztd::{Display, Error, From};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum BackendError {
+    #[Display("ZFS is unavailable without libzfs")]
+    Unavailable,
+    #[Display("File system {value} does not exist")]
+    #[From(skip)]
+    FileSystemNotExisting(String),
+    #[Display("File system {value} is already existing")]
+    #[From(skip)]
+    FileSystemAlreadyExisting(String),
+    #[Display("File system {value} has snapshots")]
+    #[From(skip)]
+    FileSystemHasSnapshots(String),
+    #[Display("File system {value} has children")]
+    #[From(skip)]
+    FileSystemHasChildren(String),
+    #[Display("File system {value} is mounted")]
+    #[From(skip)]
+    FileSystemMounted(String),
+    #[Display("File system {value} is not encrypted")]
+    #[From(skip)]
+    FileSystemNotEncrypted(String),
+    #[Display("Key of file system {value} is not loaded")]
+    #[From(skip)]
+    FileSystemKeyNotLoaded(String),
+    #[Display("File system {value} is not jailed")]
+    #[From(skip)]
+    FileSystemNotJailed(String),
+    #[Display("Snapshot {value} does not exist")]
+    #[From(skip)]
+    SnapshotNotExisting(String),
+    #[Display("Snapshot {value} is already existing")]
+    #[From(skip)]
+    SnapshotAlreadyExisting(String),
+    #[Display("Snapshot {value} is not the most recent one")]
+    #[From(skip)]
+    SnapshotNotMostRecent(String),
+    #[Display("Snapshot {value} is not an earlier one")]
+    #[From(skip)]
+    SnapshotNotEarlier(String),
+    IOError(io::Error),
+    FromUtf8Error(FromUtf8Error),
+    OpenPoolError(OpenPoolError),
+    ReadPoolHealthError(ReadPoolHealthError),
+    OpenFileSystemError(OpenFileSystemError),
+    CreateFileSystemError(CreateFileSystemError),
+    DestroyFileSystemError(DestroyFileSystemError),
+    MountFileSystemError(MountFileSystemError),
+    UnmountAllFileSystemError(UnmountAllFileSystemError),
+    WriteFileSystemPropertyError(WriteFileSystemPropertyError),
+    ReadFileSystemPropertyError(ReadFileSystemPropertyError),
+    LoadFileSystemKeyError(LoadFileSystemKeyError),
+    UnloadFileSystemKeyError(UnloadFileSystemKeyError),
+    AttachFileSystemError(AttachFileSystemError),
+    DetachFileSystemError(DetachFileSystemError),
+    ReadFileSystemIdentifierError(ReadFileSystemIdentifierError),
+    OpenFileSystemChildIteratorError(OpenFileSystemChildIteratorError),
+    OpenFileSystemSnapshotIteratorError(OpenFileSystemSnapshotIteratorError),
+    ReadSnapshotIdentifierError(ReadSnapshotIdentifierError),
+    OpenSnapshotError(OpenSnapshotError),
+    CreateSnapshotError(CreateSnapshotError),
+    DestroySnapshotError(DestroySnapshotError),
+    RollbackSnapshotError(RollbackSnapshotError),
+    SendSnapshotError(SendSnapshotError),
+    ReceiveSnapshotError(ReceiveSnapshotError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The dataset operations used by zonys, addressed by identifiers instead of open handles, so that
+/// they can be provided by libzfs as well as by an in-memory fake.
+pub trait Backend {
+    /// Returns the health of the pool, or `None` if it does not exist. Pools in states unknown to
+    /// `PoolHealth` are reported as unavailable.
+    fn pool_health(&self, identifier: &PoolIdentifier) -> Result<Option<PoolHealth>, BackendError>;
+
+    /// Returns the allocated space of the pool in percent, or `None` if it does not exist.
+    fn pool_capacity(&self, identifier: &PoolIdentifier) -> Result<Option<u64>, BackendError>;
+
+    fn has_file_system(&self, identifier: &FileSystemIdentifier) -> Result<bool, BackendError>;
+
+    /// Returns the direct children of the file system.
+    fn children(
+        &self,
+        identifier: &FileSystemIdentifier,
+    ) -> Result<Vec<FileSystemIdentifier>, BackendError>;
+
+    /// Creates the file system with the properties already set, which is required for encryption
+    /// properties.
+    fn create_file_system(
+        &self,
+        identifier: &FileSystemIdentifier,
+        properties: &[FileSystemProperty],
+    ) -> Result<(), BackendError>;
+
+    /// Destroys the file system, which must neither have snapshots nor children.
+    fn destroy_file_system(&self, identifier: &FileSystemIdentifier) -> Result<(), BackendError>;
+
+    fn mount_file_system(&self, identifier: &FileSystemIdentifier) -> Result<(), BackendError>;
+
+    /// Unmounts the file system and all of its children.
+    fn unmount_all_file_system(
+        &self,
+        identifier: &FileSystemIdentifier,
+    ) -> Result<(), BackendError>;
+
+    fn set_file_system_property(
+        &self,
+        identifier: &FileSystemIdentifier,
+        property: &FileSystemProperty,
+    ) -> Result<(), BackendError>;
+
+    fn key_status(
+        &self,
+        identifier: &FileSystemIdentifier,
+    ) -> Result<FileSystemKeyStatus, BackendError>;
+
+    fn load_key(&self, identifier: &FileSystemIdentifier) -> Result<(), BackendError>;
+
+    /// Unloads the key of the file system, which must be unmounted.
+    fn unload_key(&self, identifier: &FileSystemIdentifier) -> Result<(), BackendError>;
+
+    /// Attaches the file system to the jail, which requires the file system to be jailed.
+    fn attach_file_system(
+        &self,
+        identifier: &FileSystemIdentifier,
+        jail_id: i32,
+    ) -> Result<(), BackendError>;
+
+    fn detach_file_system(
+        &self,
+        identifier: &FileSystemIdentifier,
+        jail_id: i32,
+    ) -> Result<(), BackendError>;
+
+    /// Returns the snapshots of the file system, from the oldest to the most recent one.
+    fn snapshots(
+        &self,
+        identifier: &FileSystemIdentifier,
+    ) -> Result<Vec<SnapshotIdentifier>, BackendError>;
+
+    fn has_snapshot(&self, identifier: &SnapshotIdentifier) -> Result<bool, BackendError>;
+
+    fn create_snapshot(&self, identifier: &SnapshotIdentifier) -> Result<(), BackendError>;
+
+    fn destroy_snapshot(&self, identifier: &SnapshotIdentifier) -> Result<(), BackendError>;
+
+    /// Rolls the file system back to the snapshot and destroys more recent snapshots.
+    fn rollback_snapshot(&self, identifier: &SnapshotIdentifier) -> Result<(), BackendError>;
+
+    /// Sends the snapshot, or the changes since `base` if given.
+    fn send_snapshot(
+        &self,
+        identifier: &SnapshotIdentifier,
+        base: Option<&SnapshotIdentifier>,
+        file_descriptor: RawFd,
+        options: &SendOptions,
+    ) -> Result<(), BackendError>;
+
+    fn receive_snapshot(
+        &self,
+        identifier: &SnapshotIdentifier,
+        file_descriptor: RawFd,
+        options: &ReceiveOptions,
+    ) -> Result<(), BackendError>;
+
+    /// Sends the current state of the file system by a temporary snapshot.
+    fn send_file_system(
+        &self,
+        identifier: &FileSystemIdentifier,
+        file_descriptor: RawFd,
+        options: &SendOptions,
+    ) -> Result<(), BackendError> {
+        let snapshot_identifier = SnapshotIdentifier::new(
+            identifier.clone(),
+            Alphanumeric.sample_string(&mut thread_rng(), DEFAULT_RANDOM_NAME_LENGTH),
+        );
+
+        self.create_snapshot(&snapshot_identifier)?;
+        let result = self.send_snapshot(&snapshot_identifier, None, file_descriptor, options);
+        self.destroy_snapshot(&snapshot_identifier)?;
+
+        result
+    }
+
+    /// Receives a file system without keeping the snapshot it was sent by.
+    fn receive_file_system(
+        &self,
+        identifier: &FileSystemIdentifier,
+        file_descriptor: RawFd,
+        options: &ReceiveOptions,
+    ) -> Result<(), BackendError> {
+        let snapshot_identifier = SnapshotIdentifier::new(
+            identifier.clone(),
+            Alphanumeric.sample_string(&mut thread_rng(), DEFAULT_RANDOM_NAME_LENGTH),
+        );
+
+        self.receive_snapshot(&snapshot_identifier, file_descriptor, options)?;
+        self.destroy_snapshot(&snapshot_identifier)?;
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(feature = "libzfs")]
+fn default_backend() -> Rc<dyn Backend> {
+    Rc::new(LibzfsBackend::default())
+}
+
+#[cfg(not(feature = "libzfs"))]
+fn default_backend() -> Rc<dyn Backend> {
+    Rc::new(UnavailableBackend)
+}
+
+thread_local! {
+    static BACKEND: RefCell<Rc<dyn Backend>> = RefCell::new(default_backend());
+}
+
+/// Returns the backend of the current thread, which is libzfs unless it has been replaced. Builds
+/// without libzfs fail every operation until a backend is set by `replace`.
+pub fn current() -> Rc<dyn Backend> {
+    BACKEND.with(|backend| backend.borrow().clone())
+}
+
+/// Replaces the backend of the current thread. Every test runs on its own thread, so tests may
+/// replace the backend without affecting each other.
+pub fn replace(backend: Rc<dyn Backend>) {
+    BACKEND.with(|current| *current.borrow_mut() = backend)
+}
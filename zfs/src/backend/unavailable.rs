@@ -0,0 +1,143 @@
+use crate::backend::{Backend, BackendError};
+use crate::file_system::identifier::FileSystemIdentifier;
+use crate::file_system::property::{FileSystemKeyStatus, FileSystemProperty};
+use crate::pool::identifier::PoolIdentifier;
+use crate::pool::PoolHealth;
+use crate::snapshot::identifier::SnapshotIdentifier;
+use crate::snapshot::options::{ReceiveOptions, SendOptions};
+use std::os::unix::prelude::RawFd;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The backend of builds without libzfs, which fails every operation instead of pretending to
+/// succeed. Tests replace it by a `FakeBackend` explicitly.
+#[derive(Debug, Default)]
+pub struct UnavailableBackend;
+
+impl Backend for UnavailableBackend {
+    fn pool_health(
+        &self,
+        _identifier: &PoolIdentifier,
+    ) -> Result<Option<PoolHealth>, BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn pool_capacity(&self, _identifier: &PoolIdentifier) -> Result<Option<u64>, BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn has_file_system(&self, _identifier: &FileSystemIdentifier) -> Result<bool, BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn children(
+        &self,
+        _identifier: &FileSystemIdentifier,
+    ) -> Result<Vec<FileSystemIdentifier>, BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn create_file_system(
+        &self,
+        _identifier: &FileSystemIdentifier,
+        _properties: &[FileSystemProperty],
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn destroy_file_system(&self, _identifier: &FileSystemIdentifier) -> Result<(), BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn mount_file_system(&self, _identifier: &FileSystemIdentifier) -> Result<(), BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn unmount_all_file_system(
+        &self,
+        _identifier: &FileSystemIdentifier,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn set_file_system_property(
+        &self,
+        _identifier: &FileSystemIdentifier,
+        _property: &FileSystemProperty,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn key_status(
+        &self,
+        _identifier: &FileSystemIdentifier,
+    ) -> Result<FileSystemKeyStatus, BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn load_key(&self, _identifier: &FileSystemIdentifier) -> Result<(), BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn unload_key(&self, _identifier: &FileSystemIdentifier) -> Result<(), BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn attach_file_system(
+        &self,
+        _identifier: &FileSystemIdentifier,
+        _jail_id: i32,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn detach_file_system(
+        &self,
+        _identifier: &FileSystemIdentifier,
+        _jail_id: i32,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn snapshots(
+        &self,
+        _identifier: &FileSystemIdentifier,
+    ) -> Result<Vec<SnapshotIdentifier>, BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn has_snapshot(&self, _identifier: &SnapshotIdentifier) -> Result<bool, BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn create_snapshot(&self, _identifier: &SnapshotIdentifier) -> Result<(), BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn destroy_snapshot(&self, _identifier: &SnapshotIdentifier) -> Result<(), BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn rollback_snapshot(&self, _identifier: &SnapshotIdentifier) -> Result<(), BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn send_snapshot(
+        &self,
+        _identifier: &SnapshotIdentifier,
+        _base: Option<&SnapshotIdentifier>,
+        _file_descriptor: RawFd,
+        _options: &SendOptions,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unavailable)
+    }
+
+    fn receive_snapshot(
+        &self,
+        _identifier: &SnapshotIdentifier,
+        _file_descriptor: RawFd,
+        _options: &ReceiveOptions,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unavailable)
+    }
+}
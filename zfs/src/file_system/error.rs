@@ -4,7 +4,8 @@ use crate::snapshot::error::{
 };
 use crate::{TryIntoZfsError, ZfsError};
 use std::ffi::NulError;
-use std::num::TryFromIntError;
+use std::io;
+use std::num::{ParseIntError, TryFromIntError};
 use std::str::Utf8Error;
 use ztd::{Display, Error, From};
 
@@ -27,6 +28,7 @@ pub enum CreateFileSystemError {
     TryIntoZfsError(TryIntoZfsError),
     ZfsError(ZfsError),
     NulError(NulError),
+    IOError(io::Error),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -157,3 +159,112 @@ pub enum OpenFileSystemSnapshotError {
     ReadFileSystemIdentifierError(ReadFileSystemIdentifierError),
     OpenSnapshotError(OpenSnapshotError),
 }
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum FromStrFileSystemCompressionError {
+    #[Display("Compression {value} is unknown")]
+    #[From(skip)]
+    UnknownCompression(String),
+    ParseIntError(ParseIntError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum FromStrFileSystemEncryptionError {
+    #[Display("Encryption {value} is unknown")]
+    #[From(skip)]
+    UnknownEncryption(String),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum FromStrFileSystemKeyFormatError {
+    #[Display("Key format {value} is unknown")]
+    #[From(skip)]
+    UnknownKeyFormat(String),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum FromStrFileSystemKeyStatusError {
+    #[Display("Key status {value} is unknown")]
+    #[From(skip)]
+    UnknownKeyStatus(String),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum ReadFileSystemPropertyError {
+    TryIntoZfsError(TryIntoZfsError),
+    ZfsError(ZfsError),
+    Utf8Error(Utf8Error),
+    FromStrFileSystemCompressionError(FromStrFileSystemCompressionError),
+    FromStrFileSystemEncryptionError(FromStrFileSystemEncryptionError),
+    FromStrFileSystemKeyStatusError(FromStrFileSystemKeyStatusError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum WriteFileSystemPropertyError {
+    TryIntoZfsError(TryIntoZfsError),
+    ZfsError(ZfsError),
+    NulError(NulError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum PromoteFileSystemError {
+    TryIntoZfsError(TryIntoZfsError),
+    ZfsError(ZfsError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum LoadFileSystemKeyError {
+    TryIntoZfsError(TryIntoZfsError),
+    ZfsError(ZfsError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum UnloadFileSystemKeyError {
+    TryIntoZfsError(TryIntoZfsError),
+    ZfsError(ZfsError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum AttachFileSystemError {
+    TryIntoZfsError(TryIntoZfsError),
+    ZfsError(ZfsError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum DetachFileSystemError {
+    TryIntoZfsError(TryIntoZfsError),
+    ZfsError(ZfsError),
+}
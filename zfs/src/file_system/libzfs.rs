@@ -0,0 +1,613 @@
+use crate::file_system::error::{
+    AttachFileSystemError, CreateFileSystemError, CreateFileSystemSnapshotError,
+    DestroyFileSystemError, DetachFileSystemError, LoadFileSystemKeyError, MountFileSystemError,
+    OpenFileSystemChildError, OpenFileSystemChildIteratorError, OpenFileSystemError,
+    OpenFileSystemSnapshotError, OpenFileSystemSnapshotIteratorError, PromoteFileSystemError,
+    ReadFileSystemIdentifierError, ReadFileSystemPropertyError, ReceiveFileSystemError,
+    SendFileSystemError, UnloadFileSystemKeyError, UnmountAllFileSystemError,
+    UnmountFileSystemError, WriteFileSystemPropertyError,
+};
+use crate::file_system::identifier::{FileSystemIdentifier, FileSystemIdentifierComponent};
+use crate::file_system::iterator::{ChildFileSystemIterator, FileSystemSnapshotIterator};
+use crate::file_system::property::{
+    FileSystemCompression, FileSystemEncryption, FileSystemKeyStatus, FileSystemProperty,
+};
+use crate::file_system::DEFAULT_RANDOM_NAME_LENGTH;
+use crate::snapshot::identifier::{SnapshotIdentifier, SnapshotIdentifierName};
+use crate::snapshot::options::{ReceiveOptions, SendOptions};
+use crate::snapshot::Snapshot;
+use crate::{ZfsError, ZFS};
+use rand::distributions::{Alphanumeric, DistString};
+use rand::thread_rng;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::io;
+use std::os::unix::prelude::RawFd;
+use std::ptr::{null, null_mut};
+use std::str::FromStr;
+use zfs_sys::{
+    nvlist_add_string, nvlist_alloc, nvlist_free, nvlist_t, zfs_close, zfs_create,
+    zfs_crypto_load_key, zfs_crypto_unload_key, zfs_destroy, zfs_get_name, zfs_handle_t,
+    zfs_is_mounted, zfs_iter_children, zfs_iter_snapshots, zfs_jail, zfs_mount, zfs_open,
+    zfs_promote, zfs_prop_get, zfs_prop_get_int, zfs_prop_set, zfs_prop_t,
+    zfs_prop_t_ZFS_PROP_ATIME, zfs_prop_t_ZFS_PROP_AVAILABLE, zfs_prop_t_ZFS_PROP_COMPRESSION,
+    zfs_prop_t_ZFS_PROP_ENCRYPTION, zfs_prop_t_ZFS_PROP_JAILED, zfs_prop_t_ZFS_PROP_KEYSTATUS,
+    zfs_prop_t_ZFS_PROP_QUOTA, zfs_prop_t_ZFS_PROP_RECORDSIZE, zfs_prop_t_ZFS_PROP_REFQUOTA,
+    zfs_prop_t_ZFS_PROP_RESERVATION, zfs_prop_t_ZFS_PROP_USED, zfs_type_t_ZFS_TYPE_FILESYSTEM,
+    zfs_unmount, zfs_unmountall, NV_UNIQUE_NAME,
+};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The maximum length of a property value including the terminating nul, as `ZFS_MAXPROPLEN`.
+pub const MAXIMUM_PROPERTY_VALUE_LENGTH: usize = 1024;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub enum FileSystemMountStatus {
+    Mounted,
+    Unmounted,
+}
+
+impl FileSystemMountStatus {
+    pub fn is_mounted(&self) -> bool {
+        match self {
+            Self::Mounted => true,
+            Self::Unmounted => false,
+        }
+    }
+
+    pub fn is_unmounted(&self) -> bool {
+        !self.is_mounted()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub struct FileSystem {
+    handle: *mut zfs_handle_t,
+}
+
+impl Drop for FileSystem {
+    fn drop(&mut self) {
+        unsafe { zfs_close(self.handle) }
+    }
+}
+
+impl FileSystem {
+    pub(crate) fn new(handle: *mut zfs_handle_t) -> Self {
+        Self { handle }
+    }
+
+    pub(crate) fn handle(&self) -> *mut zfs_handle_t {
+        self.handle
+    }
+}
+
+impl FileSystem {
+    pub fn children(&self) -> FileSystemChildren<&'_ Self> {
+        FileSystemChildren::new(self)
+    }
+
+    pub fn children_mut(&mut self) -> FileSystemChildren<&'_ mut Self> {
+        FileSystemChildren::new(self)
+    }
+
+    pub fn snapshots(&self) -> FileSystemSnapshots<&'_ Self> {
+        FileSystemSnapshots::new(self)
+    }
+
+    pub fn snapshots_mut(&mut self) -> FileSystemSnapshots<&'_ mut Self> {
+        FileSystemSnapshots::new(self)
+    }
+
+    pub fn properties(&self) -> FileSystemProperties<&'_ Self> {
+        FileSystemProperties::new(self)
+    }
+
+    pub fn properties_mut(&mut self) -> FileSystemProperties<&'_ mut Self> {
+        FileSystemProperties::new(self)
+    }
+}
+
+impl FileSystem {
+    pub fn identifier(&self) -> Result<FileSystemIdentifier, ReadFileSystemIdentifierError> {
+        let result = unsafe { CStr::from_ptr(zfs_get_name(self.handle)).to_str()? };
+
+        Ok(FileSystemIdentifier::from_str(result)?)
+    }
+
+    pub fn mount_status(&self) -> FileSystemMountStatus {
+        let result = unsafe { zfs_is_mounted(self.handle, null_mut()) };
+
+        if result == 0 {
+            return FileSystemMountStatus::Unmounted;
+        }
+
+        FileSystemMountStatus::Mounted
+    }
+}
+
+impl FileSystem {
+    pub fn mount(&mut self) -> Result<(), MountFileSystemError> {
+        let result = unsafe { zfs_mount(self.handle, null_mut(), 0) };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn unmount(&mut self) -> Result<(), UnmountFileSystemError> {
+        let result = unsafe { zfs_unmount(self.handle, null_mut(), 0) };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn unmount_all(&mut self) -> Result<(), UnmountAllFileSystemError> {
+        let result = unsafe { zfs_unmountall(self.handle, 0) };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(())
+    }
+
+    /// Makes this clone independent of its origin snapshot. The origin file system becomes a clone
+    /// of this file system afterwards.
+    pub fn promote(&mut self) -> Result<(), PromoteFileSystemError> {
+        let result = unsafe { zfs_promote(self.handle) };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(())
+    }
+
+    /// Loads the key of the encrypted file system from its key location.
+    pub fn load_key(&mut self) -> Result<(), LoadFileSystemKeyError> {
+        let result = unsafe { zfs_crypto_load_key(self.handle, 0, null()) };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(())
+    }
+
+    /// Unloads the key of the encrypted file system, which must be unmounted.
+    pub fn unload_key(&mut self) -> Result<(), UnloadFileSystemKeyError> {
+        let result = unsafe { zfs_crypto_unload_key(self.handle) };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(())
+    }
+
+    /// Attaches the file system to the jail, which requires the file system to be jailed.
+    pub fn attach(&mut self, jail_id: i32) -> Result<(), AttachFileSystemError> {
+        let result = unsafe { zfs_jail(self.handle, jail_id, 1) };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn detach(&mut self, jail_id: i32) -> Result<(), DetachFileSystemError> {
+        let result = unsafe { zfs_jail(self.handle, jail_id, 0) };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn destroy(self) -> Result<(), DestroyFileSystemError> {
+        let result = unsafe { zfs_destroy(self.handle, 0) };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn create(identifier: &FileSystemIdentifier) -> Result<(), CreateFileSystemError> {
+        Self::create_with_properties(identifier, &[])
+    }
+
+    /// Creates the file system with the properties already set, which is the only way to set
+    /// properties like the encryption.
+    pub fn create_with_properties(
+        identifier: &FileSystemIdentifier,
+        properties: &[FileSystemProperty],
+    ) -> Result<(), CreateFileSystemError> {
+        let mut list: *mut nvlist_t = null_mut();
+
+        let result = unsafe { nvlist_alloc(&mut list, NV_UNIQUE_NAME, 0) };
+
+        if result != 0 {
+            return Err(io::Error::from_raw_os_error(result).into());
+        }
+
+        let result = Self::create_with_list(identifier, properties, list);
+
+        unsafe { nvlist_free(list) };
+
+        result
+    }
+
+    fn create_with_list(
+        identifier: &FileSystemIdentifier,
+        properties: &[FileSystemProperty],
+        list: *mut nvlist_t,
+    ) -> Result<(), CreateFileSystemError> {
+        for property in properties {
+            let name = CString::new(property.name())?;
+            let value = CString::new(property.value())?;
+
+            let result = unsafe { nvlist_add_string(list, name.as_ptr(), value.as_ptr()) };
+
+            if result != 0 {
+                return Err(io::Error::from_raw_os_error(result).into());
+            }
+        }
+
+        ZFS.with::<_, Result<_, CreateFileSystemError>>(|zfs| {
+            let string = CString::new(identifier.to_string())?;
+
+            let result =
+                unsafe { zfs_create(**zfs, string.as_ptr(), zfs_type_t_ZFS_TYPE_FILESYSTEM, list) };
+
+            if result != 0 {
+                return Err(ZfsError::try_from(())?.into());
+            }
+
+            Ok(())
+        })
+    }
+
+    pub fn open(identifier: &FileSystemIdentifier) -> Result<Option<Self>, OpenFileSystemError> {
+        ZFS.with::<_, Result<_, OpenFileSystemError>>(|zfs| {
+            let string = CString::new(identifier.to_string())?;
+
+            let result = unsafe {
+                zfs_open(
+                    **zfs,
+                    string.as_ptr(),
+                    zfs_type_t_ZFS_TYPE_FILESYSTEM.try_into()?,
+                )
+            };
+
+            if result.is_null() {
+                return Ok(None);
+            }
+
+            Ok(Some(Self::new(result)))
+        })
+    }
+
+    pub fn send(
+        &mut self,
+        file_descriptor: RawFd,
+        options: &SendOptions,
+    ) -> Result<(), SendFileSystemError> {
+        let name = Alphanumeric.sample_string(&mut thread_rng(), DEFAULT_RANDOM_NAME_LENGTH);
+
+        self.snapshots_mut().create(name.clone())?;
+        let mut snapshot = self
+            .snapshots()
+            .open(name)?
+            .ok_or(SendFileSystemError::SnapshotMissing)?;
+        let result = snapshot.send(file_descriptor, options);
+
+        snapshot.destroy()?;
+
+        Ok(result?)
+    }
+
+    pub fn receive(
+        file_system_identifier: FileSystemIdentifier,
+        file_descriptor: RawFd,
+        options: &ReceiveOptions,
+    ) -> Result<(), ReceiveFileSystemError> {
+        let snapshot_identifier = SnapshotIdentifier::new(
+            file_system_identifier,
+            Alphanumeric.sample_string(&mut thread_rng(), DEFAULT_RANDOM_NAME_LENGTH),
+        );
+
+        Snapshot::receive(&snapshot_identifier, file_descriptor, options)?;
+        let snapshot =
+            Snapshot::open(&snapshot_identifier)?.ok_or(ReceiveFileSystemError::SnapshotMissing)?;
+
+        snapshot.destroy()?;
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub struct FileSystemChildren<T> {
+    file_system: T,
+}
+
+impl<T> FileSystemChildren<T> {
+    fn new(file_system: T) -> Self {
+        Self { file_system }
+    }
+}
+
+impl<'a> FileSystemChildren<&'a FileSystem> {
+    pub fn iter(&self) -> Result<ChildFileSystemIterator, OpenFileSystemChildIteratorError> {
+        struct Data {
+            file_systems: Vec<FileSystem>,
+        }
+
+        extern "C" fn handler(handle: *mut zfs_handle_t, data: *mut c_void) -> c_int {
+            let data: &mut Data = unsafe { &mut *(data as *mut Data) };
+
+            data.file_systems.push(FileSystem::new(handle));
+
+            0
+        }
+
+        let mut data = Data {
+            file_systems: Vec::default(),
+        };
+
+        let result = unsafe {
+            zfs_iter_children(
+                self.file_system.handle,
+                Some(handler),
+                &mut data as *mut _ as *mut c_void,
+            )
+        };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(ChildFileSystemIterator::new(data.file_systems.into_iter()))
+    }
+
+    pub fn open(
+        &self,
+        name: FileSystemIdentifierComponent,
+    ) -> Result<Option<FileSystem>, OpenFileSystemChildError> {
+        let mut identifier = self.file_system.identifier()?;
+        identifier.components_mut().push(name);
+        FileSystem::open(&identifier).map_err(OpenFileSystemChildError::OpenFileSystemError)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub struct FileSystemSnapshots<T> {
+    file_system: T,
+}
+
+impl<T> FileSystemSnapshots<T> {
+    fn new(file_system: T) -> Self {
+        Self { file_system }
+    }
+}
+
+impl<'a> FileSystemSnapshots<&'a FileSystem> {
+    pub fn iter(&self) -> Result<FileSystemSnapshotIterator, OpenFileSystemSnapshotIteratorError> {
+        struct Data {
+            snapshots: Vec<Snapshot>,
+        }
+
+        extern "C" fn handler(handle: *mut zfs_handle_t, data: *mut c_void) -> c_int {
+            let data: &mut Data = unsafe { &mut *(data as *mut Data) };
+
+            data.snapshots.push(Snapshot::new(handle));
+
+            0
+        }
+
+        let mut data = Data {
+            snapshots: Vec::default(),
+        };
+
+        let result = unsafe {
+            zfs_iter_snapshots(
+                self.file_system.handle,
+                0,
+                Some(handler),
+                &mut data as *mut _ as *mut c_void,
+                0,
+                0,
+            )
+        };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(FileSystemSnapshotIterator::new(data.snapshots.into_iter()))
+    }
+
+    pub fn open(
+        &self,
+        name: SnapshotIdentifierName,
+    ) -> Result<Option<Snapshot>, OpenFileSystemSnapshotError> {
+        let identifier = SnapshotIdentifier::new(self.file_system.identifier()?, name);
+
+        Ok(Snapshot::open(&identifier)?)
+    }
+}
+
+impl<'a> FileSystemSnapshots<&'a mut FileSystem> {
+    pub fn create(
+        &mut self,
+        name: SnapshotIdentifierName,
+    ) -> Result<(), CreateFileSystemSnapshotError> {
+        let identifier = SnapshotIdentifier::new(self.file_system.identifier()?, name);
+
+        Ok(Snapshot::create(&identifier)?)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The properties of a file system. Sizes are in bytes, and a quota or reservation of `None`
+/// means that none is set.
+pub struct FileSystemProperties<T> {
+    file_system: T,
+}
+
+impl<T> FileSystemProperties<T> {
+    fn new(file_system: T) -> Self {
+        Self { file_system }
+    }
+}
+
+impl<'a> FileSystemProperties<&'a FileSystem> {
+    fn get_string(&self, property: zfs_prop_t) -> Result<String, ReadFileSystemPropertyError> {
+        let mut buffer = [0 as c_char; MAXIMUM_PROPERTY_VALUE_LENGTH];
+
+        let result = unsafe {
+            zfs_prop_get(
+                self.file_system.handle,
+                property,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                null_mut(),
+                null_mut(),
+                0,
+                1,
+            )
+        };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(unsafe { CStr::from_ptr(buffer.as_ptr()) }
+            .to_str()?
+            .to_string())
+    }
+
+    fn get_integer(&self, property: zfs_prop_t) -> u64 {
+        unsafe { zfs_prop_get_int(self.file_system.handle, property) }
+    }
+
+    pub fn compression(&self) -> Result<FileSystemCompression, ReadFileSystemPropertyError> {
+        Ok(FileSystemCompression::from_str(
+            &self.get_string(zfs_prop_t_ZFS_PROP_COMPRESSION)?,
+        )?)
+    }
+
+    pub fn encryption(&self) -> Result<FileSystemEncryption, ReadFileSystemPropertyError> {
+        Ok(FileSystemEncryption::from_str(
+            &self.get_string(zfs_prop_t_ZFS_PROP_ENCRYPTION)?,
+        )?)
+    }
+
+    pub fn key_status(&self) -> Result<FileSystemKeyStatus, ReadFileSystemPropertyError> {
+        Ok(FileSystemKeyStatus::from_str(
+            &self.get_string(zfs_prop_t_ZFS_PROP_KEYSTATUS)?,
+        )?)
+    }
+
+    pub fn quota(&self) -> Option<u64> {
+        Some(self.get_integer(zfs_prop_t_ZFS_PROP_QUOTA)).filter(|quota| *quota != 0)
+    }
+
+    pub fn refquota(&self) -> Option<u64> {
+        Some(self.get_integer(zfs_prop_t_ZFS_PROP_REFQUOTA)).filter(|quota| *quota != 0)
+    }
+
+    pub fn reservation(&self) -> Option<u64> {
+        Some(self.get_integer(zfs_prop_t_ZFS_PROP_RESERVATION))
+            .filter(|reservation| *reservation != 0)
+    }
+
+    pub fn record_size(&self) -> u64 {
+        self.get_integer(zfs_prop_t_ZFS_PROP_RECORDSIZE)
+    }
+
+    pub fn atime(&self) -> bool {
+        self.get_integer(zfs_prop_t_ZFS_PROP_ATIME) != 0
+    }
+
+    pub fn jailed(&self) -> bool {
+        self.get_integer(zfs_prop_t_ZFS_PROP_JAILED) != 0
+    }
+
+    pub fn used(&self) -> u64 {
+        self.get_integer(zfs_prop_t_ZFS_PROP_USED)
+    }
+
+    pub fn available(&self) -> u64 {
+        self.get_integer(zfs_prop_t_ZFS_PROP_AVAILABLE)
+    }
+}
+
+impl<'a> FileSystemProperties<&'a mut FileSystem> {
+    pub fn set(
+        &mut self,
+        property: &FileSystemProperty,
+    ) -> Result<(), WriteFileSystemPropertyError> {
+        let name = CString::new(property.name())?;
+        let value = CString::new(property.value())?;
+
+        let result =
+            unsafe { zfs_prop_set(self.file_system.handle, name.as_ptr(), value.as_ptr()) };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn set_compression(
+        &mut self,
+        compression: FileSystemCompression,
+    ) -> Result<(), WriteFileSystemPropertyError> {
+        self.set(&FileSystemProperty::Compression(compression))
+    }
+
+    pub fn set_quota(&mut self, quota: Option<u64>) -> Result<(), WriteFileSystemPropertyError> {
+        self.set(&FileSystemProperty::Quota(quota))
+    }
+
+    pub fn set_refquota(
+        &mut self,
+        refquota: Option<u64>,
+    ) -> Result<(), WriteFileSystemPropertyError> {
+        self.set(&FileSystemProperty::Refquota(refquota))
+    }
+
+    pub fn set_reservation(
+        &mut self,
+        reservation: Option<u64>,
+    ) -> Result<(), WriteFileSystemPropertyError> {
+        self.set(&FileSystemProperty::Reservation(reservation))
+    }
+
+    pub fn set_record_size(
+        &mut self,
+        record_size: u64,
+    ) -> Result<(), WriteFileSystemPropertyError> {
+        self.set(&FileSystemProperty::RecordSize(record_size))
+    }
+
+    pub fn set_atime(&mut self, atime: bool) -> Result<(), WriteFileSystemPropertyError> {
+        self.set(&FileSystemProperty::Atime(atime))
+    }
+}
@@ -0,0 +1,321 @@
+use crate::file_system::error::{
+    FromStrFileSystemCompressionError, FromStrFileSystemEncryptionError,
+    FromStrFileSystemKeyFormatError, FromStrFileSystemKeyStatusError,
+};
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub const FILE_SYSTEM_COMPRESSION_LEVEL_SEPARATOR: &str = "-";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The compression algorithm of a file system. Algorithms supporting levels use their default
+/// level if none is given.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FileSystemCompression {
+    Off,
+    On,
+    Lzjb,
+    Zle,
+    Lz4,
+    Gzip(Option<u8>),
+    Zstd(Option<u8>),
+    ZstdFast(Option<u16>),
+}
+
+impl Display for FileSystemCompression {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Off => write!(formatter, "off"),
+            Self::On => write!(formatter, "on"),
+            Self::Lzjb => write!(formatter, "lzjb"),
+            Self::Zle => write!(formatter, "zle"),
+            Self::Lz4 => write!(formatter, "lz4"),
+            Self::Gzip(None) => write!(formatter, "gzip"),
+            Self::Gzip(Some(level)) => write!(
+                formatter,
+                "gzip{}{}",
+                FILE_SYSTEM_COMPRESSION_LEVEL_SEPARATOR, level
+            ),
+            Self::Zstd(None) => write!(formatter, "zstd"),
+            Self::Zstd(Some(level)) => write!(
+                formatter,
+                "zstd{}{}",
+                FILE_SYSTEM_COMPRESSION_LEVEL_SEPARATOR, level
+            ),
+            Self::ZstdFast(None) => write!(formatter, "zstd-fast"),
+            Self::ZstdFast(Some(level)) => write!(
+                formatter,
+                "zstd-fast{}{}",
+                FILE_SYSTEM_COMPRESSION_LEVEL_SEPARATOR, level
+            ),
+        }
+    }
+}
+
+impl FromStr for FileSystemCompression {
+    type Err = FromStrFileSystemCompressionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => return Ok(Self::Off),
+            "on" => return Ok(Self::On),
+            "lzjb" => return Ok(Self::Lzjb),
+            "zle" => return Ok(Self::Zle),
+            "lz4" => return Ok(Self::Lz4),
+            "gzip" => return Ok(Self::Gzip(None)),
+            "zstd" => return Ok(Self::Zstd(None)),
+            "zstd-fast" => return Ok(Self::ZstdFast(None)),
+            _ => {}
+        };
+
+        let (algorithm, level) = match s.rsplit_once(FILE_SYSTEM_COMPRESSION_LEVEL_SEPARATOR) {
+            Some(split) => split,
+            None => {
+                return Err(FromStrFileSystemCompressionError::UnknownCompression(
+                    s.to_string(),
+                ))
+            }
+        };
+
+        match algorithm {
+            "gzip" => Ok(Self::Gzip(Some(level.parse()?))),
+            "zstd" => Ok(Self::Zstd(Some(level.parse()?))),
+            "zstd-fast" => Ok(Self::ZstdFast(Some(level.parse()?))),
+            _ => Err(FromStrFileSystemCompressionError::UnknownCompression(
+                s.to_string(),
+            )),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The encryption algorithm of a file system, which can only be chosen on creation. `On` uses the
+/// default algorithm of ZFS.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FileSystemEncryption {
+    Off,
+    On,
+    Aes128Ccm,
+    Aes192Ccm,
+    Aes256Ccm,
+    Aes128Gcm,
+    Aes192Gcm,
+    Aes256Gcm,
+}
+
+impl FileSystemEncryption {
+    pub fn is_enabled(&self) -> bool {
+        !matches!(self, Self::Off)
+    }
+}
+
+impl Display for FileSystemEncryption {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Off => write!(formatter, "off"),
+            Self::On => write!(formatter, "on"),
+            Self::Aes128Ccm => write!(formatter, "aes-128-ccm"),
+            Self::Aes192Ccm => write!(formatter, "aes-192-ccm"),
+            Self::Aes256Ccm => write!(formatter, "aes-256-ccm"),
+            Self::Aes128Gcm => write!(formatter, "aes-128-gcm"),
+            Self::Aes192Gcm => write!(formatter, "aes-192-gcm"),
+            Self::Aes256Gcm => write!(formatter, "aes-256-gcm"),
+        }
+    }
+}
+
+impl FromStr for FileSystemEncryption {
+    type Err = FromStrFileSystemEncryptionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "on" => Ok(Self::On),
+            "aes-128-ccm" => Ok(Self::Aes128Ccm),
+            "aes-192-ccm" => Ok(Self::Aes192Ccm),
+            "aes-256-ccm" => Ok(Self::Aes256Ccm),
+            "aes-128-gcm" => Ok(Self::Aes128Gcm),
+            "aes-192-gcm" => Ok(Self::Aes192Gcm),
+            "aes-256-gcm" => Ok(Self::Aes256Gcm),
+            _ => Err(FromStrFileSystemEncryptionError::UnknownEncryption(
+                s.to_string(),
+            )),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FileSystemKeyFormat {
+    Raw,
+    Hex,
+    Passphrase,
+}
+
+impl Display for FileSystemKeyFormat {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Raw => write!(formatter, "raw"),
+            Self::Hex => write!(formatter, "hex"),
+            Self::Passphrase => write!(formatter, "passphrase"),
+        }
+    }
+}
+
+impl FromStr for FileSystemKeyFormat {
+    type Err = FromStrFileSystemKeyFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "raw" => Ok(Self::Raw),
+            "hex" => Ok(Self::Hex),
+            "passphrase" => Ok(Self::Passphrase),
+            _ => Err(FromStrFileSystemKeyFormatError::UnknownKeyFormat(
+                s.to_string(),
+            )),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Whether the key of a file system is loaded. Unencrypted file systems have no key.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FileSystemKeyStatus {
+    None,
+    Available,
+    Unavailable,
+}
+
+impl FileSystemKeyStatus {
+    pub fn is_unavailable(&self) -> bool {
+        matches!(self, Self::Unavailable)
+    }
+}
+
+impl Display for FileSystemKeyStatus {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::None => write!(formatter, "none"),
+            Self::Available => write!(formatter, "available"),
+            Self::Unavailable => write!(formatter, "unavailable"),
+        }
+    }
+}
+
+impl FromStr for FileSystemKeyStatus {
+    type Err = FromStrFileSystemKeyStatusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" | "-" => Ok(Self::None),
+            "available" => Ok(Self::Available),
+            "unavailable" => Ok(Self::Unavailable),
+            _ => Err(FromStrFileSystemKeyStatusError::UnknownKeyStatus(
+                s.to_string(),
+            )),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A settable property of a file system. Sizes are in bytes, and a quota or reservation of `None`
+/// removes it. Encryption properties can only be set on creation. Jailed file systems can be
+/// attached to jails and are not mounted by the host.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum FileSystemProperty {
+    Compression(FileSystemCompression),
+    Quota(Option<u64>),
+    Refquota(Option<u64>),
+    Reservation(Option<u64>),
+    RecordSize(u64),
+    Atime(bool),
+    Encryption(FileSystemEncryption),
+    KeyFormat(FileSystemKeyFormat),
+    KeyLocation(String),
+    Jailed(bool),
+}
+
+impl FileSystemProperty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Compression(_) => "compression",
+            Self::Quota(_) => "quota",
+            Self::Refquota(_) => "refquota",
+            Self::Reservation(_) => "reservation",
+            Self::RecordSize(_) => "recordsize",
+            Self::Atime(_) => "atime",
+            Self::Encryption(_) => "encryption",
+            Self::KeyFormat(_) => "keyformat",
+            Self::KeyLocation(_) => "keylocation",
+            Self::Jailed(_) => "jailed",
+        }
+    }
+
+    pub fn value(&self) -> String {
+        match self {
+            Self::Compression(compression) => compression.to_string(),
+            Self::Quota(size) | Self::Refquota(size) | Self::Reservation(size) => match size {
+                Some(size) => size.to_string(),
+                None => String::from("none"),
+            },
+            Self::RecordSize(size) => size.to_string(),
+            Self::Atime(true) | Self::Jailed(true) => String::from("on"),
+            Self::Atime(false) | Self::Jailed(false) => String::from("off"),
+            Self::Encryption(encryption) => encryption.to_string(),
+            Self::KeyFormat(key_format) => key_format.to_string(),
+            Self::KeyLocation(key_location) => key_location.clone(),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_compressions() {
+        for compression in [
+            FileSystemCompression::Off,
+            FileSystemCompression::Lz4,
+            FileSystemCompression::Gzip(None),
+            FileSystemCompression::Gzip(Some(9)),
+            FileSystemCompression::Zstd(Some(19)),
+            FileSystemCompression::ZstdFast(Some(1000)),
+        ] {
+            assert_eq!(
+                FileSystemCompression::from_str(&compression.to_string()).unwrap(),
+                compression
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips_encryptions() {
+        for encryption in [
+            FileSystemEncryption::Off,
+            FileSystemEncryption::On,
+            FileSystemEncryption::Aes128Ccm,
+            FileSystemEncryption::Aes256Gcm,
+        ] {
+            assert_eq!(
+                FileSystemEncryption::from_str(&encryption.to_string()).unwrap(),
+                encryption
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_compressions() {
+        assert!(FileSystemCompression::from_str("brotli").is_err());
+        assert!(FileSystemCompression::from_str("lz4-1").is_err());
+    }
+}
@@ -2,23 +2,29 @@
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+pub mod backend;
 pub mod file_system;
 pub mod pool;
 pub mod snapshot;
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+#[cfg(feature = "libzfs")]
 use std::ffi::CStr;
+#[cfg(feature = "libzfs")]
 use std::ops::{Deref, DerefMut};
+#[cfg(feature = "libzfs")]
 use zfs_sys::{libzfs_errno, libzfs_error_description, libzfs_fini, libzfs_handle_t, libzfs_init};
 use ztd::{Constructor, Display, Error, Method};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+#[cfg(feature = "libzfs")]
 pub(crate) struct ZfsHandle {
     handle: *mut libzfs_handle_t,
 }
 
+#[cfg(feature = "libzfs")]
 impl ZfsHandle {
     fn new() -> Self {
         let handle = unsafe { libzfs_init() };
@@ -31,12 +37,14 @@ impl ZfsHandle {
     }
 }
 
+#[cfg(feature = "libzfs")]
 impl Drop for ZfsHandle {
     fn drop(&mut self) {
         unsafe { libzfs_fini(self.handle) }
     }
 }
 
+#[cfg(feature = "libzfs")]
 impl Deref for ZfsHandle {
     type Target = *mut libzfs_handle_t;
 
@@ -45,6 +53,7 @@ impl Deref for ZfsHandle {
     }
 }
 
+#[cfg(feature = "libzfs")]
 impl DerefMut for ZfsHandle {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.handle
@@ -53,6 +62,7 @@ impl DerefMut for ZfsHandle {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+#[cfg(feature = "libzfs")]
 thread_local! {
     pub(crate) static ZFS: ZfsHandle = ZfsHandle::new();
 }
@@ -74,6 +84,7 @@ pub struct ZfsError {
     message: String,
 }
 
+#[cfg(feature = "libzfs")]
 impl TryFrom<()> for ZfsError {
     type Error = TryIntoZfsError;
 
@@ -0,0 +1,58 @@
+use crate::{TryIntoZfsError, ZfsError};
+use std::ffi::NulError;
+use std::str::Utf8Error;
+use ztd::{Display, Error, From};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum OpenPoolError {
+    NulError(NulError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum OpenPoolIteratorError {
+    TryIntoZfsError(TryIntoZfsError),
+    ZfsError(ZfsError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum ReadPoolIdentifierError {
+    Utf8Error(Utf8Error),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum FromStrPoolHealthError {
+    #[Display("Pool health {value} is unknown")]
+    #[From(skip)]
+    UnknownHealth(String),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum ReadPoolHealthError {
+    Utf8Error(Utf8Error),
+    FromStrPoolHealthError(FromStrPoolHealthError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum ReadPoolFeaturesError {
+    #[Display("Pool features are not available")]
+    FeaturesNotAvailable,
+    Utf8Error(Utf8Error),
+}
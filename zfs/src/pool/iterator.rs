@@ -0,0 +1,32 @@
+use crate::pool::Pool;
+use std::vec::IntoIter;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub struct AllPoolIterator {
+    iterator: IntoIter<Pool>,
+}
+
+impl AllPoolIterator {
+    pub(super) fn new(iterator: IntoIter<Pool>) -> Self {
+        Self { iterator }
+    }
+
+    pub fn empty() -> Self {
+        Self::new(Vec::new().into_iter())
+    }
+}
+
+impl Iterator for AllPoolIterator {
+    type Item = Pool;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iterator.next()
+    }
+}
+
+impl Default for AllPoolIterator {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
@@ -0,0 +1,142 @@
+use crate::pool::error::{
+    OpenPoolError, OpenPoolIteratorError, ReadPoolFeaturesError, ReadPoolHealthError,
+    ReadPoolIdentifierError,
+};
+use crate::pool::identifier::PoolIdentifier;
+use crate::pool::iterator::AllPoolIterator;
+use crate::pool::{PoolFeature, PoolHealth};
+use crate::{ZfsError, ZFS};
+use std::ffi::{c_int, c_void, CStr, CString};
+use std::ptr::null_mut;
+use std::str::FromStr;
+use zfs_sys::{
+    nvlist_next_nvpair, nvpair_name, nvpair_value_uint64, zpool_close, zpool_get_features,
+    zpool_get_name, zpool_get_prop_int, zpool_get_state_str, zpool_handle_t, zpool_iter,
+    zpool_open_canfail, zpool_prop_t, zpool_prop_t_ZPOOL_PROP_ALLOCATED,
+    zpool_prop_t_ZPOOL_PROP_CAPACITY, zpool_prop_t_ZPOOL_PROP_FREE, zpool_prop_t_ZPOOL_PROP_SIZE,
+};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub struct Pool {
+    handle: *mut zpool_handle_t,
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        unsafe { zpool_close(self.handle) }
+    }
+}
+
+impl Pool {
+    pub(crate) fn new(handle: *mut zpool_handle_t) -> Self {
+        Self { handle }
+    }
+}
+
+impl Pool {
+    pub fn all() -> Result<AllPoolIterator, OpenPoolIteratorError> {
+        struct Data {
+            pools: Vec<Pool>,
+        }
+
+        extern "C" fn handler(handle: *mut zpool_handle_t, data: *mut c_void) -> c_int {
+            let data: &mut Data = unsafe { &mut *(data as *mut Data) };
+
+            data.pools.push(Pool::new(handle));
+
+            0
+        }
+
+        ZFS.with::<_, Result<_, OpenPoolIteratorError>>(|zfs| {
+            let mut data = Data {
+                pools: Vec::default(),
+            };
+
+            let result =
+                unsafe { zpool_iter(**zfs, Some(handler), &mut data as *mut _ as *mut c_void) };
+
+            if result != 0 {
+                return Err(ZfsError::try_from(())?.into());
+            }
+
+            Ok(AllPoolIterator::new(data.pools.into_iter()))
+        })
+    }
+
+    pub fn open(identifier: &PoolIdentifier) -> Result<Option<Self>, OpenPoolError> {
+        ZFS.with::<_, Result<_, OpenPoolError>>(|zfs| {
+            let string = CString::new(identifier.to_string())?;
+
+            let result = unsafe { zpool_open_canfail(**zfs, string.as_ptr()) };
+
+            if result.is_null() {
+                return Ok(None);
+            }
+
+            Ok(Some(Self::new(result)))
+        })
+    }
+}
+
+impl Pool {
+    fn get_integer(&self, property: zpool_prop_t) -> u64 {
+        unsafe { zpool_get_prop_int(self.handle, property, null_mut()) }
+    }
+
+    pub fn identifier(&self) -> Result<PoolIdentifier, ReadPoolIdentifierError> {
+        let result = unsafe { CStr::from_ptr(zpool_get_name(self.handle)).to_str()? };
+
+        Ok(PoolIdentifier::new(result.to_string()))
+    }
+
+    pub fn health(&self) -> Result<PoolHealth, ReadPoolHealthError> {
+        let result = unsafe { CStr::from_ptr(zpool_get_state_str(self.handle)).to_str()? };
+
+        Ok(PoolHealth::from_str(result)?)
+    }
+
+    /// Returns the size of the pool in bytes.
+    pub fn size(&self) -> u64 {
+        self.get_integer(zpool_prop_t_ZPOOL_PROP_SIZE)
+    }
+
+    /// Returns the allocated space of the pool in bytes.
+    pub fn allocated(&self) -> u64 {
+        self.get_integer(zpool_prop_t_ZPOOL_PROP_ALLOCATED)
+    }
+
+    /// Returns the free space of the pool in bytes.
+    pub fn free(&self) -> u64 {
+        self.get_integer(zpool_prop_t_ZPOOL_PROP_FREE)
+    }
+
+    /// Returns the allocated space of the pool in percent.
+    pub fn capacity(&self) -> u64 {
+        self.get_integer(zpool_prop_t_ZPOOL_PROP_CAPACITY)
+    }
+
+    pub fn features(&self) -> Result<Vec<PoolFeature>, ReadPoolFeaturesError> {
+        let list = unsafe { zpool_get_features(self.handle) };
+
+        if list.is_null() {
+            return Err(ReadPoolFeaturesError::FeaturesNotAvailable);
+        }
+
+        let mut features = Vec::default();
+        let mut pair = unsafe { nvlist_next_nvpair(list, null_mut()) };
+
+        while !pair.is_null() {
+            let guid = unsafe { CStr::from_ptr(nvpair_name(pair)).to_str()? };
+
+            let mut reference_count = 0;
+            unsafe { nvpair_value_uint64(pair, &mut reference_count) };
+
+            features.push(PoolFeature::new(guid.to_string(), reference_count));
+
+            pair = unsafe { nvlist_next_nvpair(list, pair) };
+        }
+
+        Ok(features)
+    }
+}
@@ -1 +1,101 @@
+pub mod error;
 pub mod identifier;
+#[cfg(feature = "libzfs")]
+pub mod iterator;
+#[cfg(feature = "libzfs")]
+mod libzfs;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(feature = "libzfs")]
+pub use libzfs::*;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+use crate::pool::error::FromStrPoolHealthError;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PoolHealth {
+    Online,
+    Degraded,
+    Faulted,
+    Offline,
+    Removed,
+    Unavailable,
+    Split,
+    Suspended,
+}
+
+impl PoolHealth {
+    pub fn is_online(&self) -> bool {
+        matches!(self, Self::Online)
+    }
+}
+
+impl Display for PoolHealth {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Online => write!(formatter, "ONLINE"),
+            Self::Degraded => write!(formatter, "DEGRADED"),
+            Self::Faulted => write!(formatter, "FAULTED"),
+            Self::Offline => write!(formatter, "OFFLINE"),
+            Self::Removed => write!(formatter, "REMOVED"),
+            Self::Unavailable => write!(formatter, "UNAVAIL"),
+            Self::Split => write!(formatter, "SPLIT"),
+            Self::Suspended => write!(formatter, "SUSPENDED"),
+        }
+    }
+}
+
+impl FromStr for PoolHealth {
+    type Err = FromStrPoolHealthError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ONLINE" => Ok(Self::Online),
+            "DEGRADED" => Ok(Self::Degraded),
+            "FAULTED" => Ok(Self::Faulted),
+            "OFFLINE" => Ok(Self::Offline),
+            "REMOVED" => Ok(Self::Removed),
+            "UNAVAIL" => Ok(Self::Unavailable),
+            "SPLIT" => Ok(Self::Split),
+            "SUSPENDED" => Ok(Self::Suspended),
+            _ => Err(FromStrPoolHealthError::UnknownHealth(s.to_string())),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A feature enabled on a pool. Features which are enabled but not in use have a reference count
+/// of zero.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PoolFeature {
+    guid: String,
+    reference_count: u64,
+}
+
+impl PoolFeature {
+    pub fn new(guid: String, reference_count: u64) -> Self {
+        Self {
+            guid,
+            reference_count,
+        }
+    }
+
+    pub fn guid(&self) -> &String {
+        &self.guid
+    }
+
+    pub fn reference_count(&self) -> u64 {
+        self.reference_count
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.reference_count != 0
+    }
+}
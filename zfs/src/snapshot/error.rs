@@ -26,9 +26,20 @@ pub enum DestroySnapshotError {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum RollbackSnapshotError {
+    OpenSnapshotFileSystemError(OpenSnapshotFileSystemError),
+    ZfsError(ZfsError),
+    TryIntoZfsError(TryIntoZfsError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug, Display, Error, From)]
 #[From(unnamed)]
 pub enum SendSnapshotError {
+    NulError(NulError),
     ZfsError(ZfsError),
     TryIntoZfsError(TryIntoZfsError),
 }
@@ -83,3 +94,47 @@ pub enum FromStrSnapshotIdentifierError {
     EmptySnapshotName,
     FromStrFileSystemIdentifierError(FromStrFileSystemIdentifierError),
 }
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum CloneSnapshotError {
+    NulError(NulError),
+    ZfsError(ZfsError),
+    TryIntoZfsError(TryIntoZfsError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum HoldSnapshotError {
+    OpenSnapshotFileSystemError(OpenSnapshotFileSystemError),
+    ReadSnapshotIdentifierError(ReadSnapshotIdentifierError),
+    NulError(NulError),
+    ZfsError(ZfsError),
+    TryIntoZfsError(TryIntoZfsError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum ReleaseSnapshotError {
+    OpenSnapshotFileSystemError(OpenSnapshotFileSystemError),
+    ReadSnapshotIdentifierError(ReadSnapshotIdentifierError),
+    NulError(NulError),
+    ZfsError(ZfsError),
+    TryIntoZfsError(TryIntoZfsError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum ReadSnapshotHoldsError {
+    ZfsError(ZfsError),
+    TryIntoZfsError(TryIntoZfsError),
+    Utf8Error(Utf8Error),
+}
@@ -0,0 +1,262 @@
+use crate::file_system::identifier::FileSystemIdentifier;
+use crate::file_system::FileSystem;
+use crate::snapshot::error::{
+    CloneSnapshotError, CreateSnapshotError, DestroySnapshotError, HoldSnapshotError,
+    OpenSnapshotError, OpenSnapshotFileSystemError, ReadSnapshotHoldsError,
+    ReadSnapshotIdentifierError, ReceiveSnapshotError, ReleaseSnapshotError, RollbackSnapshotError,
+    SendSnapshotError,
+};
+use crate::snapshot::identifier::SnapshotIdentifier;
+use crate::snapshot::options::{ReceiveOptions, SendOptions};
+use crate::{ZfsError, ZFS};
+use std::ffi::{CStr, CString};
+use std::os::unix::prelude::RawFd;
+use std::ptr::{null, null_mut};
+use std::str::FromStr;
+use zfs_sys::{
+    nvlist_free, nvlist_next_nvpair, nvlist_t, nvpair_name, zfs_clone, zfs_close, zfs_destroy,
+    zfs_get_holds, zfs_get_name, zfs_handle_t, zfs_hold, zfs_open, zfs_receive, zfs_release,
+    zfs_rollback, zfs_send_one, zfs_snapshot, zfs_type_t_ZFS_TYPE_SNAPSHOT,
+};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub struct Snapshot {
+    handle: *mut zfs_handle_t,
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        unsafe { zfs_close(self.handle) }
+    }
+}
+
+impl Snapshot {
+    pub(crate) fn new(handle: *mut zfs_handle_t) -> Self {
+        Self { handle }
+    }
+}
+
+impl Snapshot {
+    pub fn create(identifier: &SnapshotIdentifier) -> Result<(), CreateSnapshotError> {
+        ZFS.with::<_, Result<_, CreateSnapshotError>>(|zfs| {
+            let string = CString::new(identifier.to_string())?;
+
+            let result = unsafe { zfs_snapshot(**zfs, string.as_ptr(), 0, null_mut()) };
+
+            if result != 0 {
+                return Err(ZfsError::try_from(())?.into());
+            }
+
+            Ok(())
+        })
+    }
+
+    pub fn open(identifier: &SnapshotIdentifier) -> Result<Option<Self>, OpenSnapshotError> {
+        ZFS.with::<_, Result<_, OpenSnapshotError>>(|zfs| {
+            let string = CString::new(identifier.to_string())?;
+
+            let result = unsafe {
+                zfs_open(
+                    **zfs,
+                    string.as_ptr(),
+                    zfs_type_t_ZFS_TYPE_SNAPSHOT.try_into()?,
+                )
+            };
+
+            if result.is_null() {
+                return Ok(None);
+            }
+
+            Ok(Some(Self::new(result)))
+        })
+    }
+}
+
+impl Snapshot {
+    pub fn identifier(&self) -> Result<SnapshotIdentifier, ReadSnapshotIdentifierError> {
+        let result = unsafe { CStr::from_ptr(zfs_get_name(self.handle)).to_str()? };
+
+        Ok(SnapshotIdentifier::from_str(result)?)
+    }
+
+    pub fn file_system(&self) -> Result<FileSystem, OpenSnapshotFileSystemError> {
+        let identifier = self.identifier()?;
+
+        match FileSystem::open(identifier.file_system_identifier())? {
+            None => Err(OpenSnapshotFileSystemError::FileSystemNotExisting),
+            Some(f) => Ok(f),
+        }
+    }
+
+    pub fn destroy(self) -> Result<(), DestroySnapshotError> {
+        let result = unsafe { zfs_destroy(self.handle, 0) };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(())
+    }
+
+    /// Rolls the file system of the snapshot back to it. More recent snapshots of the file system
+    /// are destroyed.
+    pub fn rollback(&self, force: bool) -> Result<(), RollbackSnapshotError> {
+        let file_system = self.file_system()?;
+
+        let result = unsafe { zfs_rollback(file_system.handle(), self.handle, force.into()) };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(())
+    }
+
+    /// Creates a writable file system from the snapshot. The snapshot cannot be destroyed as long
+    /// as the clone exists, unless the clone is promoted.
+    pub fn clone_to(&self, identifier: &FileSystemIdentifier) -> Result<(), CloneSnapshotError> {
+        let string = CString::new(identifier.to_string())?;
+
+        let result = unsafe { zfs_clone(self.handle, string.as_ptr(), null_mut()) };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(())
+    }
+
+    /// Places a user hold with `tag` on the snapshot, which prevents it from being destroyed until
+    /// the hold is released.
+    pub fn hold(&mut self, tag: &str) -> Result<(), HoldSnapshotError> {
+        let file_system = self.file_system()?;
+        let name = CString::new(self.identifier()?.name().as_str())?;
+        let tag = CString::new(tag)?;
+
+        let result = unsafe { zfs_hold(file_system.handle(), name.as_ptr(), tag.as_ptr(), 0, -1) };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn release(&mut self, tag: &str) -> Result<(), ReleaseSnapshotError> {
+        let file_system = self.file_system()?;
+        let name = CString::new(self.identifier()?.name().as_str())?;
+        let tag = CString::new(tag)?;
+
+        let result = unsafe { zfs_release(file_system.handle(), name.as_ptr(), tag.as_ptr(), 0) };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(())
+    }
+
+    /// Returns the tags of all user holds on the snapshot.
+    pub fn holds(&self) -> Result<Vec<String>, ReadSnapshotHoldsError> {
+        let mut list: *mut nvlist_t = null_mut();
+
+        let result = unsafe { zfs_get_holds(self.handle, &mut list) };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        let mut tags = Vec::default();
+        let mut pair = unsafe { nvlist_next_nvpair(list, null_mut()) };
+
+        while !pair.is_null() {
+            match unsafe { CStr::from_ptr(nvpair_name(pair)).to_str() } {
+                Ok(tag) => tags.push(tag.to_string()),
+                Err(error) => {
+                    unsafe { nvlist_free(list) };
+                    return Err(error.into());
+                }
+            }
+
+            pair = unsafe { nvlist_next_nvpair(list, pair) };
+        }
+
+        unsafe { nvlist_free(list) };
+
+        Ok(tags)
+    }
+
+    pub fn send(
+        &mut self,
+        file_descriptor: RawFd,
+        options: &SendOptions,
+    ) -> Result<(), SendSnapshotError> {
+        self.send_from(None, file_descriptor, options)
+    }
+
+    /// Sends the changes between `base`, which must be an earlier snapshot of the same file
+    /// system, and this snapshot.
+    pub fn send_incremental(
+        &mut self,
+        base: &SnapshotIdentifier,
+        file_descriptor: RawFd,
+        options: &SendOptions,
+    ) -> Result<(), SendSnapshotError> {
+        self.send_from(Some(base), file_descriptor, options)
+    }
+
+    fn send_from(
+        &mut self,
+        base: Option<&SnapshotIdentifier>,
+        file_descriptor: RawFd,
+        options: &SendOptions,
+    ) -> Result<(), SendSnapshotError> {
+        let base = base
+            .map(|base| CString::new(base.to_string()))
+            .transpose()?;
+
+        let result = unsafe {
+            zfs_send_one(
+                self.handle,
+                base.as_ref().map_or(null(), |base| base.as_ptr()),
+                file_descriptor,
+                &mut options.flags(),
+                null_mut(),
+            )
+        };
+
+        if result != 0 {
+            return Err(ZfsError::try_from(())?.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn receive(
+        identifier: &SnapshotIdentifier,
+        file_descriptor: RawFd,
+        options: &ReceiveOptions,
+    ) -> Result<(), ReceiveSnapshotError> {
+        ZFS.with::<_, Result<_, ReceiveSnapshotError>>(|zfs| {
+            let string = CString::new(identifier.to_string())?;
+
+            let result = unsafe {
+                zfs_receive(
+                    **zfs,
+                    string.as_ptr(),
+                    null_mut(),
+                    &mut options.flags(),
+                    file_descriptor,
+                    null_mut(),
+                )
+            };
+
+            if result != 0 {
+                return Err(ZfsError::try_from(())?.into());
+            }
+
+            Ok(())
+        })
+    }
+}
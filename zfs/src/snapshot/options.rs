@@ -0,0 +1,176 @@
+#[cfg(feature = "libzfs")]
+use zfs_sys::{recvflags_t, sendflags_t};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Options of a send stream. Every option is disabled by default, which produces a plain stream
+/// readable by any receiver.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SendOptions {
+    properties: bool,
+    large_block: bool,
+    embed_data: bool,
+    compressed: bool,
+    raw: bool,
+    holds: bool,
+}
+
+impl SendOptions {
+    /// Includes the properties of the file system in the stream.
+    pub fn properties(mut self, properties: bool) -> Self {
+        self.properties = properties;
+        self
+    }
+
+    /// Allows blocks larger than 128 KiB in the stream.
+    pub fn large_block(mut self, large_block: bool) -> Self {
+        self.large_block = large_block;
+        self
+    }
+
+    /// Keeps blocks with embedded data embedded in the stream.
+    pub fn embed_data(mut self, embed_data: bool) -> Self {
+        self.embed_data = embed_data;
+        self
+    }
+
+    /// Sends compressed blocks as they are stored on disk instead of decompressing them.
+    pub fn compressed(mut self, compressed: bool) -> Self {
+        self.compressed = compressed;
+        self
+    }
+
+    /// Sends blocks as they are stored on disk, which keeps encrypted file systems encrypted.
+    pub fn raw(mut self, raw: bool) -> Self {
+        self.raw = raw;
+        self
+    }
+
+    /// Includes the user holds of the snapshot in the stream.
+    pub fn holds(mut self, holds: bool) -> Self {
+        self.holds = holds;
+        self
+    }
+
+    pub fn is_properties(&self) -> bool {
+        self.properties
+    }
+
+    pub fn is_large_block(&self) -> bool {
+        self.large_block
+    }
+
+    pub fn is_embed_data(&self) -> bool {
+        self.embed_data
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.compressed
+    }
+
+    pub fn is_raw(&self) -> bool {
+        self.raw
+    }
+
+    pub fn is_holds(&self) -> bool {
+        self.holds
+    }
+
+    #[cfg(feature = "libzfs")]
+    pub(crate) fn flags(&self) -> sendflags_t {
+        sendflags_t {
+            verbosity: 0,
+            replicate: 0,
+            skipmissing: 0,
+            doall: 0,
+            fromorigin: 0,
+            pad: 0,
+            props: self.properties.into(),
+            dryrun: 0,
+            progressastitle: 0,
+            parsable: 0,
+            progress: 0,
+            largeblock: self.large_block.into(),
+            embed_data: self.embed_data.into(),
+            compress: self.compressed.into(),
+            raw: self.raw.into(),
+            backup: 0,
+            holds: self.holds.into(),
+            saved: 0,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Options of receiving a stream. Every option is disabled by default.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ReceiveOptions {
+    force: bool,
+    nomount: bool,
+    resumable: bool,
+    holds: bool,
+}
+
+impl ReceiveOptions {
+    /// Rolls the file system back to the most recent snapshot before receiving, which discards
+    /// changes made since then.
+    pub fn force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Does not mount the received file system.
+    pub fn nomount(mut self, nomount: bool) -> Self {
+        self.nomount = nomount;
+        self
+    }
+
+    /// Keeps the state of an interrupted receive, so that the stream can be resumed.
+    pub fn resumable(mut self, resumable: bool) -> Self {
+        self.resumable = resumable;
+        self
+    }
+
+    /// Applies the user holds included in the stream.
+    pub fn holds(mut self, holds: bool) -> Self {
+        self.holds = holds;
+        self
+    }
+
+    pub fn is_force(&self) -> bool {
+        self.force
+    }
+
+    pub fn is_nomount(&self) -> bool {
+        self.nomount
+    }
+
+    pub fn is_resumable(&self) -> bool {
+        self.resumable
+    }
+
+    pub fn is_holds(&self) -> bool {
+        self.holds
+    }
+
+    #[cfg(feature = "libzfs")]
+    pub(crate) fn flags(&self) -> recvflags_t {
+        recvflags_t {
+            verbose: 0,
+            isprefix: 0,
+            istail: 0,
+            dryrun: 0,
+            force: self.force.into(),
+            canmountoff: 0,
+            resumable: self.resumable.into(),
+            byteswap: 0,
+            heal: 0,
+            nomount: self.nomount.into(),
+            holds: self.holds.into(),
+            skipholds: 0,
+            domount: 0,
+            forceunmount: 0,
+        }
+    }
+}
@@ -3,13 +3,17 @@
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 use clap::{Parser, Subcommand};
+use env_logger::Builder;
+use log::{warn, LevelFilter};
 use std::env::current_dir;
 use std::error;
 use std::fmt::Debug;
 use std::io::{stdin as io_stdin, stdout, ErrorKind};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use zonys_core::{
     ReceiveZoneError, Zone, ZoneConfigurationDirective, ZoneConfigurationVersionDirective,
+    ZoneLockWait, ZoneSendOptions, ZoneZfsPoolDiagnosis,
 };
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -22,6 +26,17 @@ struct MainArguments {
     #[clap(default_value = "/zroot/zonys")]
     base_path: PathBuf,
 
+    #[clap(short, long)]
+    verbose: bool,
+
+    /// Wait for zones locked by other processes instead of failing
+    #[clap(short, long)]
+    wait: bool,
+
+    /// Maximum number of seconds to wait for zones locked by other processes
+    #[clap(long)]
+    timeout: Option<u64>,
+
     #[clap(subcommand)]
     command: MainCommand,
 }
@@ -73,14 +88,88 @@ enum MainCommand {
     },
     Send {
         regular_expression: String,
+        /// Send the zone as it was when the snapshot was taken
+        #[clap(long)]
+        snapshot: Option<String>,
+        /// Send the changes since the snapshot only, up to the most recent snapshot by default
+        #[clap(long)]
+        incremental_from: Option<String>,
+        /// Allow blocks larger than 128 KiB in streams of ZFS volumes
+        #[clap(long)]
+        large_block: bool,
+        /// Keep blocks with embedded data embedded in streams of ZFS volumes
+        #[clap(long)]
+        embed_data: bool,
+        /// Send blocks of ZFS volumes compressed as they are stored on disk
+        #[clap(long)]
+        compressed: bool,
     },
     Receive,
     Run {
         #[clap(short, long)]
         include: Option<Vec<String>>,
     },
+    Snapshot {
+        #[clap(subcommand)]
+        command: SnapshotCommand,
+    },
+    Key {
+        #[clap(subcommand)]
+        command: KeyCommand,
+    },
     Status,
     List,
+    Recover,
+    /// Check the health and capacity of the pool behind the base path
+    Doctor,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Subcommand)]
+enum SnapshotCommand {
+    Create {
+        regular_expression: String,
+        /// Name of the snapshot, defaults to the current UNIX timestamp
+        name: Option<String>,
+    },
+    List {
+        regular_expression: String,
+    },
+    Destroy {
+        regular_expression: String,
+        name: String,
+    },
+    Rollback {
+        regular_expression: String,
+        name: String,
+    },
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Subcommand)]
+enum KeyCommand {
+    /// Load the key of encrypted volumes and mount them
+    Load { regular_expression: String },
+    /// Unmount encrypted volumes and unload their key
+    Unload { regular_expression: String },
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn match_zones(
+    base_path: &Path,
+    regular_expression: &String,
+    lock_wait: ZoneLockWait,
+) -> Result<Vec<Zone>, Box<dyn error::Error>> {
+    let mut zones = Zone::r#match(base_path, regular_expression)?.collect::<Result<Vec<_>, _>>()?;
+
+    for zone in &mut zones {
+        zone.set_lock_wait(lock_wait);
+    }
+
+    Ok(zones)
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -88,10 +177,28 @@ enum MainCommand {
 fn main() -> Result<(), Box<dyn error::Error>> {
     let arguments = MainArguments::parse();
 
+    Builder::new()
+        .filter_level(match arguments.verbose {
+            true => LevelFilter::Info,
+            false => LevelFilter::Warn,
+        })
+        .init();
+
+    if !matches!(arguments.command, MainCommand::Recover) {
+        for (identifier, recovery) in Zone::recover(&arguments.base_path)? {
+            warn!("Zone {} was {}", identifier.uuid(), recovery);
+        }
+    }
+
+    let lock_wait = match (arguments.wait, arguments.timeout) {
+        (_, Some(timeout)) => ZoneLockWait::Timeout(Duration::from_secs(timeout)),
+        (true, None) => ZoneLockWait::Indefinitely,
+        (false, None) => ZoneLockWait::None,
+    };
+
     match arguments.command {
         MainCommand::Show { regular_expression } => {
-            let matched_zones = Zone::r#match(&arguments.base_path, &regular_expression)?
-                .collect::<Result<Vec<_>, _>>()?;
+            let matched_zones = match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
 
             for zone in matched_zones {
                 println!("{}", zone.identifier().uuid());
@@ -112,8 +219,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
             );
         }
         MainCommand::Destroy { regular_expression } => {
-            let matched_zones = Zone::r#match(&arguments.base_path, &regular_expression)?
-                .collect::<Result<Vec<_>, _>>()?;
+            let matched_zones = match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
 
             for zone in matched_zones {
                 let uuid = zone.identifier().uuid().to_string();
@@ -124,8 +230,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
             }
         }
         MainCommand::Recreate { regular_expression } => {
-            let matched_zones = Zone::r#match(&arguments.base_path, &regular_expression)?
-                .collect::<Result<Vec<_>, _>>()?;
+            let matched_zones = match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
 
             for zone in matched_zones {
                 let new_zone = Zone::create(
@@ -140,8 +245,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
             }
         }
         MainCommand::Start { regular_expression } => {
-            let matched_zones = Zone::r#match(&arguments.base_path, &regular_expression)?
-                .collect::<Result<Vec<_>, _>>()?;
+            let matched_zones = match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
 
             for mut zone in matched_zones {
                 zone.start()?;
@@ -149,8 +253,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
             }
         }
         MainCommand::Stop { regular_expression } => {
-            let matched_zones = Zone::r#match(&arguments.base_path, &regular_expression)?
-                .collect::<Result<Vec<_>, _>>()?;
+            let matched_zones = match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
 
             for zone in matched_zones {
                 let uuid = zone.identifier().uuid().to_string();
@@ -159,8 +262,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
             }
         }
         MainCommand::Restart { regular_expression } => {
-            let matched_zones = Zone::r#match(&arguments.base_path, &regular_expression)?
-                .collect::<Result<Vec<_>, _>>()?;
+            let matched_zones = match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
 
             for zone in matched_zones {
                 let mut configuration = zone.configuration().directive()?;
@@ -197,21 +299,20 @@ fn main() -> Result<(), Box<dyn error::Error>> {
             }
         }
         MainCommand::Up { regular_expression } => {
-            let matched_zones = Zone::r#match(&arguments.base_path, &regular_expression)?
-                .collect::<Result<Vec<_>, _>>()?;
+            let matched_zones = match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
 
-            for zone in matched_zones {
+            for mut zone in matched_zones {
                 match zone.status()?.running() {
                     true => {}
                     false => {
+                        zone.start()?;
                         println!("{}", zone.identifier().uuid().to_string());
                     }
                 }
             }
         }
         MainCommand::Down { regular_expression } => {
-            let matched_zones = Zone::r#match(&arguments.base_path, &regular_expression)?
-                .collect::<Result<Vec<_>, _>>()?;
+            let matched_zones = match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
 
             for zone in matched_zones {
                 match zone.status()?.running() {
@@ -225,8 +326,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
             }
         }
         MainCommand::Reup { regular_expression } => {
-            let matched_zones = Zone::r#match(&arguments.base_path, &regular_expression)?
-                .collect::<Result<Vec<_>, _>>()?;
+            let matched_zones = match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
 
             for mut zone in matched_zones {
                 let configuration = zone.configuration().directive()?;
@@ -277,8 +377,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
             println!("{}", zone_identifier);
         }
         MainCommand::Undeploy { regular_expression } => {
-            let matched_zones = Zone::r#match(&arguments.base_path, &regular_expression)?
-                .collect::<Result<Vec<_>, _>>()?;
+            let matched_zones = match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
 
             for zone in matched_zones {
                 let uuid = zone.identifier().uuid().to_string();
@@ -296,8 +395,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
             }
         }
         MainCommand::Redeploy { regular_expression } => {
-            let matched_zones = Zone::r#match(&arguments.base_path, &regular_expression)?
-                .collect::<Result<Vec<_>, _>>()?;
+            let matched_zones = match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
 
             for zone in matched_zones {
                 let configuration = zone.configuration().directive()?;
@@ -321,14 +419,45 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 println!("{}", zone_identifier.uuid().to_string());
             }
         }
-        MainCommand::Send { regular_expression } => {
-            let matched_zones = Zone::r#match(&arguments.base_path, &regular_expression)?
-                .collect::<Result<Vec<_>, _>>()?;
+        MainCommand::Send {
+            regular_expression,
+            snapshot,
+            incremental_from,
+            large_block,
+            embed_data,
+            compressed,
+        } => {
+            let matched_zones = match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
+            let options = ZoneSendOptions::default()
+                .large_block(large_block)
+                .embed_data(embed_data)
+                .compressed(compressed);
 
             let mut stdout = stdout();
 
             for zone in matched_zones {
-                zone.send(&mut stdout)?;
+                let snapshot = match (&snapshot, &incremental_from) {
+                    (Some(snapshot), _) => Some(snapshot.clone()),
+                    (None, Some(_)) => zone.snapshots().names()?.pop(),
+                    (None, None) => None,
+                };
+
+                match snapshot {
+                    Some(snapshot) => zone.send_snapshot(
+                        &mut stdout,
+                        &snapshot,
+                        incremental_from.as_deref(),
+                        &options,
+                    )?,
+                    None if incremental_from.is_some() => {
+                        return Err(format!(
+                            "Zone {} has no snapshot to send",
+                            zone.identifier().uuid()
+                        )
+                        .into())
+                    }
+                    None => zone.send(&mut stdout, &options)?,
+                };
             }
         }
         MainCommand::Receive => {
@@ -362,9 +491,100 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 Zone::create(&arguments.base_path, &current_dir()?, configuration)?.uuid()
             );
         }
+        MainCommand::Snapshot { command } => match command {
+            SnapshotCommand::Create {
+                regular_expression,
+                name,
+            } => {
+                let name = match name {
+                    Some(name) => name,
+                    None => SystemTime::now()
+                        .duration_since(UNIX_EPOCH)?
+                        .as_secs()
+                        .to_string(),
+                };
+
+                let matched_zones =
+                    match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
+
+                for zone in matched_zones {
+                    zone.create_snapshot(&name)?;
+                    println!("{} {}", zone.identifier().uuid(), name);
+                }
+            }
+            SnapshotCommand::List { regular_expression } => {
+                let matched_zones =
+                    match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
+
+                for zone in matched_zones {
+                    for name in zone.snapshots().names()? {
+                        println!("{} {}", zone.identifier().uuid(), name);
+                    }
+                }
+            }
+            SnapshotCommand::Destroy {
+                regular_expression,
+                name,
+            } => {
+                let matched_zones =
+                    match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
+
+                for zone in matched_zones {
+                    zone.destroy_snapshot(&name)?;
+                    println!("{} {}", zone.identifier().uuid(), name);
+                }
+            }
+            SnapshotCommand::Rollback {
+                regular_expression,
+                name,
+            } => {
+                let matched_zones =
+                    match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
+
+                for zone in matched_zones {
+                    zone.rollback_snapshot(&name)?;
+                    println!("{} {}", zone.identifier().uuid(), name);
+                }
+            }
+        },
+        MainCommand::Key { command } => match command {
+            KeyCommand::Load { regular_expression } => {
+                let matched_zones =
+                    match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
+
+                for zone in matched_zones {
+                    zone.load_key()?;
+                    println!("{}", zone.identifier().uuid());
+                }
+            }
+            KeyCommand::Unload { regular_expression } => {
+                let matched_zones =
+                    match_zones(&arguments.base_path, &regular_expression, lock_wait)?;
+
+                for zone in matched_zones {
+                    zone.unload_key()?;
+                    println!("{}", zone.identifier().uuid());
+                }
+            }
+        },
         MainCommand::Status => {
             for zone in Zone::all(&arguments.base_path)? {
-                println!("{:?}", zone?.identifier().uuid());
+                let zone = zone?;
+                let status = zone.status()?;
+
+                println!(
+                    "{} {} {} {}",
+                    zone.identifier().uuid(),
+                    status.state(),
+                    status
+                        .volume_type()
+                        .map(|volume_type| volume_type.to_string())
+                        .unwrap_or_else(|| String::from("-")),
+                    status
+                        .jail_id()
+                        .map(|jail_id| usize::from(jail_id).to_string())
+                        .unwrap_or_else(|| String::from("-")),
+                );
             }
         }
         MainCommand::List => {
@@ -372,6 +592,31 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 println!("{:?}", zone?.identifier().uuid());
             }
         }
+        MainCommand::Recover => {
+            for (identifier, recovery) in Zone::recover(&arguments.base_path)? {
+                println!("{} {}", identifier.uuid(), recovery);
+            }
+        }
+        MainCommand::Doctor => {
+            let diagnosis = ZoneZfsPoolDiagnosis::diagnose(&arguments.base_path)?;
+
+            println!(
+                "{} {} {}",
+                diagnosis.identifier(),
+                diagnosis
+                    .health()
+                    .map(|health| health.to_string())
+                    .unwrap_or_else(|| String::from("-")),
+                diagnosis
+                    .capacity()
+                    .map(|capacity| format!("{}%", capacity))
+                    .unwrap_or_else(|| String::from("-")),
+            );
+
+            for warning in diagnosis.warnings() {
+                warn!("Pool {}: {}", diagnosis.identifier(), warning);
+            }
+        }
     };
 
     Ok(())
@@ -1,5 +1,7 @@
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 use ztd::{Constructor, Method};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -10,13 +12,41 @@ pub struct ZoneConfigurationVersion1JailDirective {
     from: Option<String>,
     from_work_path: Option<String>,
     volume: Option<ZoneConfigurationVersion1VolumeDirective>,
+    zfs_properties: Option<ZoneConfigurationVersion1ZfsPropertiesDirective>,
+    delegate_datasets: Option<Vec<ZoneConfigurationVersion1DatasetNameDirective>>,
+    files: Option<Vec<ZoneConfigurationVersion1FileDirective>>,
+    rc_conf: Option<BTreeMap<String, String>>,
+    ldconfig: Option<Vec<String>>,
     execute: Option<ZoneConfigurationVersion1JailExecuteDirective>,
 }
 
+impl ZoneConfigurationVersion1JailDirective {
+    /// Sets the path relative sources of files are read from, unless it is set already.
+    pub(super) fn set_default_source_work_path(&mut self, path: &Path) {
+        for file in self.files.iter_mut().flatten() {
+            if let ZoneConfigurationVersion1FileContentDirective::Source { .. } = file.content {
+                file.source_work_path
+                    .get_or_insert_with(|| path.display().to_string());
+            }
+        }
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(expecting = "a volume type or a zfs volume", untagged)]
 pub enum ZoneConfigurationVersion1VolumeDirective {
+    Type(ZoneConfigurationVersion1VolumeTypeDirective),
+    Zfs {
+        zfs: ZoneConfigurationVersion1ZfsVolumeDirective,
+    },
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum ZoneConfigurationVersion1VolumeTypeDirective {
     #[serde(alias = "auto", rename = "automatic")]
     Automatic,
     #[serde(rename = "zfs")]
@@ -27,6 +57,104 @@ pub enum ZoneConfigurationVersion1VolumeDirective {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// Encryption settings of ZFS volumes, which can only be chosen on creation. The key location is
+/// an URI like `file:///path/to/key` or `prompt`.
+#[derive(Clone, Constructor, Default, Debug, Deserialize, Method, Serialize)]
+#[Method(all)]
+pub struct ZoneConfigurationVersion1ZfsVolumeDirective {
+    encryption: Option<String>,
+    keyformat: Option<String>,
+    keylocation: Option<String>,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Properties applied to the file system of ZFS volumes on creation. Sizes are in bytes.
+#[derive(Clone, Constructor, Default, Debug, Deserialize, Method, Serialize)]
+#[Method(all)]
+pub struct ZoneConfigurationVersion1ZfsPropertiesDirective {
+    compression: Option<String>,
+    quota: Option<u64>,
+    refquota: Option<u64>,
+    reservation: Option<u64>,
+    recordsize: Option<u64>,
+    atime: Option<bool>,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Name of a dataset below the volume, like `data/poudriere`. Names are checked on reading, so
+/// that they cannot leave the volume or refer to snapshots and bookmarks.
+#[derive(Clone, Debug)]
+pub struct ZoneConfigurationVersion1DatasetNameDirective(String);
+
+impl ZoneConfigurationVersion1DatasetNameDirective {
+    pub fn value(&self) -> &String {
+        &self.0
+    }
+}
+
+impl Serialize for ZoneConfigurationVersion1DatasetNameDirective {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for ZoneConfigurationVersion1DatasetNameDirective {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+
+        let valid = value.split('/').all(|component| {
+            !component.is_empty()
+                && component != "."
+                && component != ".."
+                && component.chars().all(|character| {
+                    character.is_ascii_alphanumeric() || matches!(character, '_' | '-' | '.' | ':')
+                })
+        });
+
+        match valid {
+            true => Ok(Self(value)),
+            false => Err(D::Error::custom(format!(
+                "dataset {} is not a relative name made of letters, digits, _, -, . and :",
+                value
+            ))),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Constructor, Debug, Deserialize, Method, Serialize)]
+#[Method(all)]
+pub struct ZoneConfigurationVersion1FileDirective {
+    destination: String,
+    #[serde(flatten)]
+    content: ZoneConfigurationVersion1FileContentDirective,
+    source_work_path: Option<String>,
+    template: Option<bool>,
+    owner: Option<String>,
+    group: Option<String>,
+    mode: Option<ZoneConfigurationVersion1ModeDirective>,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(expecting = "a content or a source", untagged)]
+pub enum ZoneConfigurationVersion1FileContentDirective {
+    Content { content: String },
+    Source { source: String },
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Clone, Constructor, Debug, Deserialize, Method, Serialize)]
 #[Method(all)]
 pub struct ZoneConfigurationVersion1JailExecuteDirective {
@@ -79,7 +207,109 @@ pub struct ZoneConfigurationVersion1JailDestroyDirective {
 #[derive(Clone, Constructor, Debug, Deserialize, Method, Serialize)]
 #[Method(all)]
 pub struct ZoneConfigurationVersion1JailProgramDirective {
-    program: String,
+    #[serde(flatten)]
+    command: ZoneConfigurationVersion1JailCommandDirective,
     arguments: Option<Vec<String>>,
     environment_variables: Option<HashMap<String, String>>,
+    when: Option<String>,
+    ignore_errors: Option<bool>,
+    retries: Option<u32>,
+    retry_delay: Option<u64>,
+    timeout: Option<u64>,
+    user: Option<String>,
+    group: Option<String>,
+    working_directory: Option<String>,
+    umask: Option<ZoneConfigurationVersion1UmaskDirective>,
+    inherit_login_environment: Option<bool>,
+    host: Option<bool>,
+    register: Option<String>,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(expecting = "a program or a script", untagged)]
+pub enum ZoneConfigurationVersion1JailCommandDirective {
+    Program {
+        program: String,
+    },
+    Script {
+        script: String,
+        interpreter: Option<String>,
+    },
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug)]
+pub struct ZoneConfigurationVersion1UmaskDirective(u32);
+
+impl ZoneConfigurationVersion1UmaskDirective {
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Serialize for ZoneConfigurationVersion1UmaskDirective {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("{:04o}", self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for ZoneConfigurationVersion1UmaskDirective {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Self(deserialize_octal(deserializer, "umask", 0o777)?))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug)]
+pub struct ZoneConfigurationVersion1ModeDirective(u32);
+
+impl ZoneConfigurationVersion1ModeDirective {
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Serialize for ZoneConfigurationVersion1ModeDirective {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("{:04o}", self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for ZoneConfigurationVersion1ModeDirective {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Self(deserialize_octal(deserializer, "mode", 0o7777)?))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn deserialize_octal<'de, D>(deserializer: D, name: &str, maximum: u32) -> Result<u32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+
+    match u32::from_str_radix(&value, 8) {
+        Ok(octal) if octal <= maximum => Ok(octal),
+        _ => Err(D::Error::custom(format!(
+            "{} {} is not an octal value between 0000 and {:04o}",
+            name, value, maximum
+        ))),
+    }
 }
@@ -9,6 +9,7 @@ pub use crate::configuration::directive::jail::*;
 use crate::TemplateObject;
 use serde::{Deserialize, Serialize};
 use serde_yaml::from_reader;
+use std::env::current_dir;
 use std::fs::File;
 use std::io::{self, BufReader};
 use std::path::Path;
@@ -41,8 +42,20 @@ pub struct ZoneConfigurationDirective {
 }
 
 impl ZoneConfigurationDirective {
+    /// Reads the directive from a YAML file. Relative sources of files are read relative to the
+    /// directory containing the file.
     pub fn read_from_yaml_path(path: &Path) -> Result<Self, ReadZoneConfigurationDirectiveError> {
-        Ok(from_reader(BufReader::new(File::open(path)?))?)
+        let mut directive: Self = from_reader(BufReader::new(File::open(path)?))?;
+
+        if let Some(directory_path) = current_dir()?.join(path).parent() {
+            match &mut directive.version {
+                ZoneConfigurationVersionDirective::Version1(version1) => {
+                    version1.set_default_source_work_path(directory_path)
+                }
+            }
+        }
+
+        Ok(directive)
     }
 
     pub fn read_from_path(path: &Path) -> Result<Self, ReadZoneConfigurationDirectiveError> {
@@ -100,6 +113,16 @@ pub struct ZoneConfigurationVersion1Directive {
     destroy_after_stop: Option<bool>,
 }
 
+impl ZoneConfigurationVersion1Directive {
+    fn set_default_source_work_path(&mut self, path: &Path) {
+        match &mut self.r#type {
+            ZoneConfigurationVersion1TypeDirective::Jail(jail) => {
+                jail.set_default_source_work_path(path)
+            }
+        }
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -9,17 +9,24 @@ pub use reader::*;
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 use crate::{
-    DeserializeZoneTransmissionError, SerializeZoneTransmissionError, Zone, ZoneTransmissionReader,
-    ZoneTransmissionWriter,
+    AcquireZoneBaseLockError, DeserializeZoneTransmissionError, SerializeZoneTransmissionError,
+    Zone, ZoneBaseLock, ZoneTransmissionReader, ZoneTransmissionWriter,
 };
 use serde_yaml::{from_reader, to_writer};
-use std::fs::{remove_file, File};
-use std::io::{self, BufReader, BufWriter};
-use std::path::PathBuf;
+use std::fs::{remove_file, File, Permissions};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tempfile::Builder;
 use ztd::{Constructor, Display, Error, From};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// Configuration files are readable by everyone, so that unprivileged users can list zones.
+const ZONE_CONFIGURATION_FILE_MODE: u32 = 0o644;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug, Display, Error, From)]
 #[From(unnamed)]
 pub enum ReadZoneConfigurationError {
@@ -66,6 +73,7 @@ pub enum SendZoneConfigurationError {
 #[derive(Debug, Display, Error, From)]
 #[From(unnamed)]
 pub enum ReceiveZoneConfigurationError {
+    AcquireZoneBaseLockError(AcquireZoneBaseLockError),
     WriteZoneConfigurationError(WriteZoneConfigurationError),
     DeserializeZoneTransmissionError(DeserializeZoneTransmissionError),
 }
@@ -90,14 +98,28 @@ impl<'a> ZoneConfiguration<&'a Zone> {
         Ok(from_reader(BufReader::new(File::open(self.file_path())?))?)
     }
 
+    /// Writes the directive into a temporary file next to the configuration file first and renames
+    /// it afterwards, so that readers never observe a partially written configuration.
     pub fn set_directive(
         &self,
         persistence: &ZoneConfigurationDirective,
     ) -> Result<(), WriteZoneConfigurationError> {
-        Ok(to_writer(
-            BufWriter::new(File::create(self.file_path())?),
-            persistence,
-        )?)
+        let path = self.file_path();
+
+        let mut file = Builder::new()
+            .permissions(Permissions::from_mode(ZONE_CONFIGURATION_FILE_MODE))
+            .tempfile_in(path.parent().unwrap_or_else(|| Path::new("/")))?;
+
+        {
+            let mut writer = BufWriter::new(file.as_file_mut());
+            to_writer(&mut writer, persistence)?;
+            writer.flush()?;
+        }
+
+        file.as_file().sync_all()?;
+        file.persist(&path).map_err(io::Error::from)?;
+
+        Ok(())
     }
 
     pub(super) fn destroy(&self) -> Result<(), DestroyZoneConfigurationError> {
@@ -129,7 +151,11 @@ impl<'a> ZoneConfiguration<&'a Zone> {
         reader: &mut ZoneTransmissionReader,
     ) -> Result<Self, ReceiveZoneConfigurationError> {
         let configuration = Self::new(zone);
-        configuration.set_directive(&reader.deserialize()?)?;
+        let directive = reader.deserialize()?;
+
+        // The lock is taken after deserializing, so that a slow sender does not block listings.
+        let _lock = ZoneBaseLock::exclusive(&zone.paths().base_directory())?;
+        configuration.set_directive(&directive)?;
 
         Ok(configuration)
     }
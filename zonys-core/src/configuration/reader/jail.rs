@@ -1,7 +1,11 @@
 use crate::{
     ZoneConfigurationDirective, ZoneConfigurationReaderTraverser,
-    ZoneConfigurationVersion1JailProgramDirective, ZoneConfigurationVersion1TypeDirective,
-    ZoneConfigurationVersion1VolumeDirective, ZoneConfigurationVersionDirective, ZoneVolumeType,
+    ZoneConfigurationVersion1DatasetNameDirective, ZoneConfigurationVersion1FileDirective,
+    ZoneConfigurationVersion1JailCommandDirective, ZoneConfigurationVersion1JailProgramDirective,
+    ZoneConfigurationVersion1TypeDirective, ZoneConfigurationVersion1UmaskDirective,
+    ZoneConfigurationVersion1VolumeDirective, ZoneConfigurationVersion1VolumeTypeDirective,
+    ZoneConfigurationVersion1ZfsPropertiesDirective, ZoneConfigurationVersion1ZfsVolumeDirective,
+    ZoneConfigurationVersionDirective, ZoneVolumeType,
 };
 use std::collections::HashMap;
 use std::iter::empty;
@@ -13,17 +17,41 @@ use ztd::{Constructor, Method};
 #[Method(accessors)]
 #[Constructor(visibility = pub(self))]
 pub struct JailZoneConfigurationStep<'a> {
-    program: &'a String,
+    command: &'a ZoneConfigurationVersion1JailCommandDirective,
     arguments: &'a Option<Vec<String>>,
     environment_variables: &'a Option<HashMap<String, String>>,
+    when: &'a Option<String>,
+    ignore_errors: &'a Option<bool>,
+    retries: &'a Option<u32>,
+    retry_delay: &'a Option<u64>,
+    timeout: &'a Option<u64>,
+    user: &'a Option<String>,
+    group: &'a Option<String>,
+    working_directory: &'a Option<String>,
+    umask: &'a Option<ZoneConfigurationVersion1UmaskDirective>,
+    inherit_login_environment: &'a Option<bool>,
+    host: &'a Option<bool>,
+    register: &'a Option<String>,
 }
 
 impl<'a> From<&'a ZoneConfigurationVersion1JailProgramDirective> for JailZoneConfigurationStep<'a> {
     fn from(unit: &'a ZoneConfigurationVersion1JailProgramDirective) -> Self {
         Self::new(
-            unit.program(),
+            unit.command(),
             unit.arguments(),
             unit.environment_variables(),
+            unit.when(),
+            unit.ignore_errors(),
+            unit.retries(),
+            unit.retry_delay(),
+            unit.timeout(),
+            unit.user(),
+            unit.group(),
+            unit.working_directory(),
+            unit.umask(),
+            unit.inherit_login_environment(),
+            unit.host(),
+            unit.register(),
         )
     }
 }
@@ -47,13 +75,18 @@ impl<'a> JailZoneConfigurationReader<'a> {
 
                     if let Some(volume) = jail.volume() {
                         return match volume {
-                            ZoneConfigurationVersion1VolumeDirective::Automatic => {
-                                ZoneVolumeType::Automatic
-                            }
-                            ZoneConfigurationVersion1VolumeDirective::Directory => {
-                                ZoneVolumeType::Directory
+                            ZoneConfigurationVersion1VolumeDirective::Type(
+                                ZoneConfigurationVersion1VolumeTypeDirective::Automatic,
+                            ) => ZoneVolumeType::Automatic,
+                            ZoneConfigurationVersion1VolumeDirective::Type(
+                                ZoneConfigurationVersion1VolumeTypeDirective::Directory,
+                            ) => ZoneVolumeType::Directory,
+                            ZoneConfigurationVersion1VolumeDirective::Type(
+                                ZoneConfigurationVersion1VolumeTypeDirective::Zfs,
+                            )
+                            | ZoneConfigurationVersion1VolumeDirective::Zfs { .. } => {
+                                ZoneVolumeType::Zfs
                             }
-                            ZoneConfigurationVersion1VolumeDirective::Zfs => ZoneVolumeType::Zfs,
                         };
                     }
                 }
@@ -63,6 +96,89 @@ impl<'a> JailZoneConfigurationReader<'a> {
         ZoneVolumeType::Automatic
     }
 
+    /// Returns the encryption settings of ZFS volumes, where every setting is taken from the first
+    /// directive setting it.
+    pub fn zfs_volume(&self) -> ZoneConfigurationVersion1ZfsVolumeDirective {
+        let mut encryption = None;
+        let mut keyformat = None;
+        let mut keylocation = None;
+
+        for unit in ZoneConfigurationReaderTraverser::new(vec![self.unit]).inorder() {
+            match unit.version() {
+                ZoneConfigurationVersionDirective::Version1(version1) => {
+                    let jail = match version1.r#type() {
+                        ZoneConfigurationVersion1TypeDirective::Jail(jail) => jail,
+                    };
+
+                    if let Some(ZoneConfigurationVersion1VolumeDirective::Zfs { zfs }) =
+                        jail.volume()
+                    {
+                        encryption = encryption.or_else(|| zfs.encryption().clone());
+                        keyformat = keyformat.or_else(|| zfs.keyformat().clone());
+                        keylocation = keylocation.or_else(|| zfs.keylocation().clone());
+                    }
+                }
+            }
+        }
+
+        ZoneConfigurationVersion1ZfsVolumeDirective::new(encryption, keyformat, keylocation)
+    }
+
+    /// Returns the ZFS properties, where every property is taken from the first directive
+    /// setting it.
+    pub fn zfs_properties(&self) -> ZoneConfigurationVersion1ZfsPropertiesDirective {
+        let mut compression = None;
+        let mut quota = None;
+        let mut refquota = None;
+        let mut reservation = None;
+        let mut recordsize = None;
+        let mut atime = None;
+
+        for unit in ZoneConfigurationReaderTraverser::new(vec![self.unit]).inorder() {
+            match unit.version() {
+                ZoneConfigurationVersionDirective::Version1(version1) => {
+                    let jail = match version1.r#type() {
+                        ZoneConfigurationVersion1TypeDirective::Jail(jail) => jail,
+                    };
+
+                    if let Some(zfs_properties) = jail.zfs_properties() {
+                        compression = compression.or_else(|| zfs_properties.compression().clone());
+                        quota = quota.or(*zfs_properties.quota());
+                        refquota = refquota.or(*zfs_properties.refquota());
+                        reservation = reservation.or(*zfs_properties.reservation());
+                        recordsize = recordsize.or(*zfs_properties.recordsize());
+                        atime = atime.or(*zfs_properties.atime());
+                    }
+                }
+            }
+        }
+
+        ZoneConfigurationVersion1ZfsPropertiesDirective::new(
+            compression,
+            quota,
+            refquota,
+            reservation,
+            recordsize,
+            atime,
+        )
+    }
+
+    /// Returns the names of the datasets below the volume, which are delegated to the jail.
+    pub fn delegate_datasets(&self) -> impl Iterator<Item = &'a String> {
+        ZoneConfigurationReaderTraverser::new(vec![self.unit])
+            .inorder()
+            .flat_map(|unit| match unit.version() {
+                ZoneConfigurationVersionDirective::Version1(version1) => {
+                    let jail = match version1.r#type() {
+                        ZoneConfigurationVersion1TypeDirective::Jail(jail) => jail,
+                    };
+
+                    jail.delegate_datasets().iter().flatten()
+                }
+            })
+            .map(ZoneConfigurationVersion1DatasetNameDirective::value)
+    }
+
     pub fn from(&self) -> Option<&String> {
         for unit in ZoneConfigurationReaderTraverser::new(vec![self.unit]).inorder() {
             match unit.version() {
@@ -99,6 +215,48 @@ impl<'a> JailZoneConfigurationReader<'a> {
         None
     }
 
+    pub fn files(&self) -> impl Iterator<Item = &'a ZoneConfigurationVersion1FileDirective> {
+        ZoneConfigurationReaderTraverser::new(vec![self.unit])
+            .inorder()
+            .flat_map(|unit| match unit.version() {
+                ZoneConfigurationVersionDirective::Version1(version1) => {
+                    let jail = match version1.r#type() {
+                        ZoneConfigurationVersion1TypeDirective::Jail(jail) => jail,
+                    };
+
+                    jail.files().iter().flatten()
+                }
+            })
+    }
+
+    pub fn rc_conf(&self) -> impl Iterator<Item = (&'a String, &'a String)> {
+        ZoneConfigurationReaderTraverser::new(vec![self.unit])
+            .inorder()
+            .flat_map(|unit| match unit.version() {
+                ZoneConfigurationVersionDirective::Version1(version1) => {
+                    let jail = match version1.r#type() {
+                        ZoneConfigurationVersion1TypeDirective::Jail(jail) => jail,
+                    };
+
+                    jail.rc_conf().iter().flatten()
+                }
+            })
+    }
+
+    pub fn ldconfig(&self) -> impl Iterator<Item = &'a String> {
+        ZoneConfigurationReaderTraverser::new(vec![self.unit])
+            .inorder()
+            .flat_map(|unit| match unit.version() {
+                ZoneConfigurationVersionDirective::Version1(version1) => {
+                    let jail = match version1.r#type() {
+                        ZoneConfigurationVersion1TypeDirective::Jail(jail) => jail,
+                    };
+
+                    jail.ldconfig().iter().flatten()
+                }
+            })
+    }
+
     pub fn create_steps(&self) -> impl Iterator<Item = JailZoneConfigurationStep<'a>> {
         ZoneConfigurationReaderTraverser::new(vec![self.unit])
             .inorder()
@@ -39,12 +39,7 @@ impl<'a> Iterator for ZoneConfigurationReaderInorderTraverser<'a> {
     type Item = &'a ZoneConfigurationDirective;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let _top = match self.todo.pop() {
-            None => return None,
-            Some(top) => top,
-        };
-
-        None
+        self.todo.pop()
     }
 }
 
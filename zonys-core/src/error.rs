@@ -1,10 +1,19 @@
 use crate::{
-    AcquireZoneLockError, CleanupZoneConfigurationError, CleanupZoneLockError,
-    CleanupZoneTypeError, CreateZoneTypeError, DestroyZoneConfigurationError, DestroyZoneTypeError,
-    FileSystemIdentifierTryFromZoneIdentifierError, HoldZoneLockError, ReadZoneConfigurationError,
-    ReceiveZoneConfigurationError, ReceiveZoneTypeError, ReleaseZoneLockError, RenderTemplateError,
-    SendZoneConfigurationError, SendZoneTypeError, StartZoneTypeError, StopZoneTypeError,
-    WriteZoneConfigurationError, ZoneIdentifierTryFromPathError,
+    AcquireZoneBaseLockError, AcquireZoneLockError, CheckZoneVolumeKeyError,
+    CheckZoneVolumeSnapshotError, CleanupZoneConfigurationError, CleanupZoneJournalError,
+    CleanupZoneLifecycleError, CleanupZoneLockError, CleanupZoneSnapshotsError,
+    CleanupZoneTypeError, CleanupZoneVolumeError, CreateZoneTypeError,
+    CreateZoneVolumeSnapshotError, DeserializeZoneTransmissionError, DestroyZoneConfigurationError,
+    DestroyZoneJournalError, DestroyZoneLifecycleError, DestroyZoneSnapshotsError,
+    DestroyZoneTypeError, DestroyZoneVolumeSnapshotError,
+    FileSystemIdentifierTryFromZoneIdentifierError, HoldZoneLockError, LoadZoneVolumeKeyError,
+    OpenZoneVolumeError, ReadZoneConfigurationError, ReadZoneJournalError, ReadZoneLifecycleError,
+    ReadZoneSnapshotsError, ReadZoneTypeStatusError, ReceiveZoneConfigurationError,
+    ReceiveZoneTypeError, ReleaseZoneLockError, RenderTemplateError,
+    RollbackZoneVolumeSnapshotError, SendZoneConfigurationError, SendZoneTypeError,
+    SerializeZoneTransmissionError, StartZoneTypeError, StopZoneTypeError,
+    UnloadZoneVolumeKeyError, WriteZoneConfigurationError, WriteZoneJournalError,
+    WriteZoneLifecycleError, WriteZoneSnapshotsError, ZoneIdentifierTryFromPathError, ZoneState,
 };
 use nix::errno::Errno;
 use std::io;
@@ -17,7 +26,11 @@ use ztd::{Display, Error, From};
 #[derive(Debug, Display, Error, From)]
 #[From(unnamed)]
 pub enum ReadZoneStatusError {
-    __Placeholder,
+    IoError(io::Error),
+    ReadZoneConfigurationError(ReadZoneConfigurationError),
+    OpenZoneVolumeError(OpenZoneVolumeError),
+    ReadZoneTypeStatusError(ReadZoneTypeStatusError),
+    ReadZoneLifecycleError(ReadZoneLifecycleError),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -28,15 +41,48 @@ pub enum CleanupZoneError {
     ReadZoneConfigurationError(ReadZoneConfigurationError),
     CleanupZoneConfigurationError(CleanupZoneConfigurationError),
     CleanupZoneLockError(CleanupZoneLockError),
+    CleanupZoneLifecycleError(CleanupZoneLifecycleError),
     CleanupZoneTypeError(CleanupZoneTypeError),
+    CleanupZoneJournalError(CleanupZoneJournalError),
+    OpenZoneVolumeError(OpenZoneVolumeError),
+    CleanupZoneVolumeError(CleanupZoneVolumeError),
+    CleanupZoneSnapshotsError(CleanupZoneSnapshotsError),
     CleanupZoneErrors(Vec<CleanupZoneError>),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum ResumeZoneError {
+    HoldZoneLockError(HoldZoneLockError),
+    ReadZoneConfigurationError(ReadZoneConfigurationError),
+    CreateZoneTypeError(CreateZoneTypeError),
+    WriteZoneJournalError(WriteZoneJournalError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum RecoverZoneError {
+    AcquireZoneBaseLockError(AcquireZoneBaseLockError),
+    IoError(io::Error),
+    StripPrefixError(StripPrefixError),
+    ParseZoneIdentifierError(ParseZoneIdentifierError),
+    ReadZoneJournalError(ReadZoneJournalError),
+    DestroyZoneJournalError(DestroyZoneJournalError),
+    WriteZoneLifecycleError(WriteZoneLifecycleError),
+    CleanupZoneLockError(CleanupZoneLockError),
+    CleanupZoneError(CleanupZoneError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug, Display, Error, From)]
 #[From(unnamed)]
 pub enum CreateZoneError {
+    AcquireZoneBaseLockError(AcquireZoneBaseLockError),
     IoError(io::Error),
     #[Display("File system is not existing")]
     FileSystemNotExisting,
@@ -58,6 +104,9 @@ pub enum CreateZoneError {
     CleanupZoneError(CleanupZoneError),
     CreateZoneTypeError(CreateZoneTypeError),
     ReadZoneConfigurationError(ReadZoneConfigurationError),
+    WriteZoneLifecycleError(WriteZoneLifecycleError),
+    WriteZoneJournalError(WriteZoneJournalError),
+    DestroyZoneJournalError(DestroyZoneJournalError),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -67,9 +116,17 @@ pub enum CreateZoneError {
 pub enum StartZoneError {
     #[Display("Zone is already running")]
     AlreadyRunning,
+    #[Display("Zone cannot be started while it is {value}")]
+    #[From(skip)]
+    InvalidState(ZoneState),
+    #[Display("Key of volume is not loaded")]
+    KeyNotLoaded,
+    WriteZoneLifecycleError(WriteZoneLifecycleError),
     HoldZoneLockError(HoldZoneLockError),
     ReadZoneStatusError(ReadZoneStatusError),
     ReadZoneConfigurationError(ReadZoneConfigurationError),
+    OpenZoneVolumeError(OpenZoneVolumeError),
+    CheckZoneVolumeKeyError(CheckZoneVolumeKeyError),
     StartZoneTypeError(StartZoneTypeError),
 }
 
@@ -80,6 +137,10 @@ pub enum StartZoneError {
 pub enum StopZoneError {
     #[Display("Zone is not running")]
     NotRunning,
+    #[Display("Zone cannot be stopped while it is {value}")]
+    #[From(skip)]
+    InvalidState(ZoneState),
+    WriteZoneLifecycleError(WriteZoneLifecycleError),
     HoldZoneLockError(HoldZoneLockError),
     ReadZoneStatusError(ReadZoneStatusError),
     ReadZoneConfigurationError(ReadZoneConfigurationError),
@@ -92,14 +153,21 @@ pub enum StopZoneError {
 #[derive(Debug, Display, Error, From)]
 #[From(unnamed)]
 pub enum DestroyZoneError {
+    AcquireZoneBaseLockError(AcquireZoneBaseLockError),
     OpenZoneConfigurationError(OpenZoneConfigurationError),
     #[Display("Zone is running")]
     IsRunning,
+    #[Display("Zone cannot be destroyed while it is {value}")]
+    #[From(skip)]
+    InvalidState(ZoneState),
+    WriteZoneLifecycleError(WriteZoneLifecycleError),
+    DestroyZoneLifecycleError(DestroyZoneLifecycleError),
     AcquireZoneLockError(AcquireZoneLockError),
     ReleaseZoneLockError(ReleaseZoneLockError),
     IoError(io::Error),
     HoldZoneLockError(HoldZoneLockError),
     DestroyZoneConfigurationError(DestroyZoneConfigurationError),
+    DestroyZoneSnapshotsError(DestroyZoneSnapshotsError),
     ReadZoneStatusError(ReadZoneStatusError),
     DestroyZoneTypeError(DestroyZoneTypeError),
     ReadZoneConfigurationError(ReadZoneConfigurationError),
@@ -107,6 +175,89 @@ pub enum DestroyZoneError {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum CreateZoneSnapshotError {
+    #[Display("Snapshot name {value} is invalid")]
+    #[From(skip)]
+    InvalidName(String),
+    #[Display("Snapshot {value} is already existing")]
+    #[From(skip)]
+    AlreadyExisting(String),
+    #[Display("Volume does not exist")]
+    VolumeNotExisting,
+    HoldZoneLockError(HoldZoneLockError),
+    ReadZoneConfigurationError(ReadZoneConfigurationError),
+    OpenZoneVolumeError(OpenZoneVolumeError),
+    CreateZoneVolumeSnapshotError(CreateZoneVolumeSnapshotError),
+    WriteZoneSnapshotsError(WriteZoneSnapshotsError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum DestroyZoneSnapshotError {
+    #[Display("Snapshot {value} does not exist")]
+    #[From(skip)]
+    NotExisting(String),
+    HoldZoneLockError(HoldZoneLockError),
+    OpenZoneVolumeError(OpenZoneVolumeError),
+    DestroyZoneVolumeSnapshotError(DestroyZoneVolumeSnapshotError),
+    WriteZoneSnapshotsError(WriteZoneSnapshotsError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum RollbackZoneSnapshotError {
+    #[Display("Zone is running")]
+    IsRunning,
+    #[Display("Snapshot {value} does not exist")]
+    #[From(skip)]
+    NotExisting(String),
+    #[Display("Volume does not exist")]
+    VolumeNotExisting,
+    HoldZoneLockError(HoldZoneLockError),
+    ReadZoneStatusError(ReadZoneStatusError),
+    ReadZoneSnapshotsError(ReadZoneSnapshotsError),
+    OpenZoneVolumeError(OpenZoneVolumeError),
+    CheckZoneVolumeSnapshotError(CheckZoneVolumeSnapshotError),
+    RollbackZoneVolumeSnapshotError(RollbackZoneVolumeSnapshotError),
+    WriteZoneConfigurationError(WriteZoneConfigurationError),
+    WriteZoneSnapshotsError(WriteZoneSnapshotsError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum LoadZoneKeyError {
+    #[Display("Volume does not exist")]
+    VolumeNotExisting,
+    HoldZoneLockError(HoldZoneLockError),
+    OpenZoneVolumeError(OpenZoneVolumeError),
+    LoadZoneVolumeKeyError(LoadZoneVolumeKeyError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum UnloadZoneKeyError {
+    #[Display("Zone is running")]
+    IsRunning,
+    #[Display("Volume does not exist")]
+    VolumeNotExisting,
+    HoldZoneLockError(HoldZoneLockError),
+    ReadZoneStatusError(ReadZoneStatusError),
+    OpenZoneVolumeError(OpenZoneVolumeError),
+    UnloadZoneVolumeKeyError(UnloadZoneVolumeKeyError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug, Display, Error, From)]
 #[From(unnamed)]
 pub enum OpenZoneError {
@@ -147,6 +298,11 @@ pub enum OpenZoneConfigurationError {
 #[From(unnamed)]
 pub enum SendZoneError {
     HoldZoneLockError(HoldZoneLockError),
+    #[Display("Snapshot {value} does not exist")]
+    #[From(skip)]
+    SnapshotNotExisting(String),
+    ReadZoneSnapshotsError(ReadZoneSnapshotsError),
+    SerializeZoneTransmissionError(SerializeZoneTransmissionError),
     ZoneIsRunning,
     MissingFileSystem,
     OpenZoneConfigurationError(OpenZoneConfigurationError),
@@ -170,12 +326,40 @@ pub enum ReceiveZoneError {
     YamlError(serde_yaml::Error),
     #[Display("Magic number is missing")]
     MissingMagicNumber,
+    #[Display("Transmission header is unsupported")]
+    UnsupportedHeader,
+    #[Display("Snapshot name {value} is invalid")]
+    #[From(skip)]
+    InvalidSnapshotName(String),
+    #[Display("Snapshot {value} does not exist")]
+    #[From(skip)]
+    SnapshotNotExisting(String),
+    #[Display("Zone does not exist")]
+    ZoneNotExisting,
+    #[Display("Zone is running")]
+    IsRunning,
+    #[Display("Zone cannot be updated while it is {value}")]
+    #[From(skip)]
+    InvalidState(ZoneState),
+    #[Display("Volume does not exist")]
+    VolumeNotExisting,
     Errno(Errno),
     #[Display("Input is empty")]
     EmptyInput,
     ZoneIdentifierTryFromPathError(ZoneIdentifierTryFromPathError),
     ReceiveZoneConfigurationError(ReceiveZoneConfigurationError),
     ReceiveZoneTypeError(ReceiveZoneTypeError),
+    WriteZoneLifecycleError(WriteZoneLifecycleError),
+    WriteZoneJournalError(WriteZoneJournalError),
+    DestroyZoneJournalError(DestroyZoneJournalError),
+    DeserializeZoneTransmissionError(DeserializeZoneTransmissionError),
+    ReadZoneStatusError(ReadZoneStatusError),
+    ReadZoneConfigurationError(ReadZoneConfigurationError),
+    WriteZoneConfigurationError(WriteZoneConfigurationError),
+    OpenZoneVolumeError(OpenZoneVolumeError),
+    CheckZoneVolumeSnapshotError(CheckZoneVolumeSnapshotError),
+    CreateZoneVolumeSnapshotError(CreateZoneVolumeSnapshotError),
+    WriteZoneSnapshotsError(WriteZoneSnapshotsError),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -193,6 +377,7 @@ pub enum ConvertZoneIdentifierFromFileSystemIdentifierError {
 #[derive(Debug, Display, Error, From)]
 #[From(unnamed)]
 pub enum AllZoneIteratorError {
+    AcquireZoneBaseLockError(AcquireZoneBaseLockError),
     IoError(io::Error),
 }
 
@@ -212,6 +397,7 @@ pub enum NextAllZoneIteratorError {
 #[derive(Debug, Display, Error, From)]
 #[From(unnamed)]
 pub enum MatchZoneIteratorError {
+    AcquireZoneBaseLockError(AcquireZoneBaseLockError),
     IoError(io::Error),
     RegexError(regex::Error),
 }
@@ -0,0 +1,245 @@
+use nix::fcntl::OFlag;
+use std::ffi::OsString;
+use std::fs::{create_dir_all, read_link, read_to_string, set_permissions, symlink_metadata};
+use std::fs::{OpenOptions, Permissions};
+use std::io::{self, Write};
+use std::os::unix::fs::{chown, OpenOptionsExt, PermissionsExt};
+use std::path::{Component, Path, PathBuf};
+use ztd::{Display, Error, From};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const FILE_HANDLER_MAXIMUM_SYMBOLIC_LINKS: usize = 32;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const FILE_HANDLER_DEFAULT_MODE: u32 = 0o644;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum FileHandlerError {
+    IOError(io::Error),
+    #[From(skip)]
+    #[Display("Too many symbolic links while resolving {value}")]
+    TooManySymbolicLinks(String),
+    #[From(skip)]
+    #[Display("User {value} does not exist")]
+    UnknownUser(String),
+    #[From(skip)]
+    #[Display("Group {value} does not exist")]
+    UnknownGroup(String),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub struct FileHandler;
+
+impl FileHandler {
+    /// Resolves `path` like a chroot into `root_directory_path` would. Parent directories and
+    /// symbolic links are followed, but can never leave the root directory.
+    pub fn resolve(root_directory_path: &Path, path: &Path) -> Result<PathBuf, FileHandlerError> {
+        let mut resolved = PathBuf::default();
+        let mut pending = Self::components(path);
+        let mut links = 0;
+
+        while let Some(component) = pending.pop() {
+            if component == ".." {
+                resolved.pop();
+                continue;
+            }
+
+            let candidate = resolved.join(&component);
+            let candidate_path = root_directory_path.join(&candidate);
+
+            match symlink_metadata(&candidate_path) {
+                Ok(metadata) if metadata.file_type().is_symlink() => {
+                    links += 1;
+
+                    if links > FILE_HANDLER_MAXIMUM_SYMBOLIC_LINKS {
+                        return Err(FileHandlerError::TooManySymbolicLinks(
+                            path.display().to_string(),
+                        ));
+                    }
+
+                    let target = read_link(&candidate_path)?;
+
+                    if target.is_absolute() {
+                        resolved = PathBuf::default();
+                    }
+
+                    pending.extend(Self::components(&target));
+                }
+                Ok(_) => resolved = candidate,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => resolved = candidate,
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        Ok(root_directory_path.join(resolved))
+    }
+
+    /// Returns the normal and parent components of `path` in reverse order.
+    fn components(path: &Path) -> Vec<OsString> {
+        path.components()
+            .rev()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_os_string()),
+                Component::ParentDir => Some(OsString::from("..")),
+                Component::RootDir | Component::CurDir | Component::Prefix(_) => None,
+            })
+            .collect()
+    }
+
+    /// Looks up `name` within a database like `/etc/passwd` or `/etc/group` of the root directory
+    /// and returns its numeric identifier. Numeric names are returned as is.
+    fn lookup(
+        root_directory_path: &Path,
+        database: &str,
+        name: &str,
+    ) -> Result<Option<u32>, FileHandlerError> {
+        if let Ok(identifier) = name.parse() {
+            return Ok(Some(identifier));
+        }
+
+        let content = match read_to_string(Self::resolve(root_directory_path, Path::new(database))?)
+        {
+            Ok(content) => content,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+
+        Ok(content
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| line.split(':').collect::<Vec<_>>())
+            .find(|fields| fields.first() == Some(&name))
+            .and_then(|fields| fields.get(2).and_then(|identifier| identifier.parse().ok())))
+    }
+
+    /// Returns the numeric identifiers of `owner` and `group` within the root directory.
+    pub fn identifiers(
+        root_directory_path: &Path,
+        owner: Option<&str>,
+        group: Option<&str>,
+    ) -> Result<(Option<u32>, Option<u32>), FileHandlerError> {
+        let uid = match owner {
+            Some(owner) => Some(
+                Self::lookup(root_directory_path, "/etc/passwd", owner)?
+                    .ok_or_else(|| FileHandlerError::UnknownUser(owner.to_string()))?,
+            ),
+            None => None,
+        };
+
+        let gid = match group {
+            Some(group) => Some(
+                Self::lookup(root_directory_path, "/etc/group", group)?
+                    .ok_or_else(|| FileHandlerError::UnknownGroup(group.to_string()))?,
+            ),
+            None => None,
+        };
+
+        Ok((uid, gid))
+    }
+
+    pub fn handle(
+        root_directory_path: &Path,
+        destination: &Path,
+        content: &[u8],
+        owner: Option<&str>,
+        group: Option<&str>,
+        mode: Option<u32>,
+    ) -> Result<(), FileHandlerError> {
+        let (uid, gid) = Self::identifiers(root_directory_path, owner, group)?;
+        let path = Self::resolve(root_directory_path, destination)?;
+
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(mode.unwrap_or(FILE_HANDLER_DEFAULT_MODE))
+            .custom_flags(OFlag::O_NOFOLLOW.bits())
+            .open(&path)?
+            .write_all(content)?;
+
+        if let Some(mode) = mode {
+            set_permissions(&path, Permissions::from_mode(mode))?;
+        }
+
+        if uid.is_some() || gid.is_some() {
+            chown(&path, uid, gid)?;
+        }
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::create_dir;
+    use std::os::unix::fs::symlink;
+    use tempfile::tempdir;
+
+    #[test]
+    fn keeps_parent_directories_within_root_directory() {
+        let directory = tempdir().unwrap();
+
+        assert_eq!(
+            FileHandler::resolve(directory.path(), Path::new("../../etc/passwd")).unwrap(),
+            directory.path().join("etc/passwd"),
+        );
+        assert_eq!(
+            FileHandler::resolve(directory.path(), Path::new("/usr/../../etc/./passwd")).unwrap(),
+            directory.path().join("etc/passwd"),
+        );
+    }
+
+    #[test]
+    fn resolves_absolute_symbolic_links_within_root_directory() {
+        let directory = tempdir().unwrap();
+        create_dir(directory.path().join("usr")).unwrap();
+        symlink("/etc", directory.path().join("usr/etc")).unwrap();
+        symlink("/etc/passwd", directory.path().join("passwd")).unwrap();
+
+        assert_eq!(
+            FileHandler::resolve(directory.path(), Path::new("/usr/etc/group")).unwrap(),
+            directory.path().join("etc/group"),
+        );
+        assert_eq!(
+            FileHandler::resolve(directory.path(), Path::new("/passwd")).unwrap(),
+            directory.path().join("etc/passwd"),
+        );
+    }
+
+    #[test]
+    fn resolves_relative_symbolic_links_within_root_directory() {
+        let directory = tempdir().unwrap();
+        create_dir(directory.path().join("usr")).unwrap();
+        symlink("../../../etc", directory.path().join("usr/etc")).unwrap();
+
+        assert_eq!(
+            FileHandler::resolve(directory.path(), Path::new("/usr/etc/group")).unwrap(),
+            directory.path().join("etc/group"),
+        );
+    }
+
+    #[test]
+    fn rejects_symbolic_link_loops() {
+        let directory = tempdir().unwrap();
+        symlink("/second", directory.path().join("first")).unwrap();
+        symlink("first", directory.path().join("second")).unwrap();
+
+        assert!(matches!(
+            FileHandler::resolve(directory.path(), Path::new("/first/file")),
+            Err(FileHandlerError::TooManySymbolicLinks(_))
+        ));
+    }
+}
@@ -1,5 +1,7 @@
+mod file;
 mod from;
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+pub use file::*;
 pub use from::*;
@@ -113,7 +113,7 @@ impl FromStr for ZoneIdentifier {
     }
 }
 
-#[cfg(target_os = "freebsd")]
+#[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
 impl TryFrom<ZoneIdentifier> for zfs::file_system::identifier::FileSystemIdentifier {
     type Error = FileSystemIdentifierTryFromZoneIdentifierError;
 
@@ -151,7 +151,7 @@ impl TryFrom<PathBuf> for ZoneIdentifier {
     }
 }
 
-#[cfg(target_os = "freebsd")]
+#[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
 impl TryFrom<zfs::file_system::identifier::FileSystemIdentifier> for ZoneIdentifier {
     type Error = ConvertZoneIdentifierFromFileSystemIdentifierError;
 
@@ -1,5 +1,9 @@
-use crate::{NextAllZoneIteratorError, NextMatchZoneIteratorError, Zone, ZoneIdentifier};
+use crate::{
+    NextAllZoneIteratorError, NextMatchZoneIteratorError, Zone, ZoneBaseLock, ZoneIdentifier,
+    ZONE_CONFIGURATION_PATH_EXTENSION,
+};
 use regex::Regex;
+use std::ffi::OsStr;
 use std::fs::ReadDir;
 use ztd::Constructor;
 
@@ -8,6 +12,7 @@ use ztd::Constructor;
 #[derive(Constructor, Debug)]
 pub struct AllZoneIterator {
     iterator: ReadDir,
+    _lock: ZoneBaseLock,
 }
 
 impl Iterator for AllZoneIterator {
@@ -26,7 +31,9 @@ impl Iterator for AllZoneIterator {
                 Err(error) => break Some(Err(NextAllZoneIteratorError::from(error))),
             };
 
-            if !metadata.is_file() {
+            if !metadata.is_file()
+                || next.path().extension() != Some(OsStr::new(ZONE_CONFIGURATION_PATH_EXTENSION))
+            {
                 continue;
             }
 
@@ -0,0 +1,251 @@
+use crate::Zone;
+use std::fmt::{self, Formatter};
+use std::fs::{read_to_string, remove_file, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use ztd::{Constructor, Display, Error, From};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum ParseZoneJournalEntryError {
+    #[Display("Journal entry {value} is unknown")]
+    #[From(skip)]
+    UnknownEntry(String),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum ReadZoneJournalError {
+    IOError(io::Error),
+    ParseZoneJournalEntryError(ParseZoneJournalEntryError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum WriteZoneJournalError {
+    IOError(io::Error),
+    #[Display("Journal is already existing")]
+    AlreadyExisting,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum DestroyZoneJournalError {
+    IOError(io::Error),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum CleanupZoneJournalError {
+    IOError(io::Error),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+pub enum ZoneJournalOperation {
+    #[Display("create")]
+    Create,
+    #[Display("receive")]
+    Receive,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// An entry of the journal. `Begin` and `Volume` are written before the side effect they describe
+/// takes place, so that they describe an upper bound of the changes which need to be rolled back.
+/// Without `Volume`, neither a volume nor a jail has been created yet. `Extracted`, `Step` and
+/// `Commit` are written after the extraction of `from`, the create step with the given index and
+/// the whole operation have completed, so that recovery can resume after them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ZoneJournalEntry {
+    Begin(ZoneJournalOperation),
+    Volume,
+    Extracted,
+    Step(usize),
+    Commit,
+}
+
+impl fmt::Display for ZoneJournalEntry {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Begin(operation) => write!(formatter, "begin {}", operation),
+            Self::Volume => write!(formatter, "volume"),
+            Self::Extracted => write!(formatter, "extracted"),
+            Self::Step(index) => write!(formatter, "step {}", index),
+            Self::Commit => write!(formatter, "commit"),
+        }
+    }
+}
+
+impl FromStr for ZoneJournalEntry {
+    type Err = ParseZoneJournalEntryError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_whitespace().collect::<Vec<_>>()[..] {
+            ["begin", "create"] => Ok(Self::Begin(ZoneJournalOperation::Create)),
+            ["begin", "receive"] => Ok(Self::Begin(ZoneJournalOperation::Receive)),
+            ["volume"] => Ok(Self::Volume),
+            ["extracted"] => Ok(Self::Extracted),
+            ["step", index] => match index.parse() {
+                Ok(index) => Ok(Self::Step(index)),
+                Err(_) => Err(ParseZoneJournalEntryError::UnknownEntry(value.to_string())),
+            },
+            ["commit"] => Ok(Self::Commit),
+            _ => Err(ParseZoneJournalEntryError::UnknownEntry(value.to_string())),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub struct ZoneJournalEntries {
+    entries: Vec<ZoneJournalEntry>,
+}
+
+impl ZoneJournalEntries {
+    pub fn entries(&self) -> &Vec<ZoneJournalEntry> {
+        &self.entries
+    }
+
+    pub fn operation(&self) -> Option<ZoneJournalOperation> {
+        match self.entries.first() {
+            Some(ZoneJournalEntry::Begin(operation)) => Some(*operation),
+            _ => None,
+        }
+    }
+
+    pub fn is_committed(&self) -> bool {
+        self.entries.last() == Some(&ZoneJournalEntry::Commit)
+    }
+
+    /// Returns whether the creation of the volume may have begun.
+    pub fn has_volume(&self) -> bool {
+        self.entries.contains(&ZoneJournalEntry::Volume)
+    }
+
+    /// Returns whether `from` has been extracted into the volume.
+    pub fn is_extracted(&self) -> bool {
+        self.entries.contains(&ZoneJournalEntry::Extracted)
+    }
+
+    /// Returns the number of create steps which have completed.
+    pub fn completed_steps(&self) -> usize {
+        self.entries
+            .iter()
+            .filter_map(|entry| match entry {
+                ZoneJournalEntry::Step(index) => Some(index + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Returns whether the operation can be resumed instead of being rolled back. Creations are
+    /// resumable once `from` has been extracted, which usually takes longest.
+    pub fn is_resumable(&self) -> bool {
+        self.operation() == Some(ZoneJournalOperation::Create) && self.is_extracted()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Constructor, Debug)]
+#[Constructor(visibility = pub(super))]
+pub struct ZoneJournal<T> {
+    zone: T,
+}
+
+impl ZoneJournal<&Zone> {
+    pub fn file_path(&self) -> PathBuf {
+        self.zone.paths().journal_file()
+    }
+
+    /// Returns the entries of an unfinished operation, or `None` if there is none.
+    pub fn entries(&self) -> Result<Option<ZoneJournalEntries>, ReadZoneJournalError> {
+        let content = match read_to_string(self.file_path()) {
+            Ok(content) => content,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+
+        // A crash may leave the last line incomplete, which is ignored since the side effect it
+        // was about to describe has not happened yet.
+        let complete = match content.rfind('\n') {
+            Some(index) => &content[..index],
+            None => "",
+        };
+
+        Ok(Some(ZoneJournalEntries {
+            entries: complete
+                .lines()
+                .map(ZoneJournalEntry::from_str)
+                .collect::<Result<Vec<_>, _>>()?,
+        }))
+    }
+
+    pub(super) fn begin(
+        &self,
+        operation: ZoneJournalOperation,
+    ) -> Result<(), WriteZoneJournalError> {
+        let path = self.file_path();
+
+        let file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(WriteZoneJournalError::AlreadyExisting)
+            }
+            Err(error) => return Err(error.into()),
+        };
+
+        Self::write(file, ZoneJournalEntry::Begin(operation))?;
+
+        if let Some(parent) = path.parent() {
+            File::open(parent)?.sync_all()?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn record(&self, entry: ZoneJournalEntry) -> Result<(), WriteZoneJournalError> {
+        Self::write(
+            OpenOptions::new().append(true).open(self.file_path())?,
+            entry,
+        )
+    }
+
+    fn write(mut file: File, entry: ZoneJournalEntry) -> Result<(), WriteZoneJournalError> {
+        writeln!(file, "{}", entry)?;
+        file.sync_data()?;
+
+        Ok(())
+    }
+
+    pub(super) fn destroy(&self) -> Result<(), DestroyZoneJournalError> {
+        remove_file(self.file_path())?;
+
+        Ok(())
+    }
+
+    pub(super) fn cleanup(&self) -> Result<(), CleanupZoneJournalError> {
+        let path = self.file_path();
+
+        if path.exists() {
+            remove_file(path)?;
+        }
+
+        Ok(())
+    }
+}
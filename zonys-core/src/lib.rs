@@ -8,8 +8,11 @@ mod error;
 mod handler;
 mod identifier;
 mod iterator;
+mod journal;
 mod lock;
 mod paths;
+mod snapshot;
+mod state;
 mod template;
 mod transmission;
 mod r#type;
@@ -22,9 +25,12 @@ pub use error::*;
 pub use handler::*;
 pub use identifier::*;
 pub use iterator::*;
+pub use journal::*;
 pub use lock::*;
 pub use paths::*;
 pub use r#type::*;
+pub use snapshot::*;
+pub use state::*;
 pub use template::*;
 pub use transmission::*;
 pub use volume::*;
@@ -33,14 +39,30 @@ pub use volume::*;
 
 use crate::template::TemplateEngine;
 use byteorder::{ReadBytesExt, WriteBytesExt};
+use jail::JailId;
+use log::warn;
 use regex::Regex;
+use std::ffi::OsStr;
 use std::fmt::Debug;
 use std::fs::read_dir;
+use std::io;
 use std::os::unix::io::AsRawFd;
 use std::panic::{catch_unwind, resume_unwind};
 use std::path::Path;
 use uuid::Uuid;
-use ztd::{Constructor, Method};
+use ztd::{Constructor, Display, Method};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+pub enum ZoneRecovery {
+    #[Display("rolled forward")]
+    RolledForward,
+    #[Display("resumed")]
+    Resumed,
+    #[Display("rolled back")]
+    RolledBack,
+}
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
@@ -48,7 +70,12 @@ use ztd::{Constructor, Method};
 #[Constructor(visibility = pub(self))]
 #[Method(accessors)]
 pub struct ZoneStatus {
+    state: ZoneState,
     running: bool,
+    jail_id: Option<JailId>,
+    volume_type: Option<ZoneVolumeType>,
+    volume_existing: bool,
+    configuration_existing: bool,
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -56,19 +83,40 @@ pub struct ZoneStatus {
 #[derive(Debug)]
 pub struct Zone {
     identifier: ZoneIdentifier,
+    lock_wait: ZoneLockWait,
 }
 
 impl Zone {
     fn new(identifier: ZoneIdentifier) -> Self {
-        Self { identifier }
+        Self {
+            identifier,
+            lock_wait: ZoneLockWait::default(),
+        }
     }
 }
 
+/// Returns a zone with a new identifier whose base path is `directory`, for tests.
+#[cfg(test)]
+pub(crate) fn test_zone(directory: &tempfile::TempDir) -> Zone {
+    Zone::new(ZoneIdentifier::new(
+        ZoneIdentifierBase::try_from(directory.path()).unwrap(),
+        ZoneIdentifierUuid::new_v4(),
+    ))
+}
+
 impl Zone {
     pub fn identifier(&self) -> &ZoneIdentifier {
         &self.identifier
     }
 
+    pub fn lock_wait(&self) -> ZoneLockWait {
+        self.lock_wait
+    }
+
+    pub fn set_lock_wait(&mut self, lock_wait: ZoneLockWait) {
+        self.lock_wait = lock_wait;
+    }
+
     pub fn paths(&self) -> ZonePaths<&Self> {
         ZonePaths::new(self)
     }
@@ -81,8 +129,46 @@ impl Zone {
         ZoneLock::new(self)
     }
 
+    pub fn lifecycle(&self) -> ZoneLifecycle<&Self> {
+        ZoneLifecycle::new(self)
+    }
+
+    pub fn journal(&self) -> ZoneJournal<&Self> {
+        ZoneJournal::new(self)
+    }
+
+    pub fn snapshots(&self) -> ZoneSnapshots<&Self> {
+        ZoneSnapshots::new(self)
+    }
+
     pub fn status(&self) -> Result<ZoneStatus, ReadZoneStatusError> {
-        Ok(ZoneStatus::new(false))
+        let configuration_existing = self.paths().configuration_file().is_file();
+        let volume = ZoneVolume::open(self)?;
+
+        let (running, jail_id) = match configuration_existing {
+            true => {
+                let r#type = self.r#type()?;
+
+                (r#type.running()?, r#type.jail_id()?)
+            }
+            false => (false, None),
+        };
+
+        let state = self.lifecycle().state()?;
+        let locked = match state {
+            Some(state) if state.is_transient() => self.lock().is_held()?,
+            _ => false,
+        };
+        let state = ZoneState::reconcile(state, running, locked);
+
+        Ok(ZoneStatus::new(
+            state,
+            running,
+            jail_id,
+            volume.as_ref().map(ZoneVolume::r#type),
+            volume.is_some(),
+            configuration_existing,
+        ))
     }
 
     pub fn r#type(&self) -> Result<ZoneType<&Self>, ReadZoneConfigurationError> {
@@ -91,6 +177,52 @@ impl Zone {
 }
 
 impl Zone {
+    /// Returns the status while the lock is held by the current process. Transient states can
+    /// only be left over from interrupted operations then and are treated as failed.
+    fn handle_status(&self) -> Result<ZoneStatus, ReadZoneStatusError> {
+        let mut status = self.status()?;
+
+        if status.state.is_transient() {
+            status.state = ZoneState::Failed;
+        }
+
+        Ok(status)
+    }
+
+    fn handle_failure(&self) {
+        if let Err(error) = self.lifecycle().set_state(ZoneState::Failed) {
+            warn!("Cannot mark zone {} as failed: {}", self.identifier, error);
+        }
+    }
+
+    /// Moves the zone into `state` while `function` runs and into `target` afterwards. If
+    /// `function` fails, the zone is marked as failed instead.
+    fn handle_transition<F, R, E>(
+        &self,
+        state: ZoneState,
+        target: ZoneState,
+        function: F,
+    ) -> Result<R, E>
+    where
+        F: FnOnce() -> Result<R, E>,
+        E: From<WriteZoneLifecycleError>,
+    {
+        self.lifecycle().set_state(state)?;
+
+        match function() {
+            Ok(value) => {
+                self.lifecycle().set_state(target)?;
+
+                Ok(value)
+            }
+            Err(error) => {
+                self.handle_failure();
+
+                Err(error)
+            }
+        }
+    }
+
     fn handle_create(
         &self,
         _configuration_path: &Path,
@@ -101,10 +233,23 @@ impl Zone {
         .clone()
         .unwrap_or_default();*/
 
-        self.configuration()
-            .set_directive(&configuration_directive)?;
-        let reader = self.configuration().reader()?;
-        self.r#type()?.create()?;
+        self.journal().begin(ZoneJournalOperation::Create)?;
+
+        let reader = self.handle_transition(ZoneState::Creating, ZoneState::Created, || {
+            {
+                let _lock = ZoneBaseLock::exclusive(&self.paths().base_directory())?;
+                self.configuration()
+                    .set_directive(&configuration_directive)?;
+            }
+
+            let reader = self.configuration().reader()?;
+            self.r#type()?.create()?;
+            self.journal().record(ZoneJournalEntry::Commit)?;
+
+            Ok::<_, CreateZoneError>(reader)
+        })?;
+
+        self.journal().destroy()?;
 
         if reader.start_after_create() {
             self.handle_start()?;
@@ -114,13 +259,43 @@ impl Zone {
     }
 
     fn handle_start(&self) -> Result<(), StartZoneError> {
-        self.r#type()?.start()?;
+        match self.handle_status()?.state {
+            ZoneState::Running => return Err(StartZoneError::AlreadyRunning),
+            state if !state.can_transition_to(ZoneState::Starting) => {
+                return Err(StartZoneError::InvalidState(state))
+            }
+            _ => {}
+        }
 
-        Ok(())
+        // Volumes are not mounted while their key is unloaded, so the zone would run on an empty
+        // root directory.
+        if let Some(volume) = ZoneVolume::open(self)? {
+            if !volume.is_key_loaded()? {
+                return Err(StartZoneError::KeyNotLoaded);
+            }
+        }
+
+        self.handle_transition(ZoneState::Starting, ZoneState::Running, || {
+            self.r#type()?.start()?;
+
+            Ok(())
+        })
     }
 
     fn handle_stop(&self) -> Result<bool, StopZoneError> {
-        self.r#type()?.stop()?;
+        match self.handle_status()?.state {
+            ZoneState::Created | ZoneState::Stopped => return Err(StopZoneError::NotRunning),
+            state if !state.can_transition_to(ZoneState::Stopping) => {
+                return Err(StopZoneError::InvalidState(state))
+            }
+            _ => {}
+        }
+
+        self.handle_transition(ZoneState::Stopping, ZoneState::Stopped, || {
+            self.r#type()?.stop()?;
+
+            Ok::<_, StopZoneError>(())
+        })?;
 
         let reader = self.configuration().reader()?;
 
@@ -133,61 +308,205 @@ impl Zone {
     }
 
     fn handle_destroy(&self) -> Result<(), DestroyZoneError> {
-        if self.status()?.running() {
-            return Err(DestroyZoneError::IsRunning);
+        let status = self.handle_status()?;
+
+        match status.state {
+            _ if status.running => return Err(DestroyZoneError::IsRunning),
+            state if !state.can_transition_to(ZoneState::Destroying) => {
+                return Err(DestroyZoneError::InvalidState(state))
+            }
+            _ => {}
         }
 
-        self.r#type()?.destroy()?;
+        self.lifecycle().set_state(ZoneState::Destroying)?;
+
+        let result = self
+            .r#type()
+            .map_err(DestroyZoneError::from)
+            .and_then(|r#type| Ok(r#type.destroy()?));
+
+        if let Err(error) = result {
+            self.handle_failure();
+
+            return Err(error);
+        }
+
+        let _lock = ZoneBaseLock::exclusive(&self.paths().base_directory())?;
         self.configuration().destroy()?;
+        self.lifecycle().destroy()?;
+        self.snapshots().destroy()?;
 
         Ok(())
     }
 
-    fn handle_send<T>(&self, writer: &mut T) -> Result<(), SendZoneError>
+    fn handle_send<T>(
+        &self,
+        writer: &mut T,
+        snapshot: Option<&str>,
+        base: Option<&str>,
+        options: &ZoneSendOptions,
+    ) -> Result<(), SendZoneError>
     where
         T: AsRawFd + 'static,
     {
+        let directive = match snapshot {
+            Some(name) => match self.snapshots().directive(name)? {
+                Some(directive) => Some(directive),
+                None => return Err(SendZoneError::SnapshotNotExisting(name.to_string())),
+            },
+            None => None,
+        };
+
+        if let Some(base) = base {
+            if !self.snapshots().exists(base) {
+                return Err(SendZoneError::SnapshotNotExisting(base.to_string()));
+            }
+        }
+
+        let header = ZoneTransmissionVersion2Header::new(
+            *self.identifier.uuid(),
+            snapshot.map(String::from),
+            base.map(String::from),
+        );
+
         let mut writer = ZoneTransmissionWriter::new(writer.as_raw_fd());
-        writer.write_u64::<ZoneTransmissionEndian>(ZONE_TRANSMISSION_MAGIC_NUMBER)?;
+        writer.write_u64::<ZoneTransmissionEndian>(ZONE_TRANSMISSION_VERSION2_MAGIC_NUMBER)?;
+        writer.serialize(&ZoneTransmissionHeader::Version2(header.clone()))?;
 
-        self.configuration().send(&mut writer)?;
-        self.r#type()?.send(&mut writer)?;
+        match directive {
+            Some(directive) => writer.serialize(&directive)?,
+            None => self.configuration().send(&mut writer)?,
+        };
+
+        self.r#type()?.send(&mut writer, &header, options)?;
 
         Ok(())
     }
 
-    fn handle_receive<T>(&self, reader: &mut T) -> Result<(), ReceiveZoneError>
-    where
-        T: AsRawFd + 'static,
-    {
-        let mut reader = ZoneTransmissionReader::new(reader.as_raw_fd());
+    fn handle_receive(
+        &self,
+        reader: &mut ZoneTransmissionReader,
+        header: &ZoneTransmissionVersion2Header,
+    ) -> Result<(), ReceiveZoneError> {
+        self.journal().begin(ZoneJournalOperation::Receive)?;
 
-        let magic_number: ZoneTransmissionMagicNumberLength =
-            reader.read_u64::<ZoneTransmissionEndian>()?;
+        self.handle_transition(ZoneState::Creating, ZoneState::Created, || {
+            ZoneConfiguration::receive(self, reader)?;
+            self.journal().record(ZoneJournalEntry::Volume)?;
+            ZoneType::receive(self, reader, header)?;
+
+            if let Some(name) = header.snapshot() {
+                self.handle_receive_snapshot(name)?;
+            }
+
+            self.journal().record(ZoneJournalEntry::Commit)?;
+
+            Ok::<_, ReceiveZoneError>(())
+        })?;
+
+        self.journal().destroy()?;
+
+        Ok(())
+    }
+
+    /// Applies a transmission containing the changes since a snapshot both sides have in common.
+    /// An interrupted update cannot be rolled back, so the zone is left as failed instead.
+    fn handle_receive_update(
+        &self,
+        reader: &mut ZoneTransmissionReader,
+        header: &ZoneTransmissionVersion2Header,
+    ) -> Result<(), ReceiveZoneError> {
+        let status = self.handle_status()?;
+
+        let target = match status.state {
+            _ if status.running => return Err(ReceiveZoneError::IsRunning),
+            state if !state.can_transition_to(ZoneState::Updating) => {
+                return Err(ReceiveZoneError::InvalidState(state))
+            }
+            ZoneState::Created => ZoneState::Created,
+            _ => ZoneState::Stopped,
+        };
+
+        if let Some(base) = header.base() {
+            if !self.snapshots().exists(base) {
+                return Err(ReceiveZoneError::SnapshotNotExisting(base.clone()));
+            }
+        }
 
-        if magic_number != ZONE_TRANSMISSION_MAGIC_NUMBER {
-            return Err(ReceiveZoneError::MissingMagicNumber);
+        self.handle_transition(ZoneState::Updating, target, || {
+            let directive = reader.deserialize::<ZoneConfigurationDirective>()?;
+            self.configuration().set_directive(&directive)?;
+            ZoneType::receive(self, reader, header)?;
+
+            if let Some(name) = header.snapshot() {
+                self.handle_receive_snapshot(name)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Records a received snapshot. ZFS volumes receive the snapshot itself, while it is taken
+    /// from the received content for directory volumes.
+    fn handle_receive_snapshot(&self, name: &str) -> Result<(), ReceiveZoneError> {
+        let volume = match ZoneVolume::open(self)? {
+            Some(volume) => volume,
+            None => return Err(ReceiveZoneError::VolumeNotExisting),
+        };
+
+        if !volume.has_snapshot(name)? {
+            volume.create_snapshot(name)?;
         }
 
-        ZoneConfiguration::receive(self, &mut reader)?;
-        ZoneType::receive(self, &mut reader)?;
+        self.snapshots()
+            .set_directive(name, &self.configuration().directive()?)?;
 
         Ok(())
     }
 
     fn cleanup(&self) -> Result<(), CleanupZoneError> {
+        self.handle_cleanup(true)
+    }
+
+    /// Removes everything belonging to the zone. The volume and jail are left alone unless
+    /// `with_volume` is set, which allows rolling back operations interrupted before creating them
+    /// without accessing any pool.
+    fn handle_cleanup(&self, with_volume: bool) -> Result<(), CleanupZoneError> {
         let mut cleanup_errors = Vec::default();
 
-        if let Ok(r#type) = self.r#type() {
-            if let Err(error) = r#type.cleanup() {
-                cleanup_errors.push(CleanupZoneError::from(error));
-            }
-        };
+        if with_volume {
+            match self.r#type() {
+                Ok(r#type) => {
+                    if let Err(error) = r#type.cleanup() {
+                        cleanup_errors.push(CleanupZoneError::from(error));
+                    }
+                }
+                // Without configuration, at least the volume is cleaned up, since it may have been
+                // created before the configuration was lost.
+                Err(_) => match ZoneVolume::open(self) {
+                    Ok(Some(volume)) => {
+                        if let Err(error) = volume.cleanup() {
+                            cleanup_errors.push(CleanupZoneError::from(error));
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(error) => cleanup_errors.push(CleanupZoneError::from(error)),
+                },
+            };
+        }
 
         if let Err(error) = self.configuration().cleanup() {
             cleanup_errors.push(CleanupZoneError::from(error));
         }
 
+        if let Err(error) = self.lifecycle().cleanup() {
+            cleanup_errors.push(CleanupZoneError::from(error));
+        }
+
+        if let Err(error) = self.snapshots().cleanup() {
+            cleanup_errors.push(CleanupZoneError::from(error));
+        }
+
         if let Err(error) = self.lock().cleanup() {
             cleanup_errors.push(CleanupZoneError::from(error));
         }
@@ -200,8 +519,158 @@ impl Zone {
             return Err(error);
         }
 
+        // The journal is removed last, so that an incomplete cleanup can be recovered later.
+        self.journal().cleanup()?;
+
         Ok(())
     }
+
+    fn handle_create_snapshot(&self, name: &str) -> Result<(), CreateZoneSnapshotError> {
+        if !self.snapshots().is_valid_name(name) {
+            return Err(CreateZoneSnapshotError::InvalidName(name.to_string()));
+        }
+
+        if self.snapshots().exists(name) {
+            return Err(CreateZoneSnapshotError::AlreadyExisting(name.to_string()));
+        }
+
+        let directive = self.configuration().directive()?;
+        let volume = match ZoneVolume::open(self)? {
+            Some(volume) => volume,
+            None => return Err(CreateZoneSnapshotError::VolumeNotExisting),
+        };
+
+        volume.create_snapshot(name)?;
+
+        // The configuration is recorded last, since it makes the snapshot visible.
+        if let Err(error) = self.snapshots().set_directive(name, &directive) {
+            if let Err(error) = volume.destroy_snapshot(name) {
+                warn!(
+                    "Cannot destroy snapshot {} of zone {}: {}",
+                    name, self.identifier, error
+                );
+            }
+
+            return Err(error.into());
+        }
+
+        Ok(())
+    }
+
+    fn handle_destroy_snapshot(&self, name: &str) -> Result<(), DestroyZoneSnapshotError> {
+        if !self.snapshots().exists(name) {
+            return Err(DestroyZoneSnapshotError::NotExisting(name.to_string()));
+        }
+
+        if let Some(volume) = ZoneVolume::open(self)? {
+            volume.destroy_snapshot(name)?;
+        }
+
+        self.snapshots().remove(name)?;
+
+        Ok(())
+    }
+
+    fn handle_rollback_snapshot(&self, name: &str) -> Result<(), RollbackZoneSnapshotError> {
+        if self.handle_status()?.running {
+            return Err(RollbackZoneSnapshotError::IsRunning);
+        }
+
+        let directive = match self.snapshots().directive(name)? {
+            Some(directive) => directive,
+            None => return Err(RollbackZoneSnapshotError::NotExisting(name.to_string())),
+        };
+
+        let volume = match ZoneVolume::open(self)? {
+            Some(volume) => volume,
+            None => return Err(RollbackZoneSnapshotError::VolumeNotExisting),
+        };
+
+        volume.rollback_snapshot(name)?;
+        self.configuration().set_directive(&directive)?;
+
+        // Rolling back may destroy more recent snapshots of the volume, whose records are stale.
+        for name in self.snapshots().names()? {
+            if !volume.has_snapshot(&name)? {
+                self.snapshots().remove(&name)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_load_key(&self) -> Result<(), LoadZoneKeyError> {
+        let volume = match ZoneVolume::open(self)? {
+            Some(volume) => volume,
+            None => return Err(LoadZoneKeyError::VolumeNotExisting),
+        };
+
+        volume.load_key()?;
+
+        Ok(())
+    }
+
+    fn handle_unload_key(&self) -> Result<(), UnloadZoneKeyError> {
+        if self.handle_status()?.running {
+            return Err(UnloadZoneKeyError::IsRunning);
+        }
+
+        let volume = match ZoneVolume::open(self)? {
+            Some(volume) => volume,
+            None => return Err(UnloadZoneKeyError::VolumeNotExisting),
+        };
+
+        volume.unload_key()?;
+
+        Ok(())
+    }
+
+    fn handle_recover(&self) -> Result<Option<ZoneRecovery>, RecoverZoneError> {
+        let entries = match self.journal().entries()? {
+            None => return Ok(None),
+            Some(entries) => entries,
+        };
+
+        // Operations are performed while holding the lock of the zone, which is released by the
+        // kernel as soon as the performing process exits.
+        if self.lock().is_held()? {
+            return Ok(None);
+        }
+
+        let recovery = match entries.is_committed() {
+            true => ZoneRecovery::RolledForward,
+            false if entries.is_resumable() => match self.handle_resume() {
+                Ok(()) => ZoneRecovery::Resumed,
+                Err(error) => {
+                    warn!("Cannot resume zone {}: {}", self.identifier, error);
+                    self.handle_cleanup(true)?;
+
+                    return Ok(Some(ZoneRecovery::RolledBack));
+                }
+            },
+            false => {
+                self.handle_cleanup(entries.has_volume())?;
+
+                return Ok(Some(ZoneRecovery::RolledBack));
+            }
+        };
+
+        self.lifecycle().set_state(ZoneState::Created)?;
+        self.lock().cleanup()?;
+        self.journal().destroy()?;
+
+        Ok(Some(recovery))
+    }
+
+    /// Continues an interrupted creation after the phases recorded in the journal.
+    fn handle_resume(&self) -> Result<(), ResumeZoneError> {
+        self.lock().hold(|zone| {
+            zone.r#type()?.create()?;
+            zone.journal().record(ZoneJournalEntry::Commit)?;
+
+            Ok(())
+        })?
+    }
 }
 
 impl Zone {
@@ -231,11 +700,13 @@ impl Zone {
         match result {
             Ok(Ok(())) => Ok(identifier),
             Ok(Err(error)) => {
+                let _lock = ZoneBaseLock::exclusive(base_path)?;
                 Self::new(identifier).cleanup()?;
                 Err(error)
             }
             Err(error) => {
-                // TODO: Check
+                // If the cleanup fails as well, the journal is left behind for recovery.
+                let _lock = ZoneBaseLock::exclusive(base_path)?;
                 Self::new(identifier).cleanup()?;
                 resume_unwind(error)
             }
@@ -262,35 +733,341 @@ impl Zone {
         self.lock().hold(|zone| zone.handle_destroy())?
     }
 
-    pub fn send<T>(&self, writer: &mut T) -> Result<(), SendZoneError>
+    pub fn create_snapshot(&self, name: &str) -> Result<(), CreateZoneSnapshotError> {
+        self.lock().hold(|zone| zone.handle_create_snapshot(name))?
+    }
+
+    pub fn destroy_snapshot(&self, name: &str) -> Result<(), DestroyZoneSnapshotError> {
+        self.lock()
+            .hold(|zone| zone.handle_destroy_snapshot(name))?
+    }
+
+    pub fn rollback_snapshot(&self, name: &str) -> Result<(), RollbackZoneSnapshotError> {
+        self.lock()
+            .hold(|zone| zone.handle_rollback_snapshot(name))?
+    }
+
+    /// Loads the key of the encrypted volume and mounts it.
+    pub fn load_key(&self) -> Result<(), LoadZoneKeyError> {
+        self.lock().hold(|zone| zone.handle_load_key())?
+    }
+
+    /// Unmounts the encrypted volume and unloads its key, which requires the zone to be stopped.
+    pub fn unload_key(&self) -> Result<(), UnloadZoneKeyError> {
+        self.lock().hold(|zone| zone.handle_unload_key())?
+    }
+
+    pub fn send<T>(&self, writer: &mut T, options: &ZoneSendOptions) -> Result<(), SendZoneError>
     where
         T: AsRawFd + 'static,
     {
-        self.lock().hold(|zone| zone.handle_send(writer))?
+        self.lock()
+            .hold(|zone| zone.handle_send(writer, None, None, options))?
     }
 
+    /// Sends the zone as it was when the snapshot was taken. With a base snapshot, only the
+    /// changes since the base are sent, which requires the receiver to have received the base.
+    pub fn send_snapshot<T>(
+        &self,
+        writer: &mut T,
+        name: &str,
+        base: Option<&str>,
+        options: &ZoneSendOptions,
+    ) -> Result<(), SendZoneError>
+    where
+        T: AsRawFd + 'static,
+    {
+        self.lock()
+            .hold(|zone| zone.handle_send(writer, Some(name), base, options))?
+    }
+
+    /// Receives a zone. Transmissions with a base snapshot update the zone they were sent from,
+    /// others create a new zone, which keeps the identifier of the sent zone unless it is taken.
     pub fn receive<T>(base_path: &Path, reader: &mut T) -> Result<ZoneIdentifier, ReceiveZoneError>
     where
         T: AsRawFd + 'static,
     {
-        let zone = Self::new(ZoneIdentifier::new(base_path.try_into()?, Uuid::new_v4()));
+        let mut reader = ZoneTransmissionReader::new(reader.as_raw_fd());
+
+        let magic_number: ZoneTransmissionMagicNumberLength =
+            reader.read_u64::<ZoneTransmissionEndian>()?;
 
-        zone.lock().hold(|zone| zone.handle_receive(reader))??;
+        let header = match magic_number {
+            ZONE_TRANSMISSION_VERSION2_MAGIC_NUMBER => {
+                match reader.deserialize::<ZoneTransmissionHeader>()? {
+                    ZoneTransmissionHeader::Version2(header) => header,
+                    ZoneTransmissionHeader::Version1(_) => {
+                        return Err(ReceiveZoneError::UnsupportedHeader)
+                    }
+                }
+            }
+            ZONE_TRANSMISSION_MAGIC_NUMBER => {
+                ZoneTransmissionVersion2Header::new(Uuid::new_v4(), None, None)
+            }
+            _ => return Err(ReceiveZoneError::MissingMagicNumber),
+        };
+
+        let mut zone = Self::new(ZoneIdentifier::new(
+            base_path.try_into()?,
+            *header.identifier(),
+        ));
+
+        for name in [header.snapshot(), header.base()].into_iter().flatten() {
+            if !zone.snapshots().is_valid_name(name) {
+                return Err(ReceiveZoneError::InvalidSnapshotName(name.clone()));
+            }
+        }
+
+        let existing = zone.paths().configuration_file().is_file();
+
+        match header.base() {
+            Some(_) if !existing => return Err(ReceiveZoneError::ZoneNotExisting),
+            Some(_) => {
+                zone.lock()
+                    .hold(|zone| zone.handle_receive_update(&mut reader, &header))??;
+            }
+            None => {
+                if existing {
+                    zone = Self::new(ZoneIdentifier::new(base_path.try_into()?, Uuid::new_v4()));
+                }
+
+                zone.lock()
+                    .hold(|zone| zone.handle_receive(&mut reader, &header))??;
+            }
+        };
 
         Ok(zone.identifier)
     }
 
+    /// Rolls unfinished operations of crashed processes forward or back and returns the affected
+    /// zones.
+    pub fn recover(
+        base_path: &Path,
+    ) -> Result<Vec<(ZoneIdentifier, ZoneRecovery)>, RecoverZoneError> {
+        let entries = match read_dir(base_path) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::default()),
+            Err(error) => return Err(error.into()),
+        };
+
+        let _lock = ZoneBaseLock::exclusive(base_path)?;
+        let mut recoveries = Vec::default();
+
+        for entry in entries {
+            let path = entry?.path();
+
+            if path.extension() != Some(OsStr::new(ZONE_JOURNAL_PATH_EXTENSION)) {
+                continue;
+            }
+
+            let identifier = ZoneIdentifier::try_from(path.strip_prefix("/")?.with_extension(""))?;
+            let zone = Self::new(identifier.clone());
+
+            if let Some(recovery) = zone.handle_recover()? {
+                recoveries.push((identifier, recovery));
+            }
+        }
+
+        Ok(recoveries)
+    }
+
     pub fn all(base_path: &Path) -> Result<AllZoneIterator, AllZoneIteratorError> {
-        Ok(AllZoneIterator::new(read_dir(base_path)?))
+        let lock = ZoneBaseLock::shared(base_path)?;
+
+        Ok(AllZoneIterator::new(read_dir(base_path)?, lock))
     }
 
     pub fn r#match(
         base_path: &Path,
         regular_expression: &String,
     ) -> Result<MatchZoneIterator, MatchZoneIteratorError> {
+        let lock = ZoneBaseLock::shared(base_path)?;
+
         Ok(MatchZoneIterator::new(
-            AllZoneIterator::new(read_dir(base_path)?),
+            AllZoneIterator::new(read_dir(base_path)?, lock),
             Regex::new(&format!("^{}$", regular_expression))?,
         ))
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(all(test, feature = "fake-zfs"))]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir, write};
+    use std::process;
+    use std::rc::Rc;
+    use tempfile::{tempdir, TempDir};
+    use zfs::backend::{self, FakeBackend};
+
+    fn zone(directory: &TempDir, entries: &[ZoneJournalEntry]) -> Zone {
+        backend::replace(Rc::new(FakeBackend::default()));
+
+        let zone = test_zone(directory);
+
+        write(zone.paths().configuration_file(), "").unwrap();
+        create_dir(zone.paths().root_directory()).unwrap();
+        zone.lifecycle().set_state(ZoneState::Creating).unwrap();
+        zone.journal().begin(ZoneJournalOperation::Create).unwrap();
+
+        for entry in entries {
+            zone.journal().record(*entry).unwrap();
+        }
+
+        zone
+    }
+
+    #[test]
+    fn ignores_zones_without_journal() {
+        let directory = tempdir().unwrap();
+        let zone = zone(&directory, &[]);
+        zone.journal().destroy().unwrap();
+
+        assert_eq!(zone.handle_recover().unwrap(), None);
+    }
+
+    #[test]
+    fn skips_operations_holding_the_lock() {
+        let directory = tempdir().unwrap();
+        let zone = zone(&directory, &[]);
+        let mut lock = zone.lock();
+        lock.acquire().unwrap();
+
+        assert_eq!(zone.handle_recover().unwrap(), None);
+        assert!(zone.journal().file_path().exists());
+
+        lock.release().unwrap();
+    }
+
+    #[test]
+    fn recovers_operations_of_recorded_but_unlocking_processes() {
+        let directory = tempdir().unwrap();
+        let zone = zone(&directory, &[]);
+
+        // A recorded process which is alive, e.g. since its identifier has been reused, does not
+        // hold the lock.
+        write(zone.lock().file_path(), format!("{}\n", process::id())).unwrap();
+
+        assert_eq!(
+            zone.handle_recover().unwrap(),
+            Some(ZoneRecovery::RolledBack)
+        );
+        assert!(!zone.lock().file_path().exists());
+    }
+
+    #[test]
+    fn rolls_back_operations_without_volume() {
+        let directory = tempdir().unwrap();
+        let zone = zone(&directory, &[]);
+
+        assert_eq!(
+            zone.handle_recover().unwrap(),
+            Some(ZoneRecovery::RolledBack)
+        );
+        assert!(!zone.paths().configuration_file().exists());
+        assert!(!zone.lifecycle().file_path().exists());
+        assert!(!zone.journal().file_path().exists());
+        assert!(zone.paths().root_directory().exists());
+    }
+
+    #[test]
+    fn rolls_back_operations_with_volume() {
+        let directory = tempdir().unwrap();
+        let zone = zone(&directory, &[ZoneJournalEntry::Volume]);
+        write(zone.paths().configuration_file(), "invalid").unwrap();
+
+        assert_eq!(
+            zone.handle_recover().unwrap(),
+            Some(ZoneRecovery::RolledBack)
+        );
+        assert!(!zone.paths().configuration_file().exists());
+        assert!(!zone.paths().root_directory().exists());
+        assert!(!zone.journal().file_path().exists());
+    }
+
+    #[test]
+    fn rolls_back_extracted_operations_which_cannot_be_resumed() {
+        let directory = tempdir().unwrap();
+        let zone = zone(
+            &directory,
+            &[ZoneJournalEntry::Volume, ZoneJournalEntry::Extracted],
+        );
+        write(zone.paths().configuration_file(), "invalid").unwrap();
+
+        assert_eq!(
+            zone.handle_recover().unwrap(),
+            Some(ZoneRecovery::RolledBack)
+        );
+        assert!(!zone.paths().configuration_file().exists());
+        assert!(!zone.paths().root_directory().exists());
+        assert!(!zone.journal().file_path().exists());
+    }
+
+    #[cfg(feature = "fake-jail")]
+    #[test]
+    fn resumes_extracted_operations_after_completed_steps() {
+        let directory = tempdir().unwrap();
+        let zone = zone(
+            &directory,
+            &[
+                ZoneJournalEntry::Volume,
+                ZoneJournalEntry::Extracted,
+                ZoneJournalEntry::Step(0),
+            ],
+        );
+        let backend = Rc::new(jail::backend::FakeBackend::default());
+        jail::backend::replace(backend.clone());
+
+        write(
+            zone.paths().configuration_file(),
+            "
+version: experimental
+type: jail
+volume: directory
+execute:
+  create:
+    on:
+      - program: /bin/first
+      - program: /bin/second
+",
+        )
+        .unwrap();
+
+        assert_eq!(zone.handle_recover().unwrap(), Some(ZoneRecovery::Resumed));
+        assert_eq!(zone.lifecycle().state().unwrap(), Some(ZoneState::Created));
+        assert!(zone.paths().configuration_file().exists());
+        assert!(zone.paths().root_directory().exists());
+        assert!(!zone.journal().file_path().exists());
+        assert!(!zone.lock().file_path().exists());
+        assert_eq!(
+            backend.calls()[1..],
+            [
+                jail::backend::FakeJailCall::Execute {
+                    id: 1,
+                    program: String::from("/bin/second"),
+                    arguments: Vec::default(),
+                },
+                jail::backend::FakeJailCall::Destroy { id: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn rolls_forward_committed_operations() {
+        let directory = tempdir().unwrap();
+        let zone = zone(
+            &directory,
+            &[ZoneJournalEntry::Volume, ZoneJournalEntry::Commit],
+        );
+
+        assert_eq!(
+            zone.handle_recover().unwrap(),
+            Some(ZoneRecovery::RolledForward)
+        );
+        assert_eq!(zone.lifecycle().state().unwrap(), Some(ZoneState::Created));
+        assert!(zone.paths().configuration_file().exists());
+        assert!(zone.paths().root_directory().exists());
+        assert!(!zone.journal().file_path().exists());
+    }
+}
@@ -1,9 +1,28 @@
 use crate::Zone;
-use std::fs::remove_file;
-use std::fs::File;
-use std::io;
-use std::path::PathBuf;
-use ztd::{Constructor, Display, Error, From};
+use log::warn;
+use nix::errno::Errno;
+use nix::fcntl::{flock, FlockArg};
+use nix::sys::signal::kill;
+use nix::unistd::Pid;
+use std::env::args;
+use std::fmt::{self, Formatter};
+use std::fs::{metadata, remove_file, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use ztd::{Display, Error, From};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const ZONE_LOCK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub const ZONE_BASE_LOCK_FILE_NAME: &str = ".lock";
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
@@ -11,8 +30,22 @@ use ztd::{Constructor, Display, Error, From};
 #[From(unnamed)]
 pub enum AcquireZoneLockError {
     IOError(io::Error),
-    #[Display("Zone is already locked")]
-    AlreadyLocked,
+    Errno(Errno),
+    #[Display("Zone is already locked by {value}")]
+    #[From(skip)]
+    AlreadyLocked(ZoneLockHolder),
+    #[Display("Timed out waiting for the lock held by {value}")]
+    #[From(skip)]
+    Timeout(ZoneLockHolder),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum AcquireZoneBaseLockError {
+    IOError(io::Error),
+    Errno(Errno),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -44,10 +77,79 @@ pub enum HoldZoneLockError {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Constructor, Debug)]
-#[Constructor(visibility = pub(super))]
+/// Describes how long acquiring a lock held by another process waits before giving up.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ZoneLockWait {
+    #[default]
+    None,
+    Indefinitely,
+    Timeout(Duration),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The process recorded in a lock file.
+#[derive(Clone, Debug, Default)]
+pub struct ZoneLockHolder {
+    process_identifier: Option<u32>,
+    command: Option<String>,
+}
+
+impl ZoneLockHolder {
+    fn parse(content: &str) -> Self {
+        let mut lines = content.lines();
+
+        Self {
+            process_identifier: lines.next().and_then(|line| line.trim().parse().ok()),
+            command: lines.next().map(String::from),
+        }
+    }
+
+    pub fn process_identifier(&self) -> Option<u32> {
+        self.process_identifier
+    }
+
+    pub fn command(&self) -> Option<&String> {
+        self.command.as_ref()
+    }
+
+    /// Returns whether the recorded process does not exist anymore.
+    pub fn is_stale(&self) -> bool {
+        let process_identifier = match self.process_identifier.map(i32::try_from) {
+            Some(Ok(process_identifier)) => process_identifier,
+            _ => return false,
+        };
+
+        kill(Pid::from_raw(process_identifier), None) == Err(Errno::ESRCH)
+    }
+}
+
+impl fmt::Display for ZoneLockHolder {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match (&self.process_identifier, &self.command) {
+            (Some(process_identifier), Some(command)) => {
+                write!(formatter, "process {} ({})", process_identifier, command)
+            }
+            (Some(process_identifier), None) => write!(formatter, "process {}", process_identifier),
+            (None, _) => write!(formatter, "an unknown process"),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A lock of a zone based on `flock`. The lock file records the holding process, but the lock
+/// itself is the `flock` on it, which the kernel releases as soon as the holder exits.
+#[derive(Debug)]
 pub struct ZoneLock<T> {
     zone: T,
+    file: Option<File>,
+}
+
+impl<T> ZoneLock<T> {
+    pub(super) fn new(zone: T) -> Self {
+        Self { zone, file: None }
+    }
 }
 
 impl ZoneLock<&Zone> {
@@ -55,28 +157,122 @@ impl ZoneLock<&Zone> {
         self.zone.paths().lock_file()
     }
 
-    pub(super) fn acquire(&mut self) -> Result<(), AcquireZoneLockError> {
-        let path = self.file_path();
+    /// Returns the recorded holder of the lock file, if there is any.
+    pub fn holder(&self) -> Result<Option<ZoneLockHolder>, io::Error> {
+        match File::open(self.file_path()) {
+            Ok(mut file) => {
+                let mut content = String::default();
+                file.read_to_string(&mut content)?;
 
-        if path.exists() {
-            return Err(AcquireZoneLockError::AlreadyLocked);
+                Ok(Some(ZoneLockHolder::parse(&content)))
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
         }
+    }
 
-        // TODO: Implement concurrency aware acquire
-        File::create(&path)?;
+    /// Returns whether any process currently holds the lock.
+    pub fn is_held(&self) -> Result<bool, io::Error> {
+        let file = match File::open(self.file_path()) {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(error) => return Err(error),
+        };
 
-        Ok(())
+        match flock(file.as_raw_fd(), FlockArg::LockSharedNonblock) {
+            Ok(()) => Ok(false),
+            Err(Errno::EWOULDBLOCK) => Ok(true),
+            Err(error) => Err(error.into()),
+        }
     }
 
-    pub(super) fn release(&mut self) -> Result<(), ReleaseZoneLockError> {
+    /// Tries to lock the file without blocking. The file may be removed by a releasing holder
+    /// between opening and locking it, so the lock only counts if the path still refers to the
+    /// locked file.
+    fn try_acquire(&self) -> Result<Option<File>, AcquireZoneLockError> {
         let path = self.file_path();
 
-        if !path.exists() {
-            return Err(ReleaseZoneLockError::NotLocked);
+        loop {
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)?;
+
+            match flock(file.as_raw_fd(), FlockArg::LockExclusiveNonblock) {
+                Ok(()) => {}
+                Err(Errno::EWOULDBLOCK) => return Ok(None),
+                Err(error) => return Err(error.into()),
+            }
+
+            match metadata(&path) {
+                Ok(metadata) if metadata.ino() == file.metadata()?.ino() => return Ok(Some(file)),
+                Ok(_) => continue,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+
+    pub(super) fn acquire(&mut self) -> Result<(), AcquireZoneLockError> {
+        let started = Instant::now();
+
+        let mut file = loop {
+            if let Some(file) = self.try_acquire()? {
+                break file;
+            }
+
+            let holder = self.holder()?.unwrap_or_default();
+
+            match self.zone.lock_wait() {
+                ZoneLockWait::None => return Err(AcquireZoneLockError::AlreadyLocked(holder)),
+                ZoneLockWait::Timeout(timeout) if started.elapsed() >= timeout => {
+                    return Err(AcquireZoneLockError::Timeout(holder))
+                }
+                ZoneLockWait::Timeout(_) | ZoneLockWait::Indefinitely => {
+                    sleep(ZONE_LOCK_POLL_INTERVAL)
+                }
+            }
+        };
+
+        let mut content = String::default();
+        file.read_to_string(&mut content)?;
+
+        let previous = ZoneLockHolder::parse(&content);
+
+        if previous.is_stale() {
+            warn!(
+                "Taking over stale lock of zone {} held by {}",
+                self.zone.identifier(),
+                previous
+            );
         }
 
-        // TODO: Implement concurrency aware release
-        remove_file(&path)?;
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        writeln!(
+            file,
+            "{}\n{}",
+            process::id(),
+            args().collect::<Vec<_>>().join(" ")
+        )?;
+
+        self.file = Some(file);
+
+        Ok(())
+    }
+
+    pub(super) fn release(&mut self) -> Result<(), ReleaseZoneLockError> {
+        let file = match self.file.take() {
+            Some(file) => file,
+            None => return Err(ReleaseZoneLockError::NotLocked),
+        };
+
+        // The file is removed while it is still locked, so that no other process can lock it in
+        // between. Processes waiting on the removed file notice it when they succeed.
+        remove_file(self.file_path())?;
+        drop(file);
 
         Ok(())
     }
@@ -102,3 +298,191 @@ impl ZoneLock<&Zone> {
         Ok(value)
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A lock of all zones within a base path. Listing zones holds it shared, while publishing or
+/// removing zone files holds it exclusively. It is released when dropped.
+#[derive(Debug)]
+pub struct ZoneBaseLock {
+    _file: File,
+}
+
+impl ZoneBaseLock {
+    pub fn file_path(base_path: &Path) -> PathBuf {
+        base_path.join(ZONE_BASE_LOCK_FILE_NAME)
+    }
+
+    fn acquire(base_path: &Path, argument: FlockArg) -> Result<Self, AcquireZoneBaseLockError> {
+        let path = Self::file_path(base_path);
+
+        // Opening an existing file read-only allows unprivileged users to list zones.
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)?,
+            Err(error) => return Err(error.into()),
+        };
+
+        flock(file.as_raw_fd(), argument)?;
+
+        Ok(Self { _file: file })
+    }
+
+    pub fn shared(base_path: &Path) -> Result<Self, AcquireZoneBaseLockError> {
+        Self::acquire(base_path, FlockArg::LockShared)
+    }
+
+    pub fn exclusive(base_path: &Path) -> Result<Self, AcquireZoneBaseLockError> {
+        Self::acquire(base_path, FlockArg::LockExclusive)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_zone;
+    use std::fs::write;
+    use std::process::Command;
+    use std::thread::scope;
+    use tempfile::tempdir;
+
+    /// Returns another handle of `zone`, as another process would open it.
+    fn waiting_zone(zone: &Zone, lock_wait: ZoneLockWait) -> Zone {
+        let mut waiting_zone = Zone::new(zone.identifier().clone());
+        waiting_zone.set_lock_wait(lock_wait);
+
+        waiting_zone
+    }
+
+    #[test]
+    fn acquires_and_releases_lock() {
+        let directory = tempdir().unwrap();
+        let zone = test_zone(&directory);
+        let mut lock = zone.lock();
+
+        assert!(!lock.is_held().unwrap());
+
+        lock.acquire().unwrap();
+
+        assert!(lock.is_held().unwrap());
+        assert_eq!(
+            lock.holder().unwrap().unwrap().process_identifier(),
+            Some(process::id())
+        );
+        assert!(matches!(
+            zone.lock().acquire(),
+            Err(AcquireZoneLockError::AlreadyLocked(_))
+        ));
+
+        lock.release().unwrap();
+
+        assert!(!lock.file_path().exists());
+        assert!(!lock.is_held().unwrap());
+        assert!(matches!(
+            lock.release(),
+            Err(ReleaseZoneLockError::NotLocked)
+        ));
+    }
+
+    #[test]
+    fn times_out_waiting_for_lock() {
+        let directory = tempdir().unwrap();
+        let holding_zone = test_zone(&directory);
+        let waiting_zone = waiting_zone(
+            &holding_zone,
+            ZoneLockWait::Timeout(Duration::from_millis(200)),
+        );
+        let mut lock = holding_zone.lock();
+        lock.acquire().unwrap();
+
+        let started = Instant::now();
+
+        match waiting_zone.lock().acquire() {
+            Err(AcquireZoneLockError::Timeout(holder)) => {
+                assert_eq!(holder.process_identifier(), Some(process::id()))
+            }
+            result => panic!("{:?}", result),
+        }
+
+        assert!(started.elapsed() >= Duration::from_millis(200));
+
+        lock.release().unwrap();
+    }
+
+    #[test]
+    fn locks_file_recreated_after_release_while_waiting() {
+        let directory = tempdir().unwrap();
+        let holding_zone = test_zone(&directory);
+        let waiting_zone = waiting_zone(
+            &holding_zone,
+            ZoneLockWait::Timeout(Duration::from_secs(10)),
+        );
+
+        scope(|scope| {
+            let mut holding_lock = holding_zone.lock();
+            holding_lock.acquire().unwrap();
+
+            scope.spawn(move || {
+                sleep(Duration::from_millis(300));
+                holding_lock.release().unwrap();
+            });
+
+            let started = Instant::now();
+            let mut waiting_lock = waiting_zone.lock();
+            waiting_lock.acquire().unwrap();
+
+            assert!(started.elapsed() >= Duration::from_millis(300));
+
+            let inode = metadata(waiting_lock.file_path()).unwrap().ino();
+            assert_eq!(
+                inode,
+                waiting_lock
+                    .file
+                    .as_ref()
+                    .unwrap()
+                    .metadata()
+                    .unwrap()
+                    .ino()
+            );
+
+            waiting_lock.release().unwrap();
+        });
+    }
+
+    #[test]
+    fn takes_over_lock_of_exited_process() {
+        let directory = tempdir().unwrap();
+        let zone = test_zone(&directory);
+        let mut child = Command::new("true").spawn().unwrap();
+        let process_identifier = child.id();
+        child.wait().unwrap();
+
+        write(
+            zone.lock().file_path(),
+            format!("{}\ntrue\n", process_identifier),
+        )
+        .unwrap();
+
+        let holder = zone.lock().holder().unwrap().unwrap();
+        assert_eq!(holder.process_identifier(), Some(process_identifier));
+        assert_eq!(holder.command().map(String::as_str), Some("true"));
+        assert!(holder.is_stale());
+        assert!(!zone.lock().is_held().unwrap());
+
+        let mut lock = zone.lock();
+        lock.acquire().unwrap();
+
+        assert_eq!(
+            lock.holder().unwrap().unwrap().process_identifier(),
+            Some(process::id())
+        );
+
+        lock.release().unwrap();
+    }
+}
@@ -12,6 +12,18 @@ pub const ZONE_CONFIGURATION_PATH_EXTENSION: &str = "yaml";
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+pub const ZONE_STATE_PATH_EXTENSION: &str = "state";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub const ZONE_JOURNAL_PATH_EXTENSION: &str = "journal";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub const ZONE_SNAPSHOTS_PATH_EXTENSION: &str = "snapshots";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Constructor, Debug)]
 #[Constructor(visibility = pub(super))]
 pub struct ZonePaths<T> {
@@ -23,6 +35,13 @@ impl<'a> ZonePaths<&'a Zone> {
         self.zone.identifier().clone().into()
     }
 
+    pub fn base_directory(&self) -> PathBuf {
+        self.root_directory()
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/"))
+    }
+
     pub fn lock_file(&self) -> PathBuf {
         self.root_directory()
             .parent()
@@ -46,4 +65,40 @@ impl<'a> ZonePaths<&'a Zone> {
                 ZONE_CONFIGURATION_PATH_EXTENSION,
             ))
     }
+
+    pub fn state_file(&self) -> PathBuf {
+        self.root_directory()
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/"))
+            .join(format!(
+                "{}.{}",
+                self.zone.identifier().uuid(),
+                ZONE_STATE_PATH_EXTENSION,
+            ))
+    }
+
+    pub fn journal_file(&self) -> PathBuf {
+        self.root_directory()
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/"))
+            .join(format!(
+                "{}.{}",
+                self.zone.identifier().uuid(),
+                ZONE_JOURNAL_PATH_EXTENSION,
+            ))
+    }
+
+    pub fn snapshots_directory(&self) -> PathBuf {
+        self.root_directory()
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/"))
+            .join(format!(
+                "{}.{}",
+                self.zone.identifier().uuid(),
+                ZONE_SNAPSHOTS_PATH_EXTENSION,
+            ))
+    }
 }
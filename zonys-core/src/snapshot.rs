@@ -0,0 +1,284 @@
+use crate::{Zone, ZoneConfigurationDirective, ZONE_CONFIGURATION_PATH_EXTENSION};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{from_reader, to_writer};
+use std::ffi::OsStr;
+use std::fs::{create_dir_all, read_dir, remove_dir_all, remove_file, File, Permissions};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use tempfile::Builder;
+use ztd::{Constructor, Display, Error, From};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Snapshot records are readable by everyone like the configuration of their zone.
+const ZONE_SNAPSHOT_FILE_MODE: u32 = 0o644;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum ReadZoneSnapshotsError {
+    IOError(io::Error),
+    YamlError(serde_yaml::Error),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum WriteZoneSnapshotsError {
+    IOError(io::Error),
+    YamlError(serde_yaml::Error),
+    ReadZoneSnapshotsError(ReadZoneSnapshotsError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum DestroyZoneSnapshotsError {
+    IOError(io::Error),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum CleanupZoneSnapshotsError {
+    IOError(io::Error),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The record of a snapshot. Sequence numbers increase with every snapshot taken or received,
+/// since modification times do not keep the order snapshots were taken in.
+#[derive(Debug, Deserialize, Serialize)]
+struct ZoneSnapshotRecord {
+    sequence: u64,
+    configuration: ZoneConfigurationDirective,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The snapshots of a zone. Every snapshot is recorded by the configuration of the zone at the
+/// time it was taken, while the content of the volume is kept by the volume itself.
+#[derive(Constructor, Debug)]
+#[Constructor(visibility = pub(super))]
+pub struct ZoneSnapshots<T> {
+    zone: T,
+}
+
+impl ZoneSnapshots<&Zone> {
+    /// Returns whether `name` can be used for a snapshot, which are the characters allowed in ZFS
+    /// snapshot names without a leading dot.
+    pub fn is_valid_name(&self, name: &str) -> bool {
+        !name.is_empty()
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | ':' | '.'))
+    }
+
+    pub fn directory_path(&self) -> PathBuf {
+        self.zone.paths().snapshots_directory()
+    }
+
+    pub fn file_path(&self, name: &str) -> PathBuf {
+        self.directory_path()
+            .join(format!("{}.{}", name, ZONE_CONFIGURATION_PATH_EXTENSION))
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        self.file_path(name).is_file()
+    }
+
+    /// Returns the names of all snapshots, from the oldest to the most recent one.
+    pub fn names(&self) -> Result<Vec<String>, ReadZoneSnapshotsError> {
+        Ok(self.records()?.into_iter().map(|(_, name)| name).collect())
+    }
+
+    /// Returns the configuration recorded by the snapshot, or `None` if it does not exist.
+    pub fn directive(
+        &self,
+        name: &str,
+    ) -> Result<Option<ZoneConfigurationDirective>, ReadZoneSnapshotsError> {
+        Ok(self.record(name)?.map(|record| record.configuration))
+    }
+
+    /// Returns the sequence numbers and names of all snapshots, ordered by sequence number.
+    fn records(&self) -> Result<Vec<(u64, String)>, ReadZoneSnapshotsError> {
+        let entries = match read_dir(self.directory_path()) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::default()),
+            Err(error) => return Err(error.into()),
+        };
+
+        let mut snapshots = Vec::default();
+
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+
+            if path.extension() != Some(OsStr::new(ZONE_CONFIGURATION_PATH_EXTENSION)) {
+                continue;
+            }
+
+            let name = match path.file_stem().and_then(OsStr::to_str) {
+                Some(name) if self.is_valid_name(name) => name.to_string(),
+                _ => continue,
+            };
+
+            if let Some(record) = self.record(&name)? {
+                snapshots.push((record.sequence, name));
+            }
+        }
+
+        snapshots.sort();
+
+        Ok(snapshots)
+    }
+
+    fn record(&self, name: &str) -> Result<Option<ZoneSnapshotRecord>, ReadZoneSnapshotsError> {
+        match File::open(self.file_path(name)) {
+            Ok(file) => Ok(Some(from_reader(BufReader::new(file))?)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub(super) fn set_directive(
+        &self,
+        name: &str,
+        directive: &ZoneConfigurationDirective,
+    ) -> Result<(), WriteZoneSnapshotsError> {
+        let directory_path = self.directory_path();
+        create_dir_all(&directory_path)?;
+
+        // Overwritten records keep their place, while new ones are placed after all others.
+        let sequence = match self.record(name)? {
+            Some(record) => record.sequence,
+            None => match self.records()?.last() {
+                Some((sequence, _)) => sequence + 1,
+                None => 0,
+            },
+        };
+
+        let record = ZoneSnapshotRecord {
+            sequence,
+            configuration: directive.clone(),
+        };
+
+        let mut file = Builder::new()
+            .permissions(Permissions::from_mode(ZONE_SNAPSHOT_FILE_MODE))
+            .tempfile_in(&directory_path)?;
+
+        {
+            let mut writer = BufWriter::new(file.as_file_mut());
+            to_writer(&mut writer, &record)?;
+            writer.flush()?;
+        }
+
+        file.as_file().sync_all()?;
+        file.persist(self.file_path(name))
+            .map_err(io::Error::from)?;
+
+        Ok(())
+    }
+
+    pub(super) fn remove(&self, name: &str) -> Result<(), WriteZoneSnapshotsError> {
+        remove_file(self.file_path(name))?;
+
+        Ok(())
+    }
+
+    /// Removes all snapshot records, including the archives of directory volumes stored beside
+    /// them.
+    pub(super) fn destroy(&self) -> Result<(), DestroyZoneSnapshotsError> {
+        let path = self.directory_path();
+
+        if path.exists() {
+            remove_dir_all(path)?;
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn cleanup(&self) -> Result<(), CleanupZoneSnapshotsError> {
+        let path = self.directory_path();
+
+        if path.exists() {
+            remove_dir_all(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_zone;
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    #[test]
+    fn validates_names() {
+        let directory = tempdir().unwrap();
+        let zone = test_zone(&directory);
+
+        for name in ["daily", "2024-01-01T00:00", "before_update.1"] {
+            assert!(zone.snapshots().is_valid_name(name), "{}", name);
+        }
+
+        for name in ["", ".hidden", "a/b", "..", "with space", "zone@snapshot"] {
+            assert!(!zone.snapshots().is_valid_name(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn orders_names_by_creation() {
+        let directory = tempdir().unwrap();
+        let zone = test_zone(&directory);
+        let directive = ZoneConfigurationDirective::default();
+
+        for name in ["b", "c", "a"] {
+            zone.snapshots().set_directive(name, &directive).unwrap();
+        }
+
+        // Neither modification times nor names reflect the order snapshots were taken in.
+        for (name, time) in [("a", 1), ("b", 3), ("c", 2)] {
+            File::options()
+                .write(true)
+                .open(zone.snapshots().file_path(name))
+                .unwrap()
+                .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(time))
+                .unwrap();
+        }
+
+        zone.snapshots().set_directive("b", &directive).unwrap();
+
+        assert_eq!(zone.snapshots().names().unwrap(), vec!["b", "c", "a"]);
+
+        zone.snapshots().remove("a").unwrap();
+        zone.snapshots().set_directive("d", &directive).unwrap();
+
+        assert_eq!(zone.snapshots().names().unwrap(), vec!["b", "c", "d"]);
+    }
+
+    #[test]
+    fn ignores_files_other_than_records() {
+        let directory = tempdir().unwrap();
+        let zone = test_zone(&directory);
+
+        zone.snapshots()
+            .set_directive("a", &ZoneConfigurationDirective::default())
+            .unwrap();
+        File::create(zone.snapshots().directory_path().join("a.tar.gz")).unwrap();
+        File::create(zone.snapshots().directory_path().join(".hidden.yaml")).unwrap();
+
+        assert_eq!(zone.snapshots().names().unwrap(), vec!["a"]);
+    }
+}
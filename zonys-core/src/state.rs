@@ -0,0 +1,304 @@
+use crate::Zone;
+use std::fs::{read_to_string, remove_file, rename, File};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process;
+use std::str::FromStr;
+use ztd::{Constructor, Display, Error, From};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum ParseZoneStateError {
+    #[Display("State {value} is unknown")]
+    UnknownState(String),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum ReadZoneLifecycleError {
+    IOError(io::Error),
+    ParseZoneStateError(ParseZoneStateError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum WriteZoneLifecycleError {
+    IOError(io::Error),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum DestroyZoneLifecycleError {
+    IOError(io::Error),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum CleanupZoneLifecycleError {
+    IOError(io::Error),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+pub enum ZoneState {
+    #[Display("creating")]
+    Creating,
+    #[Display("created")]
+    Created,
+    #[Display("starting")]
+    Starting,
+    #[Display("running")]
+    Running,
+    #[Display("stopping")]
+    Stopping,
+    #[Display("stopped")]
+    Stopped,
+    #[Display("updating")]
+    Updating,
+    #[Display("destroying")]
+    Destroying,
+    #[Display("failed")]
+    Failed,
+}
+
+impl ZoneState {
+    /// Returns whether an operation is in progress while the zone is in this state.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::Creating | Self::Starting | Self::Stopping | Self::Updating | Self::Destroying
+        )
+    }
+
+    /// Reconciles the persisted state with reality, since zones may be stopped from the outside,
+    /// e.g. by a reboot, and operations may have been interrupted without holding the lock anymore.
+    pub fn reconcile(state: Option<Self>, running: bool, locked: bool) -> Self {
+        match (state, running) {
+            (Some(Self::Running), false) => Self::Stopped,
+            (Some(Self::Created | Self::Stopped), true) => Self::Running,
+            (Some(state), _) if state.is_transient() && !locked => Self::Failed,
+            (Some(state), _) => state,
+            (None, true) => Self::Running,
+            (None, false) => Self::Stopped,
+        }
+    }
+
+    pub fn can_transition_to(&self, state: Self) -> bool {
+        matches!(
+            (self, state),
+            (Self::Creating, Self::Created)
+                | (Self::Creating, Self::Failed)
+                | (Self::Created, Self::Starting)
+                | (Self::Created, Self::Updating)
+                | (Self::Created, Self::Destroying)
+                | (Self::Starting, Self::Running)
+                | (Self::Starting, Self::Failed)
+                | (Self::Running, Self::Stopping)
+                | (Self::Stopping, Self::Stopped)
+                | (Self::Stopping, Self::Failed)
+                | (Self::Stopped, Self::Starting)
+                | (Self::Stopped, Self::Updating)
+                | (Self::Stopped, Self::Destroying)
+                | (Self::Updating, Self::Created)
+                | (Self::Updating, Self::Stopped)
+                | (Self::Updating, Self::Failed)
+                | (Self::Destroying, Self::Failed)
+                | (Self::Failed, Self::Starting)
+                | (Self::Failed, Self::Stopping)
+                | (Self::Failed, Self::Updating)
+                | (Self::Failed, Self::Destroying)
+        )
+    }
+}
+
+impl FromStr for ZoneState {
+    type Err = ParseZoneStateError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "creating" => Ok(Self::Creating),
+            "created" => Ok(Self::Created),
+            "starting" => Ok(Self::Starting),
+            "running" => Ok(Self::Running),
+            "stopping" => Ok(Self::Stopping),
+            "stopped" => Ok(Self::Stopped),
+            "updating" => Ok(Self::Updating),
+            "destroying" => Ok(Self::Destroying),
+            "failed" => Ok(Self::Failed),
+            value => Err(ParseZoneStateError::UnknownState(value.to_string())),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Constructor, Debug)]
+#[Constructor(visibility = pub(super))]
+pub struct ZoneLifecycle<T> {
+    zone: T,
+}
+
+impl ZoneLifecycle<&Zone> {
+    pub fn file_path(&self) -> PathBuf {
+        self.zone.paths().state_file()
+    }
+
+    /// Returns the persisted state, or `None` for zones created before states were recorded.
+    pub fn state(&self) -> Result<Option<ZoneState>, ReadZoneLifecycleError> {
+        match read_to_string(self.file_path()) {
+            Ok(content) => Ok(Some(content.trim().parse()?)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub(super) fn set_state(&self, state: ZoneState) -> Result<(), WriteZoneLifecycleError> {
+        let path = self.file_path();
+        let temporary_path = path.with_extension(format!("{}.tmp", process::id()));
+
+        let mut file = File::create(&temporary_path)?;
+        writeln!(file, "{}", state)?;
+        file.sync_all()?;
+
+        rename(&temporary_path, &path)?;
+
+        Ok(())
+    }
+
+    pub(super) fn destroy(&self) -> Result<(), DestroyZoneLifecycleError> {
+        remove_file(self.file_path())?;
+
+        Ok(())
+    }
+
+    pub(super) fn cleanup(&self) -> Result<(), CleanupZoneLifecycleError> {
+        let path = self.file_path();
+
+        if path.exists() {
+            remove_file(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATES: [ZoneState; 9] = [
+        ZoneState::Creating,
+        ZoneState::Created,
+        ZoneState::Starting,
+        ZoneState::Running,
+        ZoneState::Stopping,
+        ZoneState::Stopped,
+        ZoneState::Updating,
+        ZoneState::Destroying,
+        ZoneState::Failed,
+    ];
+
+    #[test]
+    fn allows_transitions_of_lifecycle_only() {
+        use ZoneState::*;
+
+        let transitions = [
+            (Creating, Created),
+            (Creating, Failed),
+            (Created, Starting),
+            (Created, Updating),
+            (Created, Destroying),
+            (Starting, Running),
+            (Starting, Failed),
+            (Running, Stopping),
+            (Stopping, Stopped),
+            (Stopping, Failed),
+            (Stopped, Starting),
+            (Stopped, Updating),
+            (Stopped, Destroying),
+            (Updating, Created),
+            (Updating, Stopped),
+            (Updating, Failed),
+            (Destroying, Failed),
+            (Failed, Starting),
+            (Failed, Stopping),
+            (Failed, Updating),
+            (Failed, Destroying),
+        ];
+
+        for from in STATES {
+            for to in STATES {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    transitions.contains(&(from, to)),
+                    "{} to {}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn parses_displayed_states() {
+        for state in STATES {
+            assert_eq!(state.to_string().parse::<ZoneState>().unwrap(), state);
+        }
+
+        assert!("unknown".parse::<ZoneState>().is_err());
+    }
+
+    #[test]
+    fn reconciles_persisted_states() {
+        use ZoneState::*;
+
+        // The persisted state, whether the zone is running, whether its lock is held and the
+        // reconciled state.
+        let cases = [
+            (None, false, false, Stopped),
+            (None, true, false, Running),
+            (Some(Created), false, false, Created),
+            (Some(Created), true, false, Running),
+            (Some(Stopped), false, false, Stopped),
+            (Some(Stopped), true, false, Running),
+            (Some(Running), true, false, Running),
+            (Some(Running), false, false, Stopped),
+            (Some(Failed), false, false, Failed),
+            (Some(Failed), true, false, Failed),
+            (Some(Creating), false, true, Creating),
+            (Some(Creating), false, false, Failed),
+            (Some(Starting), true, true, Starting),
+            (Some(Starting), true, false, Failed),
+            (Some(Stopping), true, true, Stopping),
+            (Some(Stopping), false, false, Failed),
+            (Some(Updating), false, true, Updating),
+            (Some(Updating), false, false, Failed),
+            (Some(Destroying), false, true, Destroying),
+            (Some(Destroying), false, false, Failed),
+        ];
+
+        for (state, running, locked, reconciled) in cases {
+            assert_eq!(
+                ZoneState::reconcile(state, running, locked),
+                reconciled,
+                "{:?}, running {}, locked {}",
+                state,
+                running,
+                locked
+            );
+        }
+    }
+}
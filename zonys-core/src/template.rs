@@ -1,5 +1,5 @@
 use liquid::model::{Scalar, Value};
-use liquid::{Object, ObjectView, Parser};
+use liquid::{Object, ObjectView, Parser, ParserBuilder, ValueView};
 use std::collections::HashSet;
 use std::error;
 use std::fmt;
@@ -60,22 +60,126 @@ impl From<liquid::Error> for RenderTemplateError {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Default)]
+pub enum EvaluateTemplateError {
+    RenderTemplateError(RenderTemplateError),
+    NonBooleanValue(String),
+}
+
+impl Debug for EvaluateTemplateError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::RenderTemplateError(error) => Debug::fmt(error, formatter),
+            Self::NonBooleanValue(output) => write!(
+                formatter,
+                "Expression evaluates to non-boolean value \"{}\"",
+                output
+            ),
+        }
+    }
+}
+
+impl Display for EvaluateTemplateError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::RenderTemplateError(error) => Display::fmt(error, formatter),
+            Self::NonBooleanValue(output) => write!(
+                formatter,
+                "Expression evaluates to non-boolean value \"{}\"",
+                output
+            ),
+        }
+    }
+}
+
+impl error::Error for EvaluateTemplateError {}
+
+impl From<RenderTemplateError> for EvaluateTemplateError {
+    fn from(error: RenderTemplateError) -> Self {
+        Self::RenderTemplateError(error)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 pub struct TemplateEngine {
     parser: Parser,
 }
 
+impl Default for TemplateEngine {
+    fn default() -> Self {
+        Self {
+            parser: ParserBuilder::with_stdlib()
+                .build()
+                .expect("Standard library tags and filters are valid"),
+        }
+    }
+}
+
 impl TemplateEngine {
+    /// Renders `input` in a single pass, so that values inserted into `variables` are never
+    /// evaluated themselves. References between variables are expected to be resolved by
+    /// [`TemplateEngine::resolve`] before.
     pub fn render<T>(&self, variables: &T, input: &str) -> Result<String, RenderTemplateError>
     where
         T: ObjectView,
     {
+        Ok(self.parser.parse(input)?.render(variables)?)
+    }
+
+    /// Returns `variables` with every scalar rendered until it does not change anymore, so that
+    /// variables may refer to each other.
+    pub fn resolve(
+        &self,
+        variables: &TemplateObject,
+    ) -> Result<TemplateObject, RenderTemplateError> {
+        variables
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), self.resolve_value(variables, value)?)))
+            .collect()
+    }
+
+    fn resolve_value(
+        &self,
+        variables: &TemplateObject,
+        value: &TemplateValue,
+    ) -> Result<TemplateValue, RenderTemplateError> {
+        Ok(match value {
+            Value::Scalar(scalar) => {
+                let input = scalar.to_kstr();
+                let output = self.render_repeatedly(variables, input.as_str())?;
+
+                match output == input.as_str() {
+                    true => value.clone(),
+                    false => TemplateValue::scalar(output),
+                }
+            }
+            Value::Array(array) => Value::Array(
+                array
+                    .iter()
+                    .map(|value| self.resolve_value(variables, value))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(object) => Value::Object(
+                object
+                    .iter()
+                    .map(|(key, value)| Ok((key.clone(), self.resolve_value(variables, value)?)))
+                    .collect::<Result<_, RenderTemplateError>>()?,
+            ),
+            Value::State(_) | Value::Nil => value.clone(),
+        })
+    }
+
+    fn render_repeatedly(
+        &self,
+        variables: &TemplateObject,
+        input: &str,
+    ) -> Result<String, RenderTemplateError> {
         let mut rendered_values = HashSet::<String>::new();
 
         let mut input = String::from(input);
 
         loop {
-            let output = self.parser.parse(&input)?.render(&variables)?;
+            let output = self.render(variables, &input)?;
 
             if output == input {
                 return Ok(output);
@@ -88,4 +192,55 @@ impl TemplateEngine {
             input = output;
         }
     }
+
+    pub fn evaluate<T>(&self, variables: &T, input: &str) -> Result<bool, EvaluateTemplateError>
+    where
+        T: ObjectView,
+    {
+        let expression = input.trim();
+        let expression = expression
+            .strip_prefix("{{")
+            .and_then(|expression| expression.strip_suffix("}}"))
+            .unwrap_or(expression)
+            .trim();
+
+        let output = self.render(
+            variables,
+            &format!("{{% if {} %}}true{{% else %}}false{{% endif %}}", expression),
+        )?;
+
+        match output.as_str() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(EvaluateTemplateError::NonBooleanValue(output)),
+        }
+    }
+
+    /// Returns every scalar of `object` keyed by its dotted path, like `freebsd.version.major`.
+    pub fn flatten(&self, object: &TemplateObject) -> Vec<(String, String)> {
+        let mut values = Vec::default();
+
+        for (key, value) in object.iter() {
+            Self::flatten_value(key.to_string(), value, &mut values);
+        }
+
+        values
+    }
+
+    fn flatten_value(path: String, value: &TemplateValue, values: &mut Vec<(String, String)>) {
+        match value {
+            Value::Scalar(scalar) => values.push((path, scalar.to_kstr().to_string())),
+            Value::Array(array) => {
+                for (index, value) in array.iter().enumerate() {
+                    Self::flatten_value(format!("{}.{}", path, index), value, values);
+                }
+            }
+            Value::Object(object) => {
+                for (key, value) in object.iter() {
+                    Self::flatten_value(format!("{}.{}", path, key), value, values);
+                }
+            }
+            Value::State(_) | Value::Nil => {}
+        }
+    }
 }
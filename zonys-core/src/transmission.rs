@@ -1,3 +1,4 @@
+use crate::ZoneIdentifierUuid;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use nix::unistd::{fsync, read, write};
 use postcard::{from_bytes, to_allocvec};
@@ -18,6 +19,13 @@ pub const ZONE_TRANSMISSION_MAGIC_NUMBER: ZoneTransmissionMagicNumberLength = 0x
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// Marks transmissions which continue with a `ZoneTransmissionHeader`. Transmissions marked by
+/// `ZONE_TRANSMISSION_MAGIC_NUMBER` continue with the configuration directly.
+pub const ZONE_TRANSMISSION_VERSION2_MAGIC_NUMBER: ZoneTransmissionMagicNumberLength =
+    0xFFF8E9750A50AD49;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 pub(crate) type ZoneTransmissionEndian = BigEndian;
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -29,6 +37,7 @@ pub(crate) type ZoneTransmissionHeaderLength = u64;
 #[derive(Debug, Deserialize, Serialize)]
 pub enum ZoneTransmissionHeader {
     Version1(ZoneTransmissionVersion1Header),
+    Version2(ZoneTransmissionVersion2Header),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -49,6 +58,62 @@ pub struct ZoneTransmissionVersion1Header {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// Describes the transmitted zone. A transmission of a snapshot leaves the snapshot at the
+/// receiver, so that later transmissions can be based on it. A transmission with a base contains
+/// the changes since the base snapshot only and updates the zone with the same identifier.
+#[derive(Clone, Constructor, Debug, Deserialize, Method, Serialize)]
+#[Method(accessors)]
+pub struct ZoneTransmissionVersion2Header {
+    identifier: ZoneIdentifierUuid,
+    snapshot: Option<String>,
+    base: Option<String>,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Options of sending a zone. Every option is disabled by default, which produces a plain stream
+/// readable by any receiver. They only apply to ZFS volumes.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ZoneSendOptions {
+    large_block: bool,
+    embed_data: bool,
+    compressed: bool,
+}
+
+impl ZoneSendOptions {
+    /// Allows blocks larger than 128 KiB in the stream.
+    pub fn large_block(mut self, large_block: bool) -> Self {
+        self.large_block = large_block;
+        self
+    }
+
+    /// Keeps blocks with embedded data embedded in the stream.
+    pub fn embed_data(mut self, embed_data: bool) -> Self {
+        self.embed_data = embed_data;
+        self
+    }
+
+    /// Sends compressed blocks as they are stored on disk instead of decompressing them.
+    pub fn compressed(mut self, compressed: bool) -> Self {
+        self.compressed = compressed;
+        self
+    }
+
+    pub fn is_large_block(&self) -> bool {
+        self.large_block
+    }
+
+    pub fn is_embed_data(&self) -> bool {
+        self.embed_data
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.compressed
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 pub(crate) struct RawFdWriter {
     fd: RawFd,
 }
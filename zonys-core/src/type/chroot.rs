@@ -1,7 +1,8 @@
 use crate::{
     CleanupZoneVolumeError, CreateZoneVolumeError, DestroyZoneVolumeError, FromHandlerError,
     OpenZoneVolumeError, ReadZoneConfigurationError, ReceiveZoneVolumeError, SendZoneVolumeError,
-    Zone, ZoneTransmissionReader, ZoneTransmissionWriter, ZoneVolume,
+    Zone, ZoneSendOptions, ZoneTransmissionReader, ZoneTransmissionVersion2Header,
+    ZoneTransmissionWriter, ZoneVolume,
 };
 use ztd::{Constructor, Display, Error, From};
 
@@ -109,13 +110,15 @@ impl<'a> ChrootZone<&'a Zone> {
     pub(crate) fn send(
         &self,
         writer: &mut ZoneTransmissionWriter,
+        header: &ZoneTransmissionVersion2Header,
+        options: &ZoneSendOptions,
     ) -> Result<(), SendChrootZoneError> {
         let volume = match self.volume()? {
             None => return Err(SendChrootZoneError::VolumeNotExisting),
             Some(volume) => volume,
         };
 
-        volume.send(writer)?;
+        volume.send(writer, header, options)?;
 
         Ok(())
     }
@@ -123,8 +126,9 @@ impl<'a> ChrootZone<&'a Zone> {
     pub(crate) fn receive(
         zone: &'a Zone,
         reader: &mut ZoneTransmissionReader,
+        header: &ZoneTransmissionVersion2Header,
     ) -> Result<Self, ReceiveChrootZoneError> {
-        ZoneVolume::receive(zone, reader)?;
+        ZoneVolume::receive(zone, reader, header)?;
 
         Ok(Self::new(zone))
     }
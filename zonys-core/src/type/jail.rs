@@ -1,18 +1,175 @@
 use crate::{
-    CleanupZoneVolumeError, CreateZoneVolumeError, DestroyZoneVolumeError, FromHandler,
-    FromHandlerError, JailZoneConfigurationStep, OpenZoneVolumeError, ReadZoneConfigurationError,
-    ReceiveZoneVolumeError, RenderTemplateError, SendZoneVolumeError, TemplateEngine,
-    TemplateObject, Zone, ZoneConfigurationTypeReader, ZoneTransmissionReader,
+    AttachZoneVolumeDatasetsError, CleanupZoneVolumeError, CreateZoneVolumeError,
+    DestroyZoneVolumeError, DetachZoneVolumeDatasetsError, EvaluateTemplateError, FileHandler,
+    FileHandlerError, FromHandler, FromHandlerError, JailZoneConfigurationStep,
+    OpenZoneVolumeError, ReadZoneConfigurationError, ReadZoneJournalError, ReceiveZoneVolumeError,
+    RenderTemplateError, SendZoneVolumeError, TemplateEngine, TemplateObject, TemplateValue,
+    WriteZoneJournalError, Zone, ZoneConfigurationTypeReader,
+    ZoneConfigurationVersion1FileContentDirective, ZoneConfigurationVersion1JailCommandDirective,
+    ZoneJournalEntry, ZoneSendOptions, ZoneTransmissionReader, ZoneTransmissionVersion2Header,
     ZoneTransmissionWriter, ZoneVolume,
 };
+use freebsd::system::ld::{
+    default_elf_hints_path, ElfHints, ElfHintsEntry, ReadDefaultElfHintsPathError,
+    WriteElfHintsError,
+};
+use freebsd::system::rc::{RcConf, ReadRcConfError, WriteRcConfError, RC_CONF_DEFAULT_PATH};
+use jail::backend;
 use jail::{
-    CreateJailError, DestroyJailError, ExecuteJailError, Jail, JailId, JailName, JailParameter,
-    TryIntoJailIdError,
+    CreateJailError, DestroyJailError, ExecuteJailError, ExecuteJailOptions, ExecuteJailOutput,
+    JailId, JailName, JailParameter, TryIntoJailIdError,
 };
+use log::{info, warn};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::env::{temp_dir, vars_os};
+use std::fmt::{self, Formatter};
+use std::fs::{create_dir_all, read, remove_file, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::{fchown, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::Duration;
 use url::{ParseError, Url};
-use ztd::{Constructor, Display, Error, From};
+use uuid::Uuid;
+use ztd::{Constructor, Display, Error, From, Method};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const JAIL_ZONE_SCRIPT_DEFAULT_INTERPRETER: &str = "/bin/sh";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const JAIL_ZONE_SCRIPT_CONTEXT_LINES: usize = 2;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Scripts may contain rendered secrets, so only the user executing them may read them.
+const JAIL_ZONE_SCRIPT_MODE: u32 = 0o600;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Constructor, Debug, Display, Error, Method)]
+#[Display("Step {step} failed on attempt {attempt} of {attempts}: {error}{context}")]
+#[Method(accessors)]
+pub struct ExecuteJailZoneStepError {
+    step: String,
+    attempt: u32,
+    attempts: u32,
+    error: ExecuteJailError,
+    context: ExecuteJailZoneStepContext,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Lines of a failed script step, optionally pointing at the line the interpreter reported.
+#[derive(Constructor, Debug, Default, Method)]
+#[Method(accessors)]
+pub struct ExecuteJailZoneStepContext {
+    lines: Vec<(usize, String)>,
+    line: Option<usize>,
+}
+
+impl fmt::Display for ExecuteJailZoneStepContext {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        for (number, text) in &self.lines {
+            let marker = match self.line == Some(*number) {
+                true => '>',
+                false => ' ',
+            };
+
+            write!(formatter, "\n{} {:>4} | {}", marker, number, text)?;
+        }
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Constructor, Debug)]
+#[Constructor(visibility = pub(self))]
+struct JailZoneScript {
+    content: String,
+    path: String,
+}
+
+impl JailZoneScript {
+    /// Writes the script below `root_directory_path`, owned by the user and group executing it,
+    /// and returns the path on the host.
+    fn write(
+        &self,
+        root_directory_path: &Path,
+        user: Option<&str>,
+        group: Option<&str>,
+    ) -> Result<PathBuf, FileHandlerError> {
+        let (uid, gid) = FileHandler::identifiers(root_directory_path, user, group)?;
+        let path = root_directory_path.join(self.path.trim_start_matches('/'));
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(JAIL_ZONE_SCRIPT_MODE)
+            .open(&path)?;
+
+        // The content is only written once the file is owned by the executing user, and the file
+        // is removed again if that fails.
+        let result = match uid.is_some() || gid.is_some() {
+            true => fchown(&file, uid, gid),
+            false => Ok(()),
+        }
+        .and_then(|()| file.write_all(self.content.as_bytes()));
+
+        if let Err(error) = result {
+            drop(file);
+            remove_file(&path)?;
+
+            return Err(error.into());
+        }
+
+        Ok(path)
+    }
+
+    /// Returns the lines around the line reported by the interpreter in `stderr` or the whole
+    /// script if no line could be found.
+    fn context(&self, stderr: Option<&[u8]>) -> ExecuteJailZoneStepContext {
+        let lines = self
+            .content
+            .lines()
+            .enumerate()
+            .map(|(index, text)| (index + 1, text.to_string()));
+
+        let line = stderr
+            .map(String::from_utf8_lossy)
+            .and_then(|stderr| self.line(&stderr))
+            .filter(|line| *line > 0 && *line <= self.content.lines().count());
+
+        match line {
+            Some(line) => ExecuteJailZoneStepContext::new(
+                lines
+                    .skip(line.saturating_sub(JAIL_ZONE_SCRIPT_CONTEXT_LINES + 1))
+                    .take(2 * JAIL_ZONE_SCRIPT_CONTEXT_LINES + 1)
+                    .collect(),
+                Some(line),
+            ),
+            None => ExecuteJailZoneStepContext::new(lines.collect(), None),
+        }
+    }
+
+    /// Finds the last line number reported for the script, like `path: line 3:` of bash,
+    /// `path: 3:` of sh, `"path", line 3` of python or `at path line 3.` of perl.
+    fn line(&self, stderr: &str) -> Option<usize> {
+        stderr.lines().rev().find_map(|line| {
+            let (_, rest) = line.split_once(self.path.as_str())?;
+            let rest = rest.trim_start_matches(&['"', ':', ','][..]).trim_start();
+            let rest = rest.strip_prefix("line ").unwrap_or(rest);
+
+            rest.split(|character: char| !character.is_ascii_digit())
+                .next()?
+                .parse()
+                .ok()
+        })
+    }
+}
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
@@ -22,15 +179,28 @@ pub enum CreateJailZoneError {
     ReadZoneConfigurationError(ReadZoneConfigurationError),
     CreateZoneVolumeError(CreateZoneVolumeError),
     FromHandlerError(FromHandlerError),
+    FileHandlerError(FileHandlerError),
+    ReadRcConfError(ReadRcConfError),
+    WriteRcConfError(WriteRcConfError),
+    ReadDefaultElfHintsPathError(ReadDefaultElfHintsPathError),
+    WriteElfHintsError(WriteElfHintsError),
+    ReadZoneJournalError(ReadZoneJournalError),
+    WriteZoneJournalError(WriteZoneJournalError),
     CreateJailError(CreateJailError),
     DestroyJailError(DestroyJailError),
-    ExecuteJailError(ExecuteJailError),
+    ExecuteJailZoneStepError(ExecuteJailZoneStepError),
     OpenZoneVolumeError(OpenZoneVolumeError),
     #[Display("Volume does not exist")]
     VolumeNotExisting,
     RenderTemplateError(RenderTemplateError),
+    EvaluateTemplateError(EvaluateTemplateError),
+    IoError(io::Error),
     UnsupportedScheme(String),
     UrlParseError(ParseError),
+    TryIntoJailIdError(TryIntoJailIdError),
+    #[Display("Output of completed step registered as {value} cannot be restored")]
+    #[From(skip)]
+    RegisteredOutputLost(String),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -40,11 +210,19 @@ pub enum CreateJailZoneError {
 pub enum StartJailZoneError {
     ReadZoneConfigurationError(ReadZoneConfigurationError),
     CreateJailError(CreateJailError),
-    ExecuteJailError(ExecuteJailError),
+    DestroyJailError(DestroyJailError),
+    ExecuteJailZoneStepError(ExecuteJailZoneStepError),
     #[Display("Jail is already running")]
     AlreadyRunning,
+    OpenZoneVolumeError(OpenZoneVolumeError),
+    #[Display("Volume does not exist")]
+    VolumeNotExisting,
+    AttachZoneVolumeDatasetsError(AttachZoneVolumeDatasetsError),
     TryIntoJailIdError(TryIntoJailIdError),
     RenderTemplateError(RenderTemplateError),
+    EvaluateTemplateError(EvaluateTemplateError),
+    FileHandlerError(FileHandlerError),
+    IoError(io::Error),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -54,11 +232,18 @@ pub enum StartJailZoneError {
 pub enum StopJailZoneError {
     ReadZoneConfigurationError(ReadZoneConfigurationError),
     DestroyJailError(DestroyJailError),
-    ExecuteJailError(ExecuteJailError),
+    ExecuteJailZoneStepError(ExecuteJailZoneStepError),
     #[Display("Jail is not running")]
     NotRunning,
+    OpenZoneVolumeError(OpenZoneVolumeError),
+    #[Display("Volume does not exist")]
+    VolumeNotExisting,
+    DetachZoneVolumeDatasetsError(DetachZoneVolumeDatasetsError),
     TryIntoJailIdError(TryIntoJailIdError),
     RenderTemplateError(RenderTemplateError),
+    EvaluateTemplateError(EvaluateTemplateError),
+    FileHandlerError(FileHandlerError),
+    IoError(io::Error),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -70,7 +255,7 @@ pub enum DestroyJailZoneError {
     DestroyZoneVolumeError(DestroyZoneVolumeError),
     CreateJailError(CreateJailError),
     DestroyJailError(DestroyJailError),
-    ExecuteJailError(ExecuteJailError),
+    ExecuteJailZoneStepError(ExecuteJailZoneStepError),
     #[Display("Jail is running")]
     Running,
     TryIntoJailIdError(TryIntoJailIdError),
@@ -78,6 +263,9 @@ pub enum DestroyJailZoneError {
     #[Display("Volume does not exist")]
     VolumeNotExisting,
     RenderTemplateError(RenderTemplateError),
+    EvaluateTemplateError(EvaluateTemplateError),
+    FileHandlerError(FileHandlerError),
+    IoError(io::Error),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -106,6 +294,8 @@ pub enum ReceiveJailZoneError {
 pub enum CleanupJailZoneError {
     OpenZoneVolumeError(OpenZoneVolumeError),
     CleanupZoneVolumeError(CleanupZoneVolumeError),
+    TryIntoJailIdError(TryIntoJailIdError),
+    DestroyJailError(DestroyJailError),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -125,59 +315,332 @@ impl<'a> JailZone<&'a Zone> {
         JailName::new(self.zone.identifier().to_string())
     }
 
-    fn jail_id(&self) -> Result<Option<JailId>, TryIntoJailIdError> {
-        self.jail_name().try_into()
+    pub(super) fn jail_id(&self) -> Result<Option<JailId>, TryIntoJailIdError> {
+        backend::current().id(&self.jail_name())
     }
 
-    fn jail_parameters(&self) -> Vec<JailParameter> {
-        vec![
+    /// Returns the parameters of the jail. Jails with delegated datasets may mount ZFS file
+    /// systems, which requires mount points outside of the jail to be hidden at most partially.
+    fn jail_parameters(&self, delegate_datasets: &[String]) -> Vec<JailParameter> {
+        let mut parameters = vec![
             JailParameter::new("persist", "true"),
             JailParameter::new("name", self.jail_name().to_string()),
             JailParameter::new("path", self.root_directory_path().display().to_string()),
-        ]
+        ];
+
+        if !delegate_datasets.is_empty() {
+            parameters.extend([
+                JailParameter::new("allow.mount", "true"),
+                JailParameter::new("allow.mount.zfs", "true"),
+                JailParameter::new("enforce_statfs", "1"),
+            ]);
+        }
+
+        parameters
     }
 
     fn execute<E>(
         &self,
-        jail: &Jail,
+        jail: JailId,
         step: &JailZoneConfigurationStep<'a>,
         template_engine: &TemplateEngine,
-        variables: &TemplateObject,
+        variables: &mut TemplateObject,
     ) -> Result<(), E>
     where
-        E: From<ExecuteJailError> + From<RenderTemplateError>,
+        E: From<ExecuteJailZoneStepError>
+            + From<RenderTemplateError>
+            + From<EvaluateTemplateError>
+            + From<FileHandlerError>
+            + From<io::Error>,
     {
-        jail.execute(
-            &template_engine.render(&variables, step.program())?,
-            &step
-                .arguments()
-                .as_ref()
-                .cloned()
-                .unwrap_or_default()
-                .into_iter()
-                .map(|argument| template_engine.render(&variables, &argument))
-                .collect::<Result<Vec<String>, RenderTemplateError>>()?,
-            step.environment_variables()
+        let output = self.execute_step::<E>(jail, step, template_engine, variables)?;
+
+        if let (Some(register), Some(output)) = (step.register(), output) {
+            let mut registered = TemplateObject::new();
+            registered.insert(
+                "stdout".into(),
+                TemplateValue::scalar(
+                    String::from_utf8_lossy(output.stdout().as_deref().unwrap_or_default())
+                        .trim()
+                        .to_string(),
+                ),
+            );
+            registered.insert(
+                "stderr".into(),
+                TemplateValue::scalar(
+                    String::from_utf8_lossy(output.stderr().as_deref().unwrap_or_default())
+                        .to_string(),
+                ),
+            );
+            registered.insert(
+                "exit_code".into(),
+                match output.status().code() {
+                    Some(code) => TemplateValue::scalar(i64::from(code)),
+                    None => TemplateValue::Nil,
+                },
+            );
+
+            let steps = variables
+                .entry("steps")
+                .or_insert_with(|| TemplateValue::Object(TemplateObject::new()));
+
+            if !matches!(steps, TemplateValue::Object(_)) {
+                *steps = TemplateValue::Object(TemplateObject::new());
+            }
+
+            if let TemplateValue::Object(steps) = steps {
+                steps.insert(register.clone().into(), TemplateValue::Object(registered));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Executes `step` and returns the output of its last attempt, if any was captured.
+    fn execute_step<E>(
+        &self,
+        jail: JailId,
+        step: &JailZoneConfigurationStep<'a>,
+        template_engine: &TemplateEngine,
+        variables: &TemplateObject,
+    ) -> Result<Option<ExecuteJailOutput>, E>
+    where
+        E: From<ExecuteJailZoneStepError>
+            + From<RenderTemplateError>
+            + From<EvaluateTemplateError>
+            + From<FileHandlerError>
+            + From<io::Error>,
+    {
+        if let Some(when) = step.when() {
+            if !template_engine.evaluate(&variables, when)? {
+                info!(
+                    "Skipping step {} of zone {} because \"{}\" is false",
+                    match step.command() {
+                        ZoneConfigurationVersion1JailCommandDirective::Program { program } => {
+                            program.as_str()
+                        }
+                        ZoneConfigurationVersion1JailCommandDirective::Script { .. } => "script",
+                    },
+                    self.zone.identifier(),
+                    when,
+                );
+
+                return Ok(None);
+            }
+        }
+
+        let mut arguments = step
+            .arguments()
+            .as_ref()
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|argument| template_engine.render(&variables, &argument))
+            .collect::<Result<Vec<String>, RenderTemplateError>>()?;
+
+        let host = step.host().unwrap_or_default();
+
+        let (program, script) = match step.command() {
+            ZoneConfigurationVersion1JailCommandDirective::Program { program } => {
+                (template_engine.render(&variables, program)?, None)
+            }
+            ZoneConfigurationVersion1JailCommandDirective::Script {
+                script,
+                interpreter,
+            } => {
+                let interpreter = match interpreter {
+                    Some(interpreter) => template_engine.render(&variables, interpreter)?,
+                    None => JAIL_ZONE_SCRIPT_DEFAULT_INTERPRETER.to_string(),
+                };
+
+                let name = format!(".zonys-script-{}", Uuid::new_v4());
+
+                let script = JailZoneScript::new(
+                    template_engine.render(&variables, script)?,
+                    match host {
+                        true => temp_dir().join(name).display().to_string(),
+                        false => format!("/{}", name),
+                    },
+                );
+
+                arguments.insert(0, script.path.clone());
+
+                (interpreter, Some(script))
+            }
+        };
+
+        let mut environment_variables = match host {
+            true => self.host_environment_variables(template_engine, variables),
+            false => HashMap::default(),
+        };
+
+        for (key, value) in step.environment_variables().iter().flatten() {
+            environment_variables.insert(
+                template_engine.render(&variables, key)?,
+                template_engine.render(&variables, value)?,
+            );
+        }
+
+        let attempts = step.retries().unwrap_or_default().saturating_add(1);
+        let retry_delay = Duration::from_secs(step.retry_delay().unwrap_or_default());
+
+        let render_option = |value: &Option<String>| {
+            value
                 .as_ref()
-                .cloned()
-                .unwrap_or_default()
-                .into_iter()
-                .map(|(key, value)| {
-                    Ok((
-                        template_engine.render(&variables, &key)?,
-                        template_engine.render(&variables, &value)?,
-                    ))
+                .map(|value| template_engine.render(&variables, value))
+                .transpose()
+        };
+
+        let mut options = ExecuteJailOptions::default();
+        options.set_timeout(step.timeout().map(Duration::from_secs));
+        options.set_capture(step.register().is_some());
+        options.set_echo(step.register().is_some());
+        options.set_user(render_option(step.user())?);
+        options.set_group(render_option(step.group())?);
+        options.set_working_directory(render_option(step.working_directory())?);
+        options.set_umask(step.umask().map(|umask| umask.value()));
+        options.set_inherit_login_environment(step.inherit_login_environment().unwrap_or_default());
+
+        let script_root_directory_path = match host {
+            true => PathBuf::from("/"),
+            false => self.root_directory_path(),
+        };
+
+        let script_path = match &script {
+            Some(script) => Some(script.write(
+                &script_root_directory_path,
+                options.user().as_deref(),
+                options.group().as_deref(),
+            )?),
+            None => None,
+        };
+
+        let label = match &script {
+            Some(_) => String::from("script"),
+            None => program.clone(),
+        };
+
+        let backend = backend::current();
+        let mut attempt = 1;
+
+        let (result, output) = loop {
+            let output = match host {
+                true => {
+                    backend.host_execute(&program, &arguments, &environment_variables, &options)
+                }
+                false => {
+                    backend.execute(jail, &program, &arguments, &environment_variables, &options)
+                }
+            };
+
+            let (error, output) = match output {
+                Ok(output) => match output.status().exit_ok() {
+                    Ok(()) => break (Ok(()), Some(output)),
+                    Err(error) => (error, Some(output)),
+                },
+                Err(error) => (error, None),
+            };
+
+            if attempt < attempts {
+                warn!(
+                    "Step {} of zone {} failed on attempt {} of {}: {}",
+                    label,
+                    self.zone.identifier(),
+                    attempt,
+                    attempts,
+                    error,
+                );
+
+                sleep(retry_delay);
+                attempt += 1;
+
+                continue;
+            }
+
+            let context = match &script {
+                Some(script) => script.context(
+                    output
+                        .as_ref()
+                        .and_then(|output| output.stderr().as_deref()),
+                ),
+                None => ExecuteJailZoneStepContext::default(),
+            };
+
+            break (
+                Err(ExecuteJailZoneStepError::new(
+                    label, attempt, attempts, error, context,
+                )),
+                output,
+            );
+        };
+
+        if let Some(script_path) = script_path {
+            if let Err(error) = remove_file(&script_path) {
+                warn!(
+                    "Removing script {} of zone {} failed: {}",
+                    script_path.display(),
+                    self.zone.identifier(),
+                    error,
+                );
+            }
+        }
+
+        match result {
+            Ok(()) => Ok(output),
+            Err(error) if step.ignore_errors().unwrap_or_default() => {
+                warn!(
+                    "Ignoring failure of zone {}: {}",
+                    self.zone.identifier(),
+                    error
+                );
+
+                Ok(output)
+            }
+            Err(error) => Err(E::from(error)),
+        }
+    }
+
+    /// Returns the environment of the current process extended by the identifier, the root
+    /// directory and the resolved variables of the zone.
+    fn host_environment_variables(
+        &self,
+        template_engine: &TemplateEngine,
+        variables: &TemplateObject,
+    ) -> HashMap<String, String> {
+        let mut environment_variables = vars_os()
+            .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+            .collect::<HashMap<String, String>>();
+
+        environment_variables.insert(
+            String::from("ZONE_IDENTIFIER"),
+            self.zone.identifier().to_string(),
+        );
+        environment_variables.insert(
+            String::from("ZONE_ROOT_DIRECTORY"),
+            self.root_directory_path().display().to_string(),
+        );
+
+        for (path, value) in template_engine.flatten(variables) {
+            let name = path
+                .chars()
+                .map(|character| match character.is_ascii_alphanumeric() {
+                    true => character.to_ascii_uppercase(),
+                    false => '_',
                 })
-                .collect::<Result<HashMap<String, String>, RenderTemplateError>>()?,
-        )?;
+                .collect::<String>();
 
-        Ok(())
+            environment_variables.insert(format!("ZONE_VARIABLE_{}", name), value);
+        }
+
+        environment_variables
     }
 
     pub(super) fn volume(&self) -> Result<Option<ZoneVolume<&Zone>>, OpenZoneVolumeError> {
         ZoneVolume::open(self.zone)
     }
 
+    /// Creates the jail, resuming after the phases recorded in the journal. Files, rc.conf values
+    /// and ld-elf.so.hints are written again, since writing them is idempotent.
     pub(super) fn create(&self) -> Result<(), CreateJailZoneError> {
         let reader = self.zone.configuration().reader()?;
 
@@ -186,16 +649,35 @@ impl<'a> JailZone<&'a Zone> {
             ZoneConfigurationTypeReader::Chroot(_chroot) => unreachable!(),
         };
 
-        ZoneVolume::create(self.zone, jail.volume())?;
+        let entries = self.zone.journal().entries()?;
+        let extracted = entries
+            .as_ref()
+            .map(|entries| entries.is_extracted())
+            .unwrap_or_default();
+        let completed_steps = entries
+            .as_ref()
+            .map(|entries| entries.completed_steps())
+            .unwrap_or_default();
+
+        if !extracted {
+            self.zone.journal().record(ZoneJournalEntry::Volume)?;
+            ZoneVolume::create(
+                self.zone,
+                jail.volume(),
+                &jail.zfs_properties(),
+                &jail.zfs_volume(),
+            )?;
+        }
+
         let volume = match self.volume()? {
             None => return Err(CreateJailZoneError::VolumeNotExisting),
             Some(volume) => volume,
         };
 
         let engine = TemplateEngine::default();
-        let variables = reader.variables();
+        let mut variables = engine.resolve(&reader.variables())?;
 
-        if let Some(from) = jail.from() {
+        if let (false, Some(from)) = (extracted, jail.from()) {
             let from = &engine.render(&variables, from)?;
 
             let from = match Url::parse(from) {
@@ -232,18 +714,127 @@ impl<'a> JailZone<&'a Zone> {
             FromHandler::handle(&from, &volume.root_directory_path())?;
         }
 
-        let handle = Jail::create(self.jail_parameters())?;
+        if !extracted {
+            self.zone.journal().record(ZoneJournalEntry::Extracted)?;
+        }
 
-        for step in jail.create_steps() {
-            if let Err(error) =
-                self.execute::<CreateJailZoneError>(&handle, &step, &engine, &variables)
+        for file in jail.files() {
+            let content = match file.content() {
+                ZoneConfigurationVersion1FileContentDirective::Content { content } => {
+                    content.clone().into_bytes()
+                }
+                ZoneConfigurationVersion1FileContentDirective::Source { source } => read(
+                    file.source_work_path()
+                        .as_ref()
+                        .map(PathBuf::from)
+                        .unwrap_or_default()
+                        .join(engine.render(&variables, source)?),
+                )?,
+            };
+
+            let content = match file.template().unwrap_or_default() {
+                true => engine
+                    .render(&variables, &String::from_utf8_lossy(&content))?
+                    .into_bytes(),
+                false => content,
+            };
+
+            FileHandler::handle(
+                &volume.root_directory_path(),
+                Path::new(&engine.render(&variables, file.destination())?),
+                &content,
+                file.owner().as_deref(),
+                file.group().as_deref(),
+                file.mode().map(|mode| mode.value()),
+            )?;
+        }
+
+        let mut rc_conf_values = jail.rc_conf().peekable();
+
+        if rc_conf_values.peek().is_some() {
+            let rc_conf_path = FileHandler::resolve(
+                &volume.root_directory_path(),
+                Path::new(RC_CONF_DEFAULT_PATH),
+            )?;
+
+            let mut rc_conf = match RcConf::read_from_file(&rc_conf_path) {
+                Ok(rc_conf) => rc_conf,
+                Err(ReadRcConfError::Io(error)) if error.kind() == io::ErrorKind::NotFound => {
+                    RcConf::default()
+                }
+                Err(error) => return Err(error.into()),
+            };
+
+            for (name, value) in rc_conf_values {
+                let value = engine.render(&variables, value)?;
+
+                match name.strip_suffix('+') {
+                    Some(name) => rc_conf.append(name, &value),
+                    None => rc_conf.set(name, &value),
+                }
+            }
+
+            rc_conf.write_to_file(&rc_conf_path)?;
+        }
+
+        let mut ldconfig_paths = jail.ldconfig().peekable();
+
+        if ldconfig_paths.peek().is_some() {
+            let entries = ldconfig_paths
+                .map(|path| {
+                    Ok(ElfHintsEntry::new(PathBuf::from(
+                        engine.render(&variables, path)?,
+                    )))
+                })
+                .collect::<Result<Vec<_>, CreateJailZoneError>>()?;
+
+            let elf_hints_path =
+                FileHandler::resolve(&volume.root_directory_path(), &default_elf_hints_path()?)?;
+
+            if let Some(parent) = elf_hints_path.parent() {
+                create_dir_all(parent)?;
+            }
+
+            ElfHints::new(entries).write_to_file(&elf_hints_path)?;
+        }
+
+        let steps = jail.create_steps().collect::<Vec<_>>();
+
+        // Registered outputs are not journaled, so steps after them could not be rendered.
+        if completed_steps < steps.len() {
+            if let Some(register) = steps[..completed_steps]
+                .iter()
+                .find_map(|step| step.register().as_ref())
             {
-                handle.destroy()?;
+                return Err(CreateJailZoneError::RegisteredOutputLost(register.clone()));
+            }
+        }
+
+        let backend = backend::current();
+
+        // An interrupted creation may have left its jail behind.
+        if let Some(jail_id) = self.jail_id()? {
+            backend.destroy(jail_id)?;
+        }
+
+        let jail_id = backend.create(self.jail_parameters(&[]))?;
+
+        for (index, step) in steps.iter().enumerate().skip(completed_steps) {
+            let result = self
+                .execute::<CreateJailZoneError>(jail_id, step, &engine, &mut variables)
+                .and_then(|()| {
+                    self.zone.journal().record(ZoneJournalEntry::Step(index))?;
+
+                    Ok(())
+                });
+
+            if let Err(error) = result {
+                backend.destroy(jail_id)?;
                 return Err(error);
             }
         }
 
-        handle.destroy()?;
+        backend.destroy(jail_id)?;
 
         Ok(())
     }
@@ -261,12 +852,36 @@ impl<'a> JailZone<&'a Zone> {
         };
 
         let engine = TemplateEngine::default();
-        let variables = reader.variables();
+        let mut variables = engine.resolve(&reader.variables())?;
+
+        let delegate_datasets = jail.delegate_datasets().cloned().collect::<Vec<_>>();
+
+        let backend = backend::current();
+        let jail_id = backend.create(self.jail_parameters(&delegate_datasets))?;
 
-        let handle = Jail::create(self.jail_parameters())?;
+        // Datasets are attached before any step runs, so that steps can already mount them.
+        if !delegate_datasets.is_empty() {
+            let result = match self.volume() {
+                Ok(Some(volume)) => volume
+                    .attach_datasets(&delegate_datasets, jail_id)
+                    .map_err(StartJailZoneError::from),
+                Ok(None) => Err(StartJailZoneError::VolumeNotExisting),
+                Err(error) => Err(StartJailZoneError::from(error)),
+            };
+
+            if let Err(error) = result {
+                backend.destroy(jail_id)?;
+                return Err(error);
+            }
+        }
 
         for step in jail.start_steps() {
-            self.execute::<StartJailZoneError>(&handle, &step, &engine, &variables)?;
+            if let Err(error) =
+                self.execute::<StartJailZoneError>(jail_id, &step, &engine, &mut variables)
+            {
+                backend.destroy(jail_id)?;
+                return Err(error);
+            }
         }
 
         Ok(())
@@ -278,11 +893,6 @@ impl<'a> JailZone<&'a Zone> {
             None => return Err(StopJailZoneError::NotRunning),
         };
 
-        let handle = match Jail::open(jail_id) {
-            Some(jail) => jail,
-            None => return Err(StopJailZoneError::NotRunning),
-        };
-
         let reader = self.zone.configuration().reader()?;
 
         let jail = match reader.r#type() {
@@ -291,15 +901,31 @@ impl<'a> JailZone<&'a Zone> {
         };
 
         let engine = TemplateEngine::default();
-        let variables = reader.variables();
+        let mut variables = engine.resolve(&reader.variables())?;
+        let backend = backend::current();
 
         for step in jail.stop_steps() {
-            self.execute::<StopJailZoneError>(&handle, &step, &engine, &variables)?;
+            self.execute::<StopJailZoneError>(jail_id, &step, &engine, &mut variables)?;
         }
 
-        handle.destroy()?;
+        let delegate_datasets = jail.delegate_datasets().cloned().collect::<Vec<_>>();
+
+        // The jail is destroyed even if its datasets cannot be detached, so that it does not keep
+        // running with them.
+        let result = match delegate_datasets.is_empty() {
+            true => Ok(()),
+            false => match self.volume() {
+                Ok(Some(volume)) => volume
+                    .detach_datasets(&delegate_datasets, jail_id)
+                    .map_err(StopJailZoneError::from),
+                Ok(None) => Err(StopJailZoneError::VolumeNotExisting),
+                Err(error) => Err(StopJailZoneError::from(error)),
+            },
+        };
+
+        backend.destroy(jail_id)?;
 
-        Ok(())
+        result
     }
 
     pub(super) fn destroy(&self) -> Result<(), DestroyJailZoneError> {
@@ -320,20 +946,21 @@ impl<'a> JailZone<&'a Zone> {
         };
 
         let engine = TemplateEngine::default();
-        let variables = reader.variables();
+        let mut variables = engine.resolve(&reader.variables())?;
 
-        let handle = Jail::create(self.jail_parameters())?;
+        let backend = backend::current();
+        let jail_id = backend.create(self.jail_parameters(&[]))?;
 
         for step in jail.destroy_steps() {
             if let Err(error) =
-                self.execute::<DestroyJailZoneError>(&handle, &step, &engine, &variables)
+                self.execute::<DestroyJailZoneError>(jail_id, &step, &engine, &mut variables)
             {
-                handle.destroy()?;
+                backend.destroy(jail_id)?;
                 return Err(error);
             }
         }
 
-        handle.destroy()?;
+        backend.destroy(jail_id)?;
 
         volume.destroy()?;
 
@@ -343,13 +970,15 @@ impl<'a> JailZone<&'a Zone> {
     pub(super) fn send(
         &self,
         writer: &mut ZoneTransmissionWriter,
+        header: &ZoneTransmissionVersion2Header,
+        options: &ZoneSendOptions,
     ) -> Result<(), SendJailZoneError> {
         let volume = match self.volume()? {
             None => return Err(SendJailZoneError::VolumeNotExisting),
             Some(volume) => volume,
         };
 
-        volume.send(writer)?;
+        volume.send(writer, header, options)?;
 
         Ok(())
     }
@@ -357,13 +986,18 @@ impl<'a> JailZone<&'a Zone> {
     pub(super) fn receive(
         zone: &'a Zone,
         reader: &mut ZoneTransmissionReader,
+        header: &ZoneTransmissionVersion2Header,
     ) -> Result<Self, ReceiveJailZoneError> {
-        ZoneVolume::receive(zone, reader)?;
+        ZoneVolume::receive(zone, reader, header)?;
 
         Ok(Self::new(zone))
     }
 
     pub(super) fn cleanup(&self) -> Result<(), CleanupJailZoneError> {
+        if let Some(jail_id) = self.jail_id()? {
+            backend::current().destroy(jail_id)?;
+        }
+
         if let Some(volume) = self.volume()? {
             volume.cleanup()?;
         }
@@ -371,3 +1005,472 @@ impl<'a> JailZone<&'a Zone> {
         Ok(())
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(all(test, feature = "fake-jail", feature = "fake-zfs"))]
+mod tests {
+    use super::*;
+    use crate::{test_zone, ZoneJournalOperation};
+    use jail::backend::{FakeBackend, FakeJailCall};
+    use jail::ExecuteJailStatus;
+    use nix::unistd::getuid;
+    use std::fs::{metadata, write};
+    use std::os::unix::fs::MetadataExt;
+    use std::rc::Rc;
+    use tempfile::{tempdir, TempDir};
+
+    const CONFIGURATION: &str = "
+version: experimental
+type: jail
+execute:
+  start:
+    on:
+      - program: /bin/first
+        arguments:
+          - argument
+      - program: /bin/second
+      - program: /bin/third
+  stop:
+    on:
+      - program: /bin/stop
+";
+
+    fn zone(directory: &TempDir) -> Zone {
+        let zone = test_zone(directory);
+
+        write(zone.paths().configuration_file(), CONFIGURATION).unwrap();
+
+        zone
+    }
+
+    fn backend() -> Rc<FakeBackend> {
+        // Opening the volume looks for a ZFS volume first, which needs a ZFS backend as well.
+        zfs::backend::replace(Rc::new(zfs::backend::FakeBackend::default()));
+
+        let backend = Rc::new(FakeBackend::default());
+        backend::replace(backend.clone());
+
+        backend
+    }
+
+    fn execute(id: usize, program: &str, arguments: &[&str]) -> FakeJailCall {
+        FakeJailCall::Execute {
+            id,
+            program: program.to_string(),
+            arguments: arguments
+                .iter()
+                .map(|argument| argument.to_string())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn starts_and_stops_jail_in_order() {
+        let backend = backend();
+        let directory = tempdir().unwrap();
+        let zone = zone(&directory);
+        let jail_zone = JailZone::new(&zone);
+
+        jail_zone.start().unwrap();
+        assert_eq!(backend.running(), vec![zone.identifier().to_string()]);
+
+        jail_zone.stop().unwrap();
+        assert!(backend.running().is_empty());
+
+        assert_eq!(
+            backend.calls(),
+            vec![
+                FakeJailCall::Create {
+                    parameters: vec![
+                        (String::from("persist"), String::from("true")),
+                        (String::from("name"), zone.identifier().to_string()),
+                        (
+                            String::from("path"),
+                            zone.paths().root_directory().display().to_string()
+                        ),
+                    ],
+                },
+                execute(1, "/bin/first", &["argument"]),
+                execute(1, "/bin/second", &[]),
+                execute(1, "/bin/third", &[]),
+                execute(1, "/bin/stop", &[]),
+                FakeJailCall::Destroy { id: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn destroys_jail_if_start_step_fails() {
+        let backend = backend();
+        let directory = tempdir().unwrap();
+        let zone = zone(&directory);
+
+        backend.fail_execute("/bin/second", ExecuteJailStatus::Exited(1));
+
+        assert!(matches!(
+            JailZone::new(&zone).start(),
+            Err(StartJailZoneError::ExecuteJailZoneStepError(_))
+        ));
+        assert!(backend.running().is_empty());
+        assert_eq!(
+            backend.calls()[1..],
+            [
+                execute(1, "/bin/first", &["argument"]),
+                execute(1, "/bin/second", &[]),
+                FakeJailCall::Destroy { id: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn refuses_to_start_running_jail() {
+        let backend = backend();
+        let directory = tempdir().unwrap();
+        let zone = zone(&directory);
+
+        JailZone::new(&zone).start().unwrap();
+
+        assert!(matches!(
+            JailZone::new(&zone).start(),
+            Err(StartJailZoneError::AlreadyRunning)
+        ));
+        assert_eq!(backend.running().len(), 1);
+    }
+
+    #[test]
+    fn destroys_jail_if_datasets_cannot_be_delegated() {
+        let backend = backend();
+        let directory = tempdir().unwrap();
+        let zone = zone(&directory);
+
+        write(
+            zone.paths().configuration_file(),
+            "
+version: experimental
+type: jail
+delegate_datasets:
+  - data
+",
+        )
+        .unwrap();
+        create_dir_all(zone.paths().root_directory()).unwrap();
+
+        assert!(matches!(
+            JailZone::new(&zone).start(),
+            Err(StartJailZoneError::AttachZoneVolumeDatasetsError(
+                AttachZoneVolumeDatasetsError::Unsupported
+            ))
+        ));
+        assert!(backend.running().is_empty());
+        assert_eq!(
+            backend.calls(),
+            vec![
+                FakeJailCall::Create {
+                    parameters: vec![
+                        (String::from("persist"), String::from("true")),
+                        (String::from("name"), zone.identifier().to_string()),
+                        (
+                            String::from("path"),
+                            zone.paths().root_directory().display().to_string()
+                        ),
+                        (String::from("allow.mount"), String::from("true")),
+                        (String::from("allow.mount.zfs"), String::from("true")),
+                        (String::from("enforce_statfs"), String::from("1")),
+                    ],
+                },
+                FakeJailCall::Destroy { id: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn registers_output_without_evaluating_it() {
+        let backend = backend();
+        let directory = tempdir().unwrap();
+        let zone = zone(&directory);
+
+        write(
+            zone.paths().configuration_file(),
+            "
+version: experimental
+type: jail
+variables:
+  greeting: hello {{ target }}
+  target: world
+execute:
+  start:
+    on:
+      - program: /bin/hostname
+        register: hostname
+      - program: /bin/echo
+        arguments:
+          - \"{{ greeting }} from {{ steps.hostname.stdout }}\"
+",
+        )
+        .unwrap();
+
+        backend.set_stdout("/bin/hostname", b"{{ greeting }}\n");
+
+        JailZone::new(&zone).start().unwrap();
+
+        assert_eq!(
+            backend.calls()[1..],
+            [
+                execute(1, "/bin/hostname", &[]),
+                execute(1, "/bin/echo", &["hello world from {{ greeting }}"]),
+            ]
+        );
+    }
+
+    const CREATE_CONFIGURATION: &str = "
+version: experimental
+type: jail
+volume: directory
+execute:
+  create:
+    on:
+      - program: /bin/first
+      - program: /bin/second
+";
+
+    fn creating_zone(directory: &TempDir, entries: &[ZoneJournalEntry]) -> Zone {
+        let zone = zone(directory);
+
+        write(zone.paths().configuration_file(), CREATE_CONFIGURATION).unwrap();
+        zone.journal().begin(ZoneJournalOperation::Create).unwrap();
+
+        for entry in entries {
+            zone.journal().record(*entry).unwrap();
+        }
+
+        zone
+    }
+
+    #[test]
+    fn records_each_phase_of_creation() {
+        let backend = backend();
+        let directory = tempdir().unwrap();
+        let zone = creating_zone(&directory, &[]);
+
+        JailZone::new(&zone).create().unwrap();
+
+        assert!(zone.paths().root_directory().is_dir());
+        assert_eq!(
+            backend.calls()[1..],
+            [
+                execute(1, "/bin/first", &[]),
+                execute(1, "/bin/second", &[]),
+                FakeJailCall::Destroy { id: 1 },
+            ]
+        );
+        assert_eq!(
+            zone.journal().entries().unwrap().unwrap().entries(),
+            &vec![
+                ZoneJournalEntry::Begin(ZoneJournalOperation::Create),
+                ZoneJournalEntry::Volume,
+                ZoneJournalEntry::Extracted,
+                ZoneJournalEntry::Step(0),
+                ZoneJournalEntry::Step(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn resumes_creation_after_completed_steps() {
+        let backend = backend();
+        let directory = tempdir().unwrap();
+        let zone = creating_zone(
+            &directory,
+            &[
+                ZoneJournalEntry::Volume,
+                ZoneJournalEntry::Extracted,
+                ZoneJournalEntry::Step(0),
+            ],
+        );
+        let jail_zone = JailZone::new(&zone);
+
+        create_dir_all(zone.paths().root_directory()).unwrap();
+        write(zone.paths().root_directory().join("extracted"), "").unwrap();
+        backend::current()
+            .create(jail_zone.jail_parameters(&[]))
+            .unwrap();
+
+        jail_zone.create().unwrap();
+
+        let calls = backend.calls();
+
+        assert!(zone.paths().root_directory().join("extracted").exists());
+        assert_eq!(calls[1], FakeJailCall::Destroy { id: 1 });
+        assert_eq!(calls[2], calls[0]);
+        assert_eq!(
+            calls[3..],
+            [
+                execute(2, "/bin/second", &[]),
+                FakeJailCall::Destroy { id: 2 },
+            ]
+        );
+        assert_eq!(
+            zone.journal().entries().unwrap().unwrap().entries()[4..],
+            [ZoneJournalEntry::Step(1)]
+        );
+    }
+
+    #[test]
+    fn refuses_to_resume_after_registered_output() {
+        let backend = backend();
+        let directory = tempdir().unwrap();
+        let zone = creating_zone(
+            &directory,
+            &[
+                ZoneJournalEntry::Volume,
+                ZoneJournalEntry::Extracted,
+                ZoneJournalEntry::Step(0),
+            ],
+        );
+
+        write(
+            zone.paths().configuration_file(),
+            CREATE_CONFIGURATION.replace("/bin/first", "/bin/first\n        register: first"),
+        )
+        .unwrap();
+        create_dir_all(zone.paths().root_directory()).unwrap();
+
+        assert!(matches!(
+            JailZone::new(&zone).create(),
+            Err(CreateJailZoneError::RegisteredOutputLost(register)) if register == "first"
+        ));
+        assert!(backend.calls().is_empty());
+    }
+
+    #[test]
+    fn destroys_jail_if_datasets_cannot_be_detached() {
+        let backend = backend();
+        let directory = tempdir().unwrap();
+        let zone = zone(&directory);
+
+        create_dir_all(zone.paths().root_directory()).unwrap();
+        JailZone::new(&zone).start().unwrap();
+
+        write(
+            zone.paths().configuration_file(),
+            "
+version: experimental
+type: jail
+delegate_datasets:
+  - data
+",
+        )
+        .unwrap();
+
+        assert!(matches!(
+            JailZone::new(&zone).stop(),
+            Err(StopJailZoneError::DetachZoneVolumeDatasetsError(
+                DetachZoneVolumeDatasetsError::Unsupported
+            ))
+        ));
+        assert!(backend.running().is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_dataset_names() {
+        let directory = tempdir().unwrap();
+        let zone = zone(&directory);
+
+        for name in ["..", "a@b", "x/../y", "/data", "data/", "a//b", "\"\""] {
+            write(
+                zone.paths().configuration_file(),
+                format!(
+                    "
+version: experimental
+type: jail
+delegate_datasets:
+  - {}
+",
+                    name
+                ),
+            )
+            .unwrap();
+
+            assert!(zone.configuration().reader().is_err(), "{}", name);
+        }
+
+        write(
+            zone.paths().configuration_file(),
+            "
+version: experimental
+type: jail
+delegate_datasets:
+  - data/poudriere.cache
+",
+        )
+        .unwrap();
+
+        assert!(zone.configuration().reader().is_ok());
+    }
+
+    #[test]
+    fn reads_relative_sources_relative_to_configuration_file() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("zone.yaml");
+
+        write(
+            &path,
+            "
+version: experimental
+type: jail
+files:
+  - destination: /etc/motd
+    source: motd
+  - destination: /etc/hosts
+    source: hosts
+    source_work_path: /configuration
+  - destination: /etc/issue
+    content: issue
+",
+        )
+        .unwrap();
+
+        let directive = crate::ZoneConfigurationDirective::read_from_path(&path).unwrap();
+        let jail = match directive.version() {
+            crate::ZoneConfigurationVersionDirective::Version1(version1) => match version1.r#type()
+            {
+                crate::ZoneConfigurationVersion1TypeDirective::Jail(jail) => jail,
+            },
+        };
+
+        assert_eq!(
+            jail.files()
+                .iter()
+                .flatten()
+                .map(|file| file.source_work_path().clone())
+                .collect::<Vec<_>>(),
+            vec![
+                Some(directory.path().display().to_string()),
+                Some(String::from("/configuration")),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn writes_scripts_readable_by_executing_user_only() {
+        let directory = tempdir().unwrap();
+        let script = JailZoneScript::new(String::from("echo secret"), String::from("/script"));
+        let uid = getuid().as_raw();
+
+        let path = script
+            .write(directory.path(), Some(&uid.to_string()), None)
+            .unwrap();
+        let metadata = metadata(&path).unwrap();
+
+        assert_eq!(path, directory.path().join("script"));
+        assert_eq!(metadata.mode() & 0o777, JAIL_ZONE_SCRIPT_MODE);
+        assert_eq!(metadata.uid(), uid);
+
+        assert!(matches!(
+            script.write(directory.path(), Some("unknown"), None),
+            Err(FileHandlerError::UnknownUser(_))
+        ));
+    }
+}
@@ -10,8 +10,10 @@ pub use chroot::*;
 
 use crate::{
     DeserializeZoneTransmissionError, ReadZoneConfigurationError, SerializeZoneTransmissionError,
-    Zone, ZoneConfigurationTypeReader, ZoneTransmissionReader, ZoneTransmissionWriter,
+    Zone, ZoneConfigurationTypeReader, ZoneSendOptions, ZoneTransmissionReader,
+    ZoneTransmissionVersion2Header, ZoneTransmissionWriter,
 };
+use ::jail::{JailId, TryIntoJailIdError};
 use serde::{Deserialize, Serialize};
 use ztd::{Display, Error, From};
 
@@ -44,6 +46,14 @@ pub enum StopZoneTypeError {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum ReadZoneTypeStatusError {
+    TryIntoJailIdError(TryIntoJailIdError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug, Display, Error, From)]
 #[From(unnamed)]
 pub enum DestroyZoneTypeError {
@@ -135,6 +145,22 @@ impl<'a> ZoneType<&'a Zone> {
         }
     }
 
+    /// Returns whether the zone is running. Starting chroot zones does not spawn any process, so
+    /// they are never considered running.
+    pub(super) fn running(&self) -> Result<bool, ReadZoneTypeStatusError> {
+        match &self {
+            Self::Chroot(_chroot) => Ok(false),
+            Self::Jail(jail) => Ok(jail.jail_id()?.is_some()),
+        }
+    }
+
+    pub(super) fn jail_id(&self) -> Result<Option<JailId>, ReadZoneTypeStatusError> {
+        match &self {
+            Self::Chroot(_chroot) => Ok(None),
+            Self::Jail(jail) => Ok(jail.jail_id()?),
+        }
+    }
+
     pub(super) fn destroy(&self) -> Result<(), DestroyZoneTypeError> {
         match &self {
             Self::Chroot(chroot) => Ok(chroot.destroy()?),
@@ -145,6 +171,8 @@ impl<'a> ZoneType<&'a Zone> {
     pub(super) fn send(
         &self,
         writer: &mut ZoneTransmissionWriter,
+        header: &ZoneTransmissionVersion2Header,
+        options: &ZoneSendOptions,
     ) -> Result<(), SendZoneTypeError> {
         match &self {
             Self::Chroot(chroot) => {
@@ -152,14 +180,14 @@ impl<'a> ZoneType<&'a Zone> {
                     r#type: ZoneTypeTransmissionHeaderVersion1Type::Chroot,
                 })?;
 
-                Ok(chroot.send(writer)?)
+                Ok(chroot.send(writer, header, options)?)
             }
             Self::Jail(jail) => {
                 writer.serialize(&ZoneTypeTransmissionHeader::Version1 {
                     r#type: ZoneTypeTransmissionHeaderVersion1Type::Jail,
                 })?;
 
-                Ok(jail.send(writer)?)
+                Ok(jail.send(writer, header, options)?)
             }
         }
     }
@@ -167,14 +195,15 @@ impl<'a> ZoneType<&'a Zone> {
     pub(super) fn receive(
         zone: &'a Zone,
         reader: &mut ZoneTransmissionReader,
+        header: &ZoneTransmissionVersion2Header,
     ) -> Result<Self, ReceiveZoneTypeError> {
         match reader.deserialize::<ZoneTypeTransmissionHeader>()? {
             ZoneTypeTransmissionHeader::Version1 { r#type } => match r#type {
                 ZoneTypeTransmissionHeaderVersion1Type::Chroot => {
-                    Ok(Self::Chroot(ChrootZone::receive(zone, reader)?))
+                    Ok(Self::Chroot(ChrootZone::receive(zone, reader, header)?))
                 }
                 ZoneTypeTransmissionHeaderVersion1Type::Jail => {
-                    Ok(Self::Jail(JailZone::receive(zone, reader)?))
+                    Ok(Self::Jail(JailZone::receive(zone, reader, header)?))
                 }
             },
         }
@@ -1,13 +1,30 @@
-use crate::{RawFdReader, RawFdWriter, Zone, ZoneTransmissionReader, ZoneTransmissionWriter};
-use std::fs::{create_dir_all, remove_dir_all};
-use std::io::{self, BufReader, BufWriter};
+use crate::{
+    RawFdReader, RawFdWriter, ReadZoneSnapshotsError, Zone, ZoneSendOptions,
+    ZoneTransmissionReader, ZoneTransmissionVersion2Header, ZoneTransmissionWriter,
+};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{
+    create_dir_all, metadata, remove_dir_all, remove_file, rename, set_permissions, File,
+};
+use std::io::{self, copy, BufReader, BufWriter, IntoInnerError, Write};
 use std::os::unix::io::AsRawFd;
 use std::path::PathBuf;
 use tar::{Archive, Builder};
+use tempfile::{tempdir_in, NamedTempFile};
 use ztd::{Constructor, Display, Error, From};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+pub const ZONE_DIRECTORY_VOLUME_SNAPSHOT_PATH_EXTENSION: &str = "tar.gz";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub const ZONE_DIRECTORY_VOLUME_PREVIOUS_ROOT_DIRECTORY_NAME: &str = ".root";
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug, Display, Error, From)]
 #[From(unnamed)]
 pub enum CreateZoneDirectoryVolumeError {
@@ -28,6 +45,10 @@ pub enum DestroyZoneDirectoryVolumeError {
 #[From(unnamed)]
 pub enum SendZoneDirectoryVolumeError {
     IOError(io::Error),
+    #[Display("Snapshot does not exist")]
+    SnapshotNotExisting,
+    #[Display("Directory volumes do not support incremental transmissions")]
+    IncrementalUnsupported,
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -36,6 +57,8 @@ pub enum SendZoneDirectoryVolumeError {
 #[From(unnamed)]
 pub enum ReceiveZoneDirectoryVolumeError {
     IOError(io::Error),
+    #[Display("Directory volumes do not support incremental transmissions")]
+    IncrementalUnsupported,
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -48,6 +71,34 @@ pub enum CleanupZoneDirectoryVolumeError {
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum CreateZoneDirectoryVolumeSnapshotError {
+    IOError(io::Error),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum DestroyZoneDirectoryVolumeSnapshotError {
+    IOError(io::Error),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum RollbackZoneDirectoryVolumeSnapshotError {
+    IOError(io::Error),
+    ReadZoneSnapshotsError(ReadZoneSnapshotsError),
+    DestroyZoneDirectoryVolumeSnapshotError(DestroyZoneDirectoryVolumeSnapshotError),
+    #[Display("Snapshot does not exist")]
+    SnapshotNotExisting,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug, Constructor)]
 #[Constructor(visibility = pub(crate))]
 pub struct ZoneDirectoryVolume<T> {
@@ -59,12 +110,30 @@ impl<'a> ZoneDirectoryVolume<&'a Zone> {
         self.zone.paths().root_directory()
     }
 
+    /// Returns the root directory replaced by an unfinished rollback, which is kept within the
+    /// snapshots directory until the rollback has completed.
+    pub fn previous_root_directory_path(&self) -> PathBuf {
+        self.zone
+            .paths()
+            .snapshots_directory()
+            .join(ZONE_DIRECTORY_VOLUME_PREVIOUS_ROOT_DIRECTORY_NAME)
+    }
+
     pub(super) fn open(zone: &'a Zone) -> Option<Self> {
-        if !zone.paths().root_directory().exists() {
+        let volume = Self::new(zone);
+        let root_directory_path = volume.root_directory_path();
+
+        // A rollback interrupted after moving the root directory aside is undone by moving it
+        // back, which fails harmlessly if another process did so already.
+        if !root_directory_path.exists() {
+            let _ = rename(volume.previous_root_directory_path(), &root_directory_path);
+        }
+
+        if !root_directory_path.exists() {
             return None;
         }
 
-        Some(Self::new(zone))
+        Some(volume)
     }
 
     pub(super) fn create(zone: &'a Zone) -> Result<(), CreateZoneDirectoryVolumeError> {
@@ -82,12 +151,37 @@ impl<'a> ZoneDirectoryVolume<&'a Zone> {
     pub(super) fn send(
         &self,
         writer: &mut ZoneTransmissionWriter,
+        header: &ZoneTransmissionVersion2Header,
+        _options: &ZoneSendOptions,
     ) -> Result<(), SendZoneDirectoryVolumeError> {
-        let mut builder = Builder::new(BufWriter::new(RawFdWriter::new(writer.as_raw_fd())));
+        if header.base().is_some() {
+            return Err(SendZoneDirectoryVolumeError::IncrementalUnsupported);
+        }
+
+        let mut writer = BufWriter::new(RawFdWriter::new(writer.as_raw_fd()));
+
+        match header.snapshot() {
+            // Snapshots are transmitted decompressed, just like the root directory itself.
+            Some(name) => {
+                let file = match File::open(self.snapshot_file_path(name)) {
+                    Ok(file) => file,
+                    Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                        return Err(SendZoneDirectoryVolumeError::SnapshotNotExisting)
+                    }
+                    Err(error) => return Err(error.into()),
+                };
 
-        builder.follow_symlinks(false);
-        builder.append_dir_all(".", &self.root_directory_path())?;
-        builder.into_inner()?;
+                copy(&mut GzDecoder::new(BufReader::new(file)), &mut writer)?;
+                writer.into_inner().map_err(IntoInnerError::into_error)?;
+            }
+            None => {
+                let mut builder = Builder::new(writer);
+
+                builder.follow_symlinks(false);
+                builder.append_dir_all(".", self.root_directory_path())?;
+                builder.into_inner()?;
+            }
+        }
 
         Ok(())
     }
@@ -95,7 +189,12 @@ impl<'a> ZoneDirectoryVolume<&'a Zone> {
     pub(super) fn receive(
         zone: &'a Zone,
         reader: &mut ZoneTransmissionReader,
+        header: &ZoneTransmissionVersion2Header,
     ) -> Result<Self, ReceiveZoneDirectoryVolumeError> {
+        if header.base().is_some() {
+            return Err(ReceiveZoneDirectoryVolumeError::IncrementalUnsupported);
+        }
+
         let mut archive = Archive::new(BufReader::new(RawFdReader::new(reader.as_raw_fd())));
 
         archive.unpack(zone.paths().root_directory())?;
@@ -111,4 +210,186 @@ impl<'a> ZoneDirectoryVolume<&'a Zone> {
 
         Ok(())
     }
+
+    pub fn snapshot_file_path(&self, name: &str) -> PathBuf {
+        self.zone.paths().snapshots_directory().join(format!(
+            "{}.{}",
+            name, ZONE_DIRECTORY_VOLUME_SNAPSHOT_PATH_EXTENSION
+        ))
+    }
+
+    pub(super) fn has_snapshot(&self, name: &str) -> bool {
+        self.snapshot_file_path(name).is_file()
+    }
+
+    /// Archives the root directory into a compressed tar file stored beside the zone.
+    pub(super) fn create_snapshot(
+        &self,
+        name: &str,
+    ) -> Result<(), CreateZoneDirectoryVolumeSnapshotError> {
+        let directory_path = self.zone.paths().snapshots_directory();
+        create_dir_all(&directory_path)?;
+
+        let mut file = NamedTempFile::new_in(&directory_path)?;
+
+        {
+            let mut builder = Builder::new(GzEncoder::new(
+                BufWriter::new(file.as_file_mut()),
+                Compression::default(),
+            ));
+
+            builder.follow_symlinks(false);
+            builder.append_dir_all(".", self.root_directory_path())?;
+            builder.into_inner()?.finish()?.flush()?;
+        }
+
+        file.as_file().sync_all()?;
+        file.persist(self.snapshot_file_path(name))
+            .map_err(io::Error::from)?;
+
+        Ok(())
+    }
+
+    pub(super) fn destroy_snapshot(
+        &self,
+        name: &str,
+    ) -> Result<(), DestroyZoneDirectoryVolumeSnapshotError> {
+        let path = self.snapshot_file_path(name);
+
+        if path.exists() {
+            remove_file(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Unpacks the snapshot next to the root directory first and replaces the root directory
+    /// afterwards, so that a failing unpack leaves the current content untouched. The current
+    /// root directory is moved aside before and removed after the replacement, so that it can be
+    /// restored by `open` if the rollback is interrupted in between. Just like ZFS, more recent
+    /// snapshots are destroyed on the way.
+    pub(super) fn rollback_snapshot(
+        &self,
+        name: &str,
+    ) -> Result<(), RollbackZoneDirectoryVolumeSnapshotError> {
+        let file = match File::open(self.snapshot_file_path(name)) {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Err(RollbackZoneDirectoryVolumeSnapshotError::SnapshotNotExisting)
+            }
+            Err(error) => return Err(error.into()),
+        };
+
+        let root_directory_path = self.root_directory_path();
+        let previous_root_directory_path = self.previous_root_directory_path();
+
+        // Left over from a rollback interrupted after the replacement.
+        if previous_root_directory_path.exists() {
+            remove_dir_all(&previous_root_directory_path)?;
+        }
+
+        let directory = tempdir_in(self.zone.paths().snapshots_directory())?;
+
+        let mut archive = Archive::new(GzDecoder::new(BufReader::new(file)));
+        archive.set_preserve_permissions(true);
+        archive.set_preserve_ownerships(true);
+        archive.unpack(directory.path())?;
+
+        set_permissions(
+            directory.path(),
+            metadata(&root_directory_path)?.permissions(),
+        )?;
+
+        rename(&root_directory_path, &previous_root_directory_path)?;
+
+        // Once renamed, dropping the temporary directory has nothing left to remove.
+        if let Err(error) = rename(directory.path(), &root_directory_path) {
+            rename(&previous_root_directory_path, &root_directory_path)?;
+
+            return Err(error.into());
+        }
+
+        remove_dir_all(&previous_root_directory_path)?;
+
+        let names = self.zone.snapshots().names()?;
+
+        if let Some(position) = names.iter().position(|snapshot| snapshot == name) {
+            for name in &names[position + 1..] {
+                self.destroy_snapshot(name)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_zone, ZoneConfigurationDirective};
+    use std::fs::{read_to_string, write};
+    use tempfile::{tempdir, TempDir};
+
+    fn zone(directory: &TempDir) -> Zone {
+        let zone = test_zone(directory);
+
+        ZoneDirectoryVolume::create(&zone).unwrap();
+
+        zone
+    }
+
+    fn create_snapshot(zone: &Zone, name: &str) {
+        ZoneDirectoryVolume::new(zone)
+            .create_snapshot(name)
+            .unwrap();
+        zone.snapshots()
+            .set_directive(name, &ZoneConfigurationDirective::default())
+            .unwrap();
+    }
+
+    #[test]
+    fn rolls_back_and_destroys_more_recent_snapshots() {
+        let directory = tempdir().unwrap();
+        let zone = zone(&directory);
+        let volume = ZoneDirectoryVolume::new(&zone);
+        let file_path = volume.root_directory_path().join("file");
+
+        write(&file_path, "first").unwrap();
+        create_snapshot(&zone, "first");
+        write(&file_path, "second").unwrap();
+        create_snapshot(&zone, "second");
+        write(&file_path, "third").unwrap();
+
+        volume.rollback_snapshot("first").unwrap();
+
+        assert_eq!(read_to_string(&file_path).unwrap(), "first");
+        assert!(volume.has_snapshot("first"));
+        assert!(!volume.has_snapshot("second"));
+        assert!(!volume.previous_root_directory_path().exists());
+    }
+
+    #[test]
+    fn restores_root_directory_of_interrupted_rollback() {
+        let directory = tempdir().unwrap();
+        let zone = zone(&directory);
+        let volume = ZoneDirectoryVolume::new(&zone);
+
+        write(volume.root_directory_path().join("file"), "content").unwrap();
+        create_snapshot(&zone, "snapshot");
+        rename(
+            volume.root_directory_path(),
+            volume.previous_root_directory_path(),
+        )
+        .unwrap();
+
+        let volume = ZoneDirectoryVolume::open(&zone).unwrap();
+
+        assert_eq!(
+            read_to_string(volume.root_directory_path().join("file")).unwrap(),
+            "content"
+        );
+        assert!(!volume.previous_root_directory_path().exists());
+    }
 }
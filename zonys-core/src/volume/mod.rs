@@ -1,19 +1,23 @@
 mod directory;
-#[cfg(target_os = "freebsd")]
+#[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
 mod zfs;
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
-#[cfg(target_os = "freebsd")]
+#[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
 pub use crate::volume::zfs::*;
 pub use directory::*;
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 use crate::{
-    DeserializeZoneTransmissionError, SerializeZoneTransmissionError, Zone, ZoneTransmissionReader,
+    DeserializeZoneTransmissionError, SerializeZoneTransmissionError, Zone,
+    ZoneConfigurationVersion1ZfsPropertiesDirective, ZoneConfigurationVersion1ZfsVolumeDirective,
+    ZoneSendOptions, ZoneTransmissionReader, ZoneTransmissionVersion2Header,
     ZoneTransmissionWriter,
 };
+use jail::JailId;
+use log::info;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use ztd::{Display, Error, From};
@@ -25,8 +29,12 @@ use ztd::{Display, Error, From};
 pub enum CreateZoneVolumeError {
     CheckZoneZfsVolumeSupportError(CheckZoneZfsVolumeSupportError),
     CreateZoneDirectoryVolumeError(CreateZoneDirectoryVolumeError),
-    #[cfg(target_os = "freebsd")]
+    #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
     CreateZoneZfsVolumeError(CreateZoneZfsVolumeError),
+    #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+    #[Display("ZFS volume is unsupported ({value})")]
+    #[From(skip)]
+    ZfsUnsupported(ZoneZfsVolumeUnsupportedReason),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -43,7 +51,7 @@ pub enum OpenZoneVolumeError {
 #[From(unnamed)]
 pub enum DestroyZoneVolumeError {
     DestroyZoneDirectoryVolumeError(DestroyZoneDirectoryVolumeError),
-    #[cfg(target_os = "freebsd")]
+    #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
     DestroyZoneZfsVolumeError(DestroyZoneZfsVolumeError),
 }
 
@@ -53,7 +61,7 @@ pub enum DestroyZoneVolumeError {
 #[From(unnamed)]
 pub enum SendZoneVolumeError {
     SendZoneDirectoryVolumeError(SendZoneDirectoryVolumeError),
-    #[cfg(target_os = "freebsd")]
+    #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
     SendZoneZfsVolumeError(SendZoneZfsVolumeError),
     SerializeZoneTransmissionError(SerializeZoneTransmissionError),
 }
@@ -64,7 +72,7 @@ pub enum SendZoneVolumeError {
 #[From(unnamed)]
 pub enum ReceiveZoneVolumeError {
     ReceiveZoneDirectoryVolumeError(ReceiveZoneDirectoryVolumeError),
-    #[cfg(target_os = "freebsd")]
+    #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
     ReceiveZoneZfsVolumeError(ReceiveZoneZfsVolumeError),
     DeserializeZoneTransmissionError(DeserializeZoneTransmissionError),
     #[Display("Unsupported transmission type ({value})")]
@@ -77,16 +85,111 @@ pub enum ReceiveZoneVolumeError {
 #[From(unnamed)]
 pub enum CleanupZoneVolumeError {
     CleanupZoneDirectoryVolumeError(CleanupZoneDirectoryVolumeError),
-    #[cfg(target_os = "freebsd")]
+    #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
     CleanupZoneZfsVolumeError(CleanupZoneZfsVolumeError),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Debug)]
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum CheckZoneVolumeSnapshotError {
+    #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+    CheckZoneZfsVolumeSnapshotError(CheckZoneZfsVolumeSnapshotError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum CreateZoneVolumeSnapshotError {
+    CreateZoneDirectoryVolumeSnapshotError(CreateZoneDirectoryVolumeSnapshotError),
+    #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+    CreateZoneZfsVolumeSnapshotError(CreateZoneZfsVolumeSnapshotError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum DestroyZoneVolumeSnapshotError {
+    DestroyZoneDirectoryVolumeSnapshotError(DestroyZoneDirectoryVolumeSnapshotError),
+    #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+    DestroyZoneZfsVolumeSnapshotError(DestroyZoneZfsVolumeSnapshotError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum RollbackZoneVolumeSnapshotError {
+    RollbackZoneDirectoryVolumeSnapshotError(RollbackZoneDirectoryVolumeSnapshotError),
+    #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+    RollbackZoneZfsVolumeSnapshotError(RollbackZoneZfsVolumeSnapshotError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum CheckZoneVolumeKeyError {
+    #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+    CheckZoneZfsVolumeKeyError(CheckZoneZfsVolumeKeyError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum LoadZoneVolumeKeyError {
+    #[Display("Volume is not encrypted")]
+    NotEncrypted,
+    #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+    LoadZoneZfsVolumeKeyError(LoadZoneZfsVolumeKeyError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum UnloadZoneVolumeKeyError {
+    #[Display("Volume is not encrypted")]
+    NotEncrypted,
+    #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+    UnloadZoneZfsVolumeKeyError(UnloadZoneZfsVolumeKeyError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum AttachZoneVolumeDatasetsError {
+    #[Display("Datasets can only be delegated from ZFS volumes")]
+    Unsupported,
+    #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+    AttachZoneZfsVolumeDatasetsError(AttachZoneZfsVolumeDatasetsError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum DetachZoneVolumeDatasetsError {
+    #[Display("Datasets can only be delegated from ZFS volumes")]
+    Unsupported,
+    #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+    DetachZoneZfsVolumeDatasetsError(DetachZoneZfsVolumeDatasetsError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, Display)]
 pub enum ZoneVolumeType {
+    #[Display("automatic")]
     Automatic,
+    #[Display("directory")]
     Directory,
+    #[Display("zfs")]
     Zfs,
 }
 
@@ -114,15 +217,23 @@ enum ZoneVolumeTransmissionHeader {
 #[derive(Debug)]
 pub enum ZoneVolume<T> {
     Directory(ZoneDirectoryVolume<T>),
-    #[cfg(target_os = "freebsd")]
+    #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
     Zfs(ZoneZfsVolume<T>),
 }
 
 impl<'a> ZoneVolume<&'a Zone> {
+    pub fn r#type(&self) -> ZoneVolumeType {
+        match self {
+            Self::Directory(_directory) => ZoneVolumeType::Directory,
+            #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+            Self::Zfs(_zfs) => ZoneVolumeType::Zfs,
+        }
+    }
+
     pub fn root_directory_path(&self) -> PathBuf {
         match self {
             Self::Directory(directory) => directory.root_directory_path(),
-            #[cfg(target_os = "freebsd")]
+            #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
             Self::Zfs(zfs) => zfs.root_directory_path(),
         }
     }
@@ -144,20 +255,32 @@ impl<'a> ZoneVolume<&'a Zone> {
     pub(crate) fn create(
         zone: &'a Zone,
         r#type: ZoneVolumeType,
+        zfs_properties: &ZoneConfigurationVersion1ZfsPropertiesDirective,
+        zfs_volume: &ZoneConfigurationVersion1ZfsVolumeDirective,
     ) -> Result<(), CreateZoneVolumeError> {
         match r#type {
-            ZoneVolumeType::Automatic => {
-                if ZoneZfsVolume::is_supported(zone)? {
-                    ZoneZfsVolume::create(zone)?;
-                } else {
+            ZoneVolumeType::Automatic => match ZoneZfsVolume::unsupported_reason(zone)? {
+                None => {
+                    ZoneZfsVolume::create(zone, zfs_properties, zfs_volume)?;
+                }
+                Some(reason) => {
+                    info!(
+                        "Creating directory volume for zone {}: {}",
+                        zone.identifier(),
+                        reason
+                    );
                     ZoneDirectoryVolume::create(zone)?;
                 }
-            }
+            },
             ZoneVolumeType::Directory => {
                 ZoneDirectoryVolume::create(zone)?;
             }
             ZoneVolumeType::Zfs => {
-                ZoneZfsVolume::create(zone)?;
+                if let Some(reason) = ZoneZfsVolume::unsupported_reason(zone)? {
+                    return Err(CreateZoneVolumeError::ZfsUnsupported(reason));
+                }
+
+                ZoneZfsVolume::create(zone, zfs_properties, zfs_volume)?;
             }
         }
 
@@ -167,7 +290,7 @@ impl<'a> ZoneVolume<&'a Zone> {
     pub(crate) fn destroy(&self) -> Result<(), DestroyZoneVolumeError> {
         match self {
             Self::Directory(directory) => Ok(directory.destroy()?),
-            #[cfg(target_os = "freebsd")]
+            #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
             Self::Zfs(zfs) => Ok(zfs.destroy()?),
         }
     }
@@ -175,6 +298,8 @@ impl<'a> ZoneVolume<&'a Zone> {
     pub(crate) fn send(
         &self,
         writer: &mut ZoneTransmissionWriter,
+        header: &ZoneTransmissionVersion2Header,
+        options: &ZoneSendOptions,
     ) -> Result<(), SendZoneVolumeError> {
         match self {
             Self::Directory(directory) => {
@@ -182,15 +307,15 @@ impl<'a> ZoneVolume<&'a Zone> {
                     r#type: ZoneVolumeTransmissionVersion1Type::Directory,
                 })?;
 
-                Ok(directory.send(writer)?)
+                Ok(directory.send(writer, header, options)?)
             }
-            #[cfg(target_os = "freebsd")]
+            #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
             Self::Zfs(zfs) => {
                 writer.serialize(&ZoneVolumeTransmissionHeader::Version1 {
                     r#type: ZoneVolumeTransmissionVersion1Type::Zfs,
                 })?;
 
-                Ok(zfs.send(writer)?)
+                Ok(zfs.send(writer, header, options)?)
             }
         }
     }
@@ -198,33 +323,123 @@ impl<'a> ZoneVolume<&'a Zone> {
     pub(crate) fn receive(
         zone: &'a Zone,
         reader: &mut ZoneTransmissionReader,
+        header: &ZoneTransmissionVersion2Header,
     ) -> Result<Self, ReceiveZoneVolumeError> {
-        let header = reader.deserialize::<ZoneVolumeTransmissionHeader>()?;
+        let volume_header = reader.deserialize::<ZoneVolumeTransmissionHeader>()?;
 
-        let r#type = match header {
+        let r#type = match volume_header {
             ZoneVolumeTransmissionHeader::Version1 { r#type } => r#type,
         };
 
         match r#type {
-            #[cfg(target_os = "freebsd")]
+            #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
             ZoneVolumeTransmissionVersion1Type::Zfs => {
-                Ok(Self::Zfs(ZoneZfsVolume::receive(zone, reader)?))
+                Ok(Self::Zfs(ZoneZfsVolume::receive(zone, reader, header)?))
             }
-            #[cfg(not(target_os = "freebsd"))]
+            #[cfg(not(any(target_os = "freebsd", feature = "fake-zfs")))]
             ZoneVolumeTransmissionVersion1Type::Zfs => Err(
                 ReceiveZoneVolumeError::UnsupportedTransmissionType(String::from("ZFS")),
             ),
-            ZoneVolumeTransmissionVersion1Type::Directory => {
-                Ok(Self::Directory(ZoneDirectoryVolume::receive(zone, reader)?))
-            }
+            ZoneVolumeTransmissionVersion1Type::Directory => Ok(Self::Directory(
+                ZoneDirectoryVolume::receive(zone, reader, header)?,
+            )),
         }
     }
 
     pub(crate) fn cleanup(&self) -> Result<(), CleanupZoneVolumeError> {
         match self {
             Self::Directory(directory) => Ok(directory.cleanup()?),
-            #[cfg(target_os = "freebsd")]
+            #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
             Self::Zfs(zfs) => Ok(zfs.cleanup()?),
         }
     }
+
+    /// Returns whether the key of the volume is loaded. Unencrypted volumes have no key and count
+    /// as loaded.
+    pub(crate) fn is_key_loaded(&self) -> Result<bool, CheckZoneVolumeKeyError> {
+        match self {
+            Self::Directory(_directory) => Ok(true),
+            #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+            Self::Zfs(zfs) => Ok(zfs.is_key_loaded()?),
+        }
+    }
+
+    pub(crate) fn load_key(&self) -> Result<(), LoadZoneVolumeKeyError> {
+        match self {
+            Self::Directory(_directory) => Err(LoadZoneVolumeKeyError::NotEncrypted),
+            #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+            Self::Zfs(zfs) => Ok(zfs.load_key()?),
+        }
+    }
+
+    pub(crate) fn unload_key(&self) -> Result<(), UnloadZoneVolumeKeyError> {
+        match self {
+            Self::Directory(_directory) => Err(UnloadZoneVolumeKeyError::NotEncrypted),
+            #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+            Self::Zfs(zfs) => Ok(zfs.unload_key()?),
+        }
+    }
+
+    /// Attaches the datasets below the volume to the jail, creating missing ones.
+    pub(crate) fn attach_datasets(
+        &self,
+        names: &[String],
+        jail_id: JailId,
+    ) -> Result<(), AttachZoneVolumeDatasetsError> {
+        match self {
+            Self::Directory(_directory) => Err(AttachZoneVolumeDatasetsError::Unsupported),
+            #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+            Self::Zfs(zfs) => Ok(zfs.attach_datasets(names, jail_id)?),
+        }
+    }
+
+    pub(crate) fn detach_datasets(
+        &self,
+        names: &[String],
+        jail_id: JailId,
+    ) -> Result<(), DetachZoneVolumeDatasetsError> {
+        match self {
+            Self::Directory(_directory) => Err(DetachZoneVolumeDatasetsError::Unsupported),
+            #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+            Self::Zfs(zfs) => Ok(zfs.detach_datasets(names, jail_id)?),
+        }
+    }
+
+    pub(crate) fn has_snapshot(&self, name: &str) -> Result<bool, CheckZoneVolumeSnapshotError> {
+        match self {
+            Self::Directory(directory) => Ok(directory.has_snapshot(name)),
+            #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+            Self::Zfs(zfs) => Ok(zfs.has_snapshot(name)?),
+        }
+    }
+
+    pub(crate) fn create_snapshot(&self, name: &str) -> Result<(), CreateZoneVolumeSnapshotError> {
+        match self {
+            Self::Directory(directory) => Ok(directory.create_snapshot(name)?),
+            #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+            Self::Zfs(zfs) => Ok(zfs.create_snapshot(name)?),
+        }
+    }
+
+    pub(crate) fn destroy_snapshot(
+        &self,
+        name: &str,
+    ) -> Result<(), DestroyZoneVolumeSnapshotError> {
+        match self {
+            Self::Directory(directory) => Ok(directory.destroy_snapshot(name)?),
+            #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+            Self::Zfs(zfs) => Ok(zfs.destroy_snapshot(name)?),
+        }
+    }
+
+    pub(crate) fn rollback_snapshot(
+        &self,
+        name: &str,
+    ) -> Result<(), RollbackZoneVolumeSnapshotError> {
+        match self {
+            Self::Directory(directory) => Ok(directory.rollback_snapshot(name)?),
+            #[cfg(any(target_os = "freebsd", feature = "fake-zfs"))]
+            Self::Zfs(zfs) => Ok(zfs.rollback_snapshot(name)?),
+        }
+    }
 }
@@ -1,18 +1,35 @@
 use crate::{
-    FileSystemIdentifierTryFromZoneIdentifierError, Zone, ZoneTransmissionReader,
-    ZoneTransmissionWriter,
+    FileSystemIdentifierTryFromZoneIdentifierError, Zone,
+    ZoneConfigurationVersion1ZfsPropertiesDirective, ZoneConfigurationVersion1ZfsVolumeDirective,
+    ZoneIdentifierBase, ZoneIdentifierTryFromPathError, ZoneSendOptions, ZoneTransmissionReader,
+    ZoneTransmissionVersion2Header, ZoneTransmissionWriter,
 };
+use jail::JailId;
+use std::num::TryFromIntError;
 use std::os::fd::AsRawFd;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use zfs::backend::{self, Backend, BackendError};
 use zfs::file_system::error::{
-    CreateFileSystemError, DestroyFileSystemError, MountFileSystemError, OpenFileSystemError,
-    OpenFileSystemSnapshotIteratorError, ReceiveFileSystemError, SendFileSystemError,
-    UnmountAllFileSystemError,
+    FromStrFileSystemCompressionError, FromStrFileSystemEncryptionError,
+    FromStrFileSystemKeyFormatError,
 };
 use zfs::file_system::identifier::FileSystemIdentifier;
-use zfs::file_system::FileSystem;
-use zfs::snapshot::error::DestroySnapshotError;
-use ztd::{Constructor, Display, Error, From};
+use zfs::file_system::property::{
+    FileSystemCompression, FileSystemEncryption, FileSystemKeyFormat, FileSystemKeyStatus,
+    FileSystemProperty,
+};
+use zfs::pool::identifier::PoolIdentifier;
+use zfs::pool::PoolHealth;
+use zfs::snapshot::identifier::SnapshotIdentifier;
+use zfs::snapshot::options::{ReceiveOptions, SendOptions};
+use ztd::{Constructor, Display, Error, From, Method};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The allocated space of a pool in percent from which on no ZFS volumes are created on it, since
+/// ZFS slows down on nearly full pools.
+pub const ZONE_ZFS_VOLUME_MAXIMUM_POOL_CAPACITY: u64 = 90;
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
@@ -20,7 +37,7 @@ use ztd::{Constructor, Display, Error, From};
 #[From(unnamed)]
 pub enum CheckZoneZfsVolumeSupportError {
     FileSystemIdentifierTryFromZoneIdentifierError(FileSystemIdentifierTryFromZoneIdentifierError),
-    OpenFileSystemError(OpenFileSystemError),
+    BackendError(BackendError),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -29,7 +46,7 @@ pub enum CheckZoneZfsVolumeSupportError {
 #[From(unnamed)]
 pub enum OpenZoneZfsVolumeError {
     FileSystemIdentifierTryFromZoneIdentifierError(FileSystemIdentifierTryFromZoneIdentifierError),
-    OpenFileSystemError(OpenFileSystemError),
+    BackendError(BackendError),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -38,11 +55,10 @@ pub enum OpenZoneZfsVolumeError {
 #[From(unnamed)]
 pub enum CreateZoneZfsVolumeError {
     FileSystemIdentifierTryFromZoneIdentifierError(FileSystemIdentifierTryFromZoneIdentifierError),
-    #[Display("Created file system does not exist")]
-    FileSystemNotExisting,
-    OpenFileSystemError(OpenFileSystemError),
-    CreateFileSystemError(CreateFileSystemError),
-    MountFileSystemError(MountFileSystemError),
+    FromStrFileSystemCompressionError(FromStrFileSystemCompressionError),
+    FromStrFileSystemEncryptionError(FromStrFileSystemEncryptionError),
+    FromStrFileSystemKeyFormatError(FromStrFileSystemKeyFormatError),
+    BackendError(BackendError),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -51,13 +67,7 @@ pub enum CreateZoneZfsVolumeError {
 #[From(unnamed)]
 pub enum DestroyZoneZfsVolumeError {
     FileSystemIdentifierTryFromZoneIdentifierError(FileSystemIdentifierTryFromZoneIdentifierError),
-    OpenFileSystemError(OpenFileSystemError),
-    #[Display("File system does not exist")]
-    FileSystemNotExisting,
-    DestroyFileSystemError(DestroyFileSystemError),
-    DestroySnapshotError(DestroySnapshotError),
-    OpenFileSystemSnapshotIteratorError(OpenFileSystemSnapshotIteratorError),
-    UnmountAllFileSystemError(UnmountAllFileSystemError),
+    BackendError(BackendError),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -66,10 +76,7 @@ pub enum DestroyZoneZfsVolumeError {
 #[From(unnamed)]
 pub enum SendZoneZfsVolumeError {
     FileSystemIdentifierTryFromZoneIdentifierError(FileSystemIdentifierTryFromZoneIdentifierError),
-    OpenFileSystemError(OpenFileSystemError),
-    SendFileSystemError(SendFileSystemError),
-    #[Display("File system does not exist")]
-    FileSystemNotExisting,
+    BackendError(BackendError),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -78,7 +85,7 @@ pub enum SendZoneZfsVolumeError {
 #[From(unnamed)]
 pub enum ReceiveZoneZfsVolumeError {
     FileSystemIdentifierTryFromZoneIdentifierError(FileSystemIdentifierTryFromZoneIdentifierError),
-    ReceiveFileSystemError(ReceiveFileSystemError),
+    BackendError(BackendError),
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -87,11 +94,177 @@ pub enum ReceiveZoneZfsVolumeError {
 #[From(unnamed)]
 pub enum CleanupZoneZfsVolumeError {
     FileSystemIdentifierTryFromZoneIdentifierError(FileSystemIdentifierTryFromZoneIdentifierError),
-    OpenFileSystemError(OpenFileSystemError),
-    DestroyFileSystemError(DestroyFileSystemError),
-    DestroySnapshotError(DestroySnapshotError),
-    OpenFileSystemSnapshotIteratorError(OpenFileSystemSnapshotIteratorError),
-    UnmountAllFileSystemError(UnmountAllFileSystemError),
+    BackendError(BackendError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum CheckZoneZfsVolumeSnapshotError {
+    FileSystemIdentifierTryFromZoneIdentifierError(FileSystemIdentifierTryFromZoneIdentifierError),
+    BackendError(BackendError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum CreateZoneZfsVolumeSnapshotError {
+    FileSystemIdentifierTryFromZoneIdentifierError(FileSystemIdentifierTryFromZoneIdentifierError),
+    BackendError(BackendError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum DestroyZoneZfsVolumeSnapshotError {
+    FileSystemIdentifierTryFromZoneIdentifierError(FileSystemIdentifierTryFromZoneIdentifierError),
+    BackendError(BackendError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum RollbackZoneZfsVolumeSnapshotError {
+    FileSystemIdentifierTryFromZoneIdentifierError(FileSystemIdentifierTryFromZoneIdentifierError),
+    BackendError(BackendError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum CheckZoneZfsVolumeKeyError {
+    FileSystemIdentifierTryFromZoneIdentifierError(FileSystemIdentifierTryFromZoneIdentifierError),
+    BackendError(BackendError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum LoadZoneZfsVolumeKeyError {
+    FileSystemIdentifierTryFromZoneIdentifierError(FileSystemIdentifierTryFromZoneIdentifierError),
+    BackendError(BackendError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum UnloadZoneZfsVolumeKeyError {
+    FileSystemIdentifierTryFromZoneIdentifierError(FileSystemIdentifierTryFromZoneIdentifierError),
+    BackendError(BackendError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum AttachZoneZfsVolumeDatasetsError {
+    FileSystemIdentifierTryFromZoneIdentifierError(FileSystemIdentifierTryFromZoneIdentifierError),
+    TryFromIntError(TryFromIntError),
+    BackendError(BackendError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum DetachZoneZfsVolumeDatasetsError {
+    FileSystemIdentifierTryFromZoneIdentifierError(FileSystemIdentifierTryFromZoneIdentifierError),
+    TryFromIntError(TryFromIntError),
+    BackendError(BackendError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display, Error, From)]
+#[From(unnamed)]
+pub enum DiagnoseZoneZfsPoolError {
+    ZoneIdentifierTryFromPathError(ZoneIdentifierTryFromPathError),
+    #[Display("Base path does not contain a pool")]
+    MissingPool,
+    BackendError(BackendError),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, Display)]
+pub enum ZoneZfsPoolWarning {
+    #[Display("Pool does not exist")]
+    NotExisting,
+    #[Display("Pool is {value}")]
+    NotOnline(PoolHealth),
+    #[Display("Pool is {value}% full")]
+    NearlyFull(u64),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The health and capacity of the pool behind a base path.
+#[derive(Debug, Method)]
+pub struct ZoneZfsPoolDiagnosis {
+    identifier: PoolIdentifier,
+    health: Option<PoolHealth>,
+    capacity: Option<u64>,
+}
+
+impl ZoneZfsPoolDiagnosis {
+    pub fn diagnose(base_path: &Path) -> Result<Self, DiagnoseZoneZfsPoolError> {
+        let identifier = match ZoneIdentifierBase::try_from(base_path)?
+            .components()
+            .first()
+        {
+            Some(component) => PoolIdentifier::new(component.clone()),
+            None => return Err(DiagnoseZoneZfsPoolError::MissingPool),
+        };
+
+        let backend = backend::current();
+
+        Ok(Self {
+            health: backend.pool_health(&identifier)?,
+            capacity: backend.pool_capacity(&identifier)?,
+            identifier,
+        })
+    }
+
+    /// Degraded pools still work, but lose data on further device failures.
+    pub fn warnings(&self) -> Vec<ZoneZfsPoolWarning> {
+        let mut warnings = Vec::default();
+
+        match self.health {
+            None => warnings.push(ZoneZfsPoolWarning::NotExisting),
+            Some(PoolHealth::Online) => {}
+            Some(health) => warnings.push(ZoneZfsPoolWarning::NotOnline(health)),
+        }
+
+        match self.capacity {
+            Some(capacity) if capacity >= ZONE_ZFS_VOLUME_MAXIMUM_POOL_CAPACITY => {
+                warnings.push(ZoneZfsPoolWarning::NearlyFull(capacity))
+            }
+            _ => {}
+        }
+
+        warnings
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, Display)]
+pub enum ZoneZfsVolumeUnsupportedReason {
+    #[Display("Pool does not exist")]
+    PoolNotExisting,
+    #[Display("Pool is {value}")]
+    PoolNotAvailable(PoolHealth),
+    #[Display("Pool is {value}% full")]
+    PoolNearlyFull(u64),
+    #[Display("Parent file system does not exist")]
+    ParentFileSystemNotExisting,
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -107,57 +280,182 @@ impl<'a> ZoneZfsVolume<&'a Zone> {
         self.zone.paths().root_directory()
     }
 
-    pub(super) fn is_supported(zone: &'a Zone) -> Result<bool, CheckZoneZfsVolumeSupportError> {
-        let mut file_system_identifier = FileSystemIdentifier::try_from(zone.identifier().clone())?;
+    /// Encrypted file systems are always sent raw, so that they stay encrypted and can be sent
+    /// without their key.
+    fn send_options(
+        &self,
+        options: &ZoneSendOptions,
+    ) -> Result<SendOptions, SendZoneZfsVolumeError> {
+        let key_status = backend::current().key_status(&self.file_system_identifier()?)?;
+
+        Ok(SendOptions::default()
+            .large_block(options.is_large_block())
+            .embed_data(options.is_embed_data())
+            .compressed(options.is_compressed())
+            .raw(key_status != FileSystemKeyStatus::None))
+    }
+
+    fn receive_options() -> ReceiveOptions {
+        ReceiveOptions::default()
+    }
+
+    fn file_system_identifier(
+        &self,
+    ) -> Result<FileSystemIdentifier, FileSystemIdentifierTryFromZoneIdentifierError> {
+        FileSystemIdentifier::try_from(self.zone.identifier().clone())
+    }
+
+    /// Returns the identifier of a dataset below the file system, whose name may contain
+    /// further components. Names are validated when the configuration is read.
+    fn dataset_identifier(
+        &self,
+        name: &str,
+    ) -> Result<FileSystemIdentifier, FileSystemIdentifierTryFromZoneIdentifierError> {
+        let mut identifier = self.file_system_identifier()?;
+
+        identifier
+            .components_mut()
+            .extend(name.split('/').map(String::from));
+
+        Ok(identifier)
+    }
+
+    /// Destroys the file system with its snapshots and children, which include delegated
+    /// datasets.
+    fn destroy_file_system(
+        backend: &dyn Backend,
+        identifier: &FileSystemIdentifier,
+    ) -> Result<(), BackendError> {
+        for child_identifier in backend.children(identifier)? {
+            Self::destroy_file_system(backend, &child_identifier)?;
+        }
+
+        for snapshot_identifier in backend.snapshots(identifier)? {
+            backend.destroy_snapshot(&snapshot_identifier)?;
+        }
+
+        backend.unmount_all_file_system(identifier)?;
+        backend.destroy_file_system(identifier)?;
+
+        Ok(())
+    }
 
-        loop {
-            file_system_identifier = file_system_identifier.parent();
+    fn snapshot_identifier(
+        &self,
+        name: &str,
+    ) -> Result<SnapshotIdentifier, FileSystemIdentifierTryFromZoneIdentifierError> {
+        Ok(SnapshotIdentifier::new(
+            self.file_system_identifier()?,
+            name.to_string(),
+        ))
+    }
 
-            if FileSystem::open(&file_system_identifier)?.is_some() {
-                return Ok(true);
+    /// Returns why a ZFS volume cannot be created for the zone, or `None` if it can.
+    pub(super) fn unsupported_reason(
+        zone: &'a Zone,
+    ) -> Result<Option<ZoneZfsVolumeUnsupportedReason>, CheckZoneZfsVolumeSupportError> {
+        let file_system_identifier = FileSystemIdentifier::try_from(zone.identifier().clone())?;
+        let backend = backend::current();
+
+        match backend.pool_health(file_system_identifier.pool_identifier())? {
+            None => return Ok(Some(ZoneZfsVolumeUnsupportedReason::PoolNotExisting)),
+            Some(PoolHealth::Online | PoolHealth::Degraded) => {}
+            Some(health) => {
+                return Ok(Some(ZoneZfsVolumeUnsupportedReason::PoolNotAvailable(
+                    health,
+                )))
             }
+        };
 
-            if file_system_identifier.components().is_empty() {
-                break;
+        match backend.pool_capacity(file_system_identifier.pool_identifier())? {
+            Some(capacity) if capacity >= ZONE_ZFS_VOLUME_MAXIMUM_POOL_CAPACITY => {
+                return Ok(Some(ZoneZfsVolumeUnsupportedReason::PoolNearlyFull(
+                    capacity,
+                )))
             }
+            _ => {}
         }
 
-        Ok(false)
+        if !backend.has_file_system(&file_system_identifier.parent())? {
+            return Ok(Some(
+                ZoneZfsVolumeUnsupportedReason::ParentFileSystemNotExisting,
+            ));
+        }
+
+        Ok(None)
     }
 
     pub(super) fn open(zone: &'a Zone) -> Result<Option<Self>, OpenZoneZfsVolumeError> {
         let file_system_identifier = FileSystemIdentifier::try_from(zone.identifier().clone())?;
 
-        match FileSystem::open(&file_system_identifier)? {
-            None => Ok(None),
-            Some(_file_system) => Ok(Some(Self::new(zone))),
+        match backend::current().has_file_system(&file_system_identifier)? {
+            false => Ok(None),
+            true => Ok(Some(Self::new(zone))),
         }
     }
 
-    pub(super) fn create(zone: &'a Zone) -> Result<(), CreateZoneZfsVolumeError> {
+    /// Creates the file system with the properties and encryption settings already set, so that
+    /// no data is written with other settings.
+    pub(super) fn create(
+        zone: &'a Zone,
+        properties: &ZoneConfigurationVersion1ZfsPropertiesDirective,
+        volume: &ZoneConfigurationVersion1ZfsVolumeDirective,
+    ) -> Result<(), CreateZoneZfsVolumeError> {
+        let mut file_system_properties = Vec::default();
+
+        if let Some(encryption) = volume.encryption() {
+            file_system_properties.push(FileSystemProperty::Encryption(
+                FileSystemEncryption::from_str(encryption)?,
+            ));
+        }
+
+        if let Some(keyformat) = volume.keyformat() {
+            file_system_properties.push(FileSystemProperty::KeyFormat(
+                FileSystemKeyFormat::from_str(keyformat)?,
+            ));
+        }
+
+        if let Some(keylocation) = volume.keylocation() {
+            file_system_properties.push(FileSystemProperty::KeyLocation(keylocation.clone()));
+        }
+
+        if let Some(compression) = properties.compression() {
+            file_system_properties.push(FileSystemProperty::Compression(
+                FileSystemCompression::from_str(compression)?,
+            ));
+        }
+
+        if properties.quota().is_some() {
+            file_system_properties.push(FileSystemProperty::Quota(*properties.quota()));
+        }
+
+        if properties.refquota().is_some() {
+            file_system_properties.push(FileSystemProperty::Refquota(*properties.refquota()));
+        }
+
+        if properties.reservation().is_some() {
+            file_system_properties.push(FileSystemProperty::Reservation(*properties.reservation()));
+        }
+
+        if let Some(recordsize) = properties.recordsize() {
+            file_system_properties.push(FileSystemProperty::RecordSize(*recordsize));
+        }
+
+        if let Some(atime) = properties.atime() {
+            file_system_properties.push(FileSystemProperty::Atime(*atime));
+        }
+
         let file_system_identifier = FileSystemIdentifier::try_from(zone.identifier().clone())?;
-        FileSystem::create(&file_system_identifier)?;
-        let mut file_system = FileSystem::open(&file_system_identifier)?
-            .ok_or(CreateZoneZfsVolumeError::FileSystemNotExisting)?;
-        file_system.mount()?;
+        let backend = backend::current();
+
+        backend.create_file_system(&file_system_identifier, &file_system_properties)?;
+        backend.mount_file_system(&file_system_identifier)?;
 
         Ok(())
     }
 
     pub(super) fn destroy(&self) -> Result<(), DestroyZoneZfsVolumeError> {
-        let mut file_system = match FileSystem::open(&FileSystemIdentifier::try_from(
-            self.zone.identifier().clone(),
-        )?)? {
-            Some(file_system) => file_system,
-            None => return Err(DestroyZoneZfsVolumeError::FileSystemNotExisting),
-        };
-
-        for snapshot in file_system.snapshots().iter()? {
-            snapshot.destroy()?;
-        }
-
-        file_system.unmount_all()?;
-        file_system.destroy()?;
+        Self::destroy_file_system(backend::current().as_ref(), &self.file_system_identifier()?)?;
 
         Ok(())
     }
@@ -165,15 +463,35 @@ impl<'a> ZoneZfsVolume<&'a Zone> {
     pub(super) fn send(
         &self,
         writer: &mut ZoneTransmissionWriter,
+        header: &ZoneTransmissionVersion2Header,
+        options: &ZoneSendOptions,
     ) -> Result<(), SendZoneZfsVolumeError> {
-        let mut file_system = match FileSystem::open(&FileSystemIdentifier::try_from(
-            self.zone.identifier().clone(),
-        )?)? {
-            Some(file_system) => file_system,
-            None => return Err(SendZoneZfsVolumeError::FileSystemNotExisting),
+        let backend = backend::current();
+        let options = self.send_options(options)?;
+
+        let name = match header.snapshot() {
+            Some(name) => name,
+            None => {
+                backend.send_file_system(
+                    &self.file_system_identifier()?,
+                    writer.as_raw_fd(),
+                    &options,
+                )?;
+                return Ok(());
+            }
+        };
+
+        let base = match header.base() {
+            Some(base) => Some(self.snapshot_identifier(base)?),
+            None => None,
         };
 
-        file_system.send(writer.as_raw_fd())?;
+        backend.send_snapshot(
+            &self.snapshot_identifier(name)?,
+            base.as_ref(),
+            writer.as_raw_fd(),
+            &options,
+        )?;
 
         Ok(())
     }
@@ -181,20 +499,463 @@ impl<'a> ZoneZfsVolume<&'a Zone> {
     pub(super) fn receive(
         zone: &'a Zone,
         reader: &mut ZoneTransmissionReader,
+        header: &ZoneTransmissionVersion2Header,
     ) -> Result<Self, ReceiveZoneZfsVolumeError> {
-        FileSystem::receive(zone.identifier().clone().try_into()?, reader.as_raw_fd())?;
+        let file_system_identifier = FileSystemIdentifier::try_from(zone.identifier().clone())?;
+        let backend = backend::current();
+
+        match header.snapshot() {
+            // Received snapshots are kept, since later transmissions may be based on them.
+            Some(name) => backend.receive_snapshot(
+                &SnapshotIdentifier::new(file_system_identifier, name.clone()),
+                reader.as_raw_fd(),
+                &Self::receive_options(),
+            )?,
+            None => backend.receive_file_system(
+                &file_system_identifier,
+                reader.as_raw_fd(),
+                &Self::receive_options(),
+            )?,
+        };
 
         Ok(Self::new(zone))
     }
 
     pub(super) fn cleanup(&self) -> Result<(), CleanupZoneZfsVolumeError> {
-        if let Some(mut file_system) = FileSystem::open(&FileSystemIdentifier::try_from(
-            self.zone.identifier().clone(),
-        )?)? {
-            file_system.unmount_all()?;
-            file_system.destroy()?;
-        };
+        let file_system_identifier = self.file_system_identifier()?;
+        let backend = backend::current();
+
+        if backend.has_file_system(&file_system_identifier)? {
+            Self::destroy_file_system(backend.as_ref(), &file_system_identifier)?;
+        }
+
+        Ok(())
+    }
+
+    /// Attaches the datasets to the jail. Missing datasets are created, and existing ones are
+    /// unmounted from the host and marked as jailed.
+    pub(super) fn attach_datasets(
+        &self,
+        names: &[String],
+        jail_id: JailId,
+    ) -> Result<(), AttachZoneZfsVolumeDatasetsError> {
+        let jail_id = i32::try_from(usize::from(jail_id))?;
+        let backend = backend::current();
+
+        for name in names {
+            let dataset_identifier = self.dataset_identifier(name)?;
+
+            if backend.has_file_system(&dataset_identifier)? {
+                backend.unmount_all_file_system(&dataset_identifier)?;
+                backend.set_file_system_property(
+                    &dataset_identifier,
+                    &FileSystemProperty::Jailed(true),
+                )?;
+            } else {
+                backend
+                    .create_file_system(&dataset_identifier, &[FileSystemProperty::Jailed(true)])?;
+            }
+
+            backend.attach_file_system(&dataset_identifier, jail_id)?;
+        }
 
         Ok(())
     }
+
+    pub(super) fn detach_datasets(
+        &self,
+        names: &[String],
+        jail_id: JailId,
+    ) -> Result<(), DetachZoneZfsVolumeDatasetsError> {
+        let jail_id = i32::try_from(usize::from(jail_id))?;
+        let backend = backend::current();
+
+        for name in names {
+            backend.detach_file_system(&self.dataset_identifier(name)?, jail_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the key of the file system is loaded, which is always the case for
+    /// unencrypted file systems.
+    pub(super) fn is_key_loaded(&self) -> Result<bool, CheckZoneZfsVolumeKeyError> {
+        Ok(!backend::current()
+            .key_status(&self.file_system_identifier()?)?
+            .is_unavailable())
+    }
+
+    /// Loads the key of the file system and mounts it.
+    pub(super) fn load_key(&self) -> Result<(), LoadZoneZfsVolumeKeyError> {
+        let file_system_identifier = self.file_system_identifier()?;
+        let backend = backend::current();
+
+        backend.load_key(&file_system_identifier)?;
+        backend.mount_file_system(&file_system_identifier)?;
+
+        Ok(())
+    }
+
+    /// Unmounts the file system and unloads its key.
+    pub(super) fn unload_key(&self) -> Result<(), UnloadZoneZfsVolumeKeyError> {
+        let file_system_identifier = self.file_system_identifier()?;
+        let backend = backend::current();
+
+        backend.unmount_all_file_system(&file_system_identifier)?;
+        backend.unload_key(&file_system_identifier)?;
+
+        Ok(())
+    }
+
+    pub(super) fn has_snapshot(&self, name: &str) -> Result<bool, CheckZoneZfsVolumeSnapshotError> {
+        Ok(backend::current().has_snapshot(&self.snapshot_identifier(name)?)?)
+    }
+
+    pub(super) fn create_snapshot(
+        &self,
+        name: &str,
+    ) -> Result<(), CreateZoneZfsVolumeSnapshotError> {
+        Ok(backend::current().create_snapshot(&self.snapshot_identifier(name)?)?)
+    }
+
+    pub(super) fn destroy_snapshot(
+        &self,
+        name: &str,
+    ) -> Result<(), DestroyZoneZfsVolumeSnapshotError> {
+        let snapshot_identifier = self.snapshot_identifier(name)?;
+        let backend = backend::current();
+
+        if backend.has_snapshot(&snapshot_identifier)? {
+            backend.destroy_snapshot(&snapshot_identifier)?;
+        }
+
+        Ok(())
+    }
+
+    /// Rolls the file system back to the snapshot. ZFS destroys more recent snapshots on the way.
+    pub(super) fn rollback_snapshot(
+        &self,
+        name: &str,
+    ) -> Result<(), RollbackZoneZfsVolumeSnapshotError> {
+        Ok(backend::current().rollback_snapshot(&self.snapshot_identifier(name)?)?)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(all(test, feature = "fake-zfs"))]
+mod tests {
+    use super::*;
+    use crate::{ZoneIdentifier, ZoneIdentifierBase, ZoneIdentifierUuid};
+    use std::io::{Seek, SeekFrom};
+    use std::rc::Rc;
+    use tempfile::tempfile;
+    use zfs::backend::FakeBackend;
+
+    fn zone() -> Zone {
+        Zone::new(ZoneIdentifier::new(
+            ZoneIdentifierBase::new(vec![String::from("zroot"), String::from("zones")]),
+            ZoneIdentifierUuid::new_v4(),
+        ))
+    }
+
+    fn backend() -> Rc<FakeBackend> {
+        let backend = Rc::new(FakeBackend::default());
+        backend.add_pool(
+            &PoolIdentifier::new(String::from("zroot")),
+            PoolHealth::Online,
+        );
+        backend
+            .create_file_system(
+                &FileSystemIdentifier::new(
+                    PoolIdentifier::new(String::from("zroot")),
+                    vec![String::from("zones")],
+                ),
+                &[],
+            )
+            .unwrap();
+        backend::replace(backend.clone());
+
+        backend
+    }
+
+    #[test]
+    fn reports_missing_parent_file_system() {
+        let _backend = backend();
+        let zone = Zone::new(ZoneIdentifier::new(
+            ZoneIdentifierBase::new(vec![String::from("zroot"), String::from("jails")]),
+            ZoneIdentifierUuid::new_v4(),
+        ));
+
+        assert!(matches!(
+            ZoneZfsVolume::unsupported_reason(&zone).unwrap(),
+            Some(ZoneZfsVolumeUnsupportedReason::ParentFileSystemNotExisting)
+        ));
+    }
+
+    #[test]
+    fn reports_unavailable_and_nearly_full_pools() {
+        let backend = backend();
+        let zone = zone();
+        let pool_identifier = PoolIdentifier::new(String::from("zroot"));
+
+        assert!(ZoneZfsVolume::unsupported_reason(&zone).unwrap().is_none());
+
+        backend.set_pool_capacity(&pool_identifier, ZONE_ZFS_VOLUME_MAXIMUM_POOL_CAPACITY);
+        assert!(matches!(
+            ZoneZfsVolume::unsupported_reason(&zone).unwrap(),
+            Some(ZoneZfsVolumeUnsupportedReason::PoolNearlyFull(
+                ZONE_ZFS_VOLUME_MAXIMUM_POOL_CAPACITY
+            ))
+        ));
+
+        backend.add_pool(&pool_identifier, PoolHealth::Unavailable);
+        assert!(matches!(
+            ZoneZfsVolume::unsupported_reason(&zone).unwrap(),
+            Some(ZoneZfsVolumeUnsupportedReason::PoolNotAvailable(
+                PoolHealth::Unavailable
+            ))
+        ));
+    }
+
+    #[test]
+    fn diagnoses_degraded_and_nearly_full_pools() {
+        let backend = backend();
+        let pool_identifier = PoolIdentifier::new(String::from("zroot"));
+
+        let diagnosis = ZoneZfsPoolDiagnosis::diagnose(Path::new("/zroot/zones")).unwrap();
+        assert_eq!(diagnosis.identifier(), &pool_identifier);
+        assert_eq!(diagnosis.health(), &Some(PoolHealth::Online));
+        assert!(diagnosis.warnings().is_empty());
+
+        backend.add_pool(&pool_identifier, PoolHealth::Degraded);
+        backend.set_pool_capacity(&pool_identifier, ZONE_ZFS_VOLUME_MAXIMUM_POOL_CAPACITY);
+        assert!(matches!(
+            ZoneZfsPoolDiagnosis::diagnose(Path::new("/zroot/zones"))
+                .unwrap()
+                .warnings()[..],
+            [
+                ZoneZfsPoolWarning::NotOnline(PoolHealth::Degraded),
+                ZoneZfsPoolWarning::NearlyFull(ZONE_ZFS_VOLUME_MAXIMUM_POOL_CAPACITY)
+            ]
+        ));
+
+        assert!(matches!(
+            ZoneZfsPoolDiagnosis::diagnose(Path::new("/tank/zones"))
+                .unwrap()
+                .warnings()[..],
+            [ZoneZfsPoolWarning::NotExisting]
+        ));
+        assert!(matches!(
+            ZoneZfsPoolDiagnosis::diagnose(Path::new("/")),
+            Err(DiagnoseZoneZfsPoolError::MissingPool)
+        ));
+    }
+
+    #[test]
+    fn creates_file_system_with_properties() {
+        let backend = backend();
+        let zone = zone();
+
+        ZoneZfsVolume::create(
+            &zone,
+            &ZoneConfigurationVersion1ZfsPropertiesDirective::new(
+                Some(String::from("zstd")),
+                Some(1073741824),
+                None,
+                None,
+                None,
+                Some(false),
+            ),
+            &ZoneConfigurationVersion1ZfsVolumeDirective::default(),
+        )
+        .unwrap();
+
+        let file_system = backend
+            .file_system(&FileSystemIdentifier::try_from(zone.identifier().clone()).unwrap())
+            .unwrap();
+
+        assert!(file_system.is_mounted());
+        assert_eq!(
+            file_system.properties().get("compression"),
+            Some(&String::from("zstd"))
+        );
+        assert_eq!(
+            file_system.properties().get("quota"),
+            Some(&String::from("1073741824"))
+        );
+        assert_eq!(
+            file_system.properties().get("atime"),
+            Some(&String::from("off"))
+        );
+    }
+
+    #[test]
+    fn destroys_file_system_with_snapshots() {
+        let backend = backend();
+        let zone = zone();
+
+        ZoneZfsVolume::create(
+            &zone,
+            &ZoneConfigurationVersion1ZfsPropertiesDirective::default(),
+            &ZoneConfigurationVersion1ZfsVolumeDirective::default(),
+        )
+        .unwrap();
+
+        let volume = ZoneZfsVolume::open(&zone).unwrap().unwrap();
+        volume.create_snapshot("first").unwrap();
+        volume.create_snapshot("second").unwrap();
+        volume.destroy().unwrap();
+
+        assert!(ZoneZfsVolume::open(&zone).unwrap().is_none());
+        assert!(backend
+            .file_system(&FileSystemIdentifier::try_from(zone.identifier().clone()).unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn unloads_and_loads_keys_of_encrypted_volumes() {
+        let backend = backend();
+        let zone = zone();
+
+        ZoneZfsVolume::create(
+            &zone,
+            &ZoneConfigurationVersion1ZfsPropertiesDirective::default(),
+            &ZoneConfigurationVersion1ZfsVolumeDirective::new(
+                Some(String::from("aes-256-gcm")),
+                Some(String::from("passphrase")),
+                Some(String::from("prompt")),
+            ),
+        )
+        .unwrap();
+
+        let file_system_identifier =
+            FileSystemIdentifier::try_from(zone.identifier().clone()).unwrap();
+        let volume = ZoneZfsVolume::open(&zone).unwrap().unwrap();
+
+        assert_eq!(
+            backend
+                .file_system(&file_system_identifier)
+                .unwrap()
+                .properties()
+                .get("encryption"),
+            Some(&String::from("aes-256-gcm"))
+        );
+        assert!(volume.is_key_loaded().unwrap());
+
+        volume.unload_key().unwrap();
+        assert!(!volume.is_key_loaded().unwrap());
+        assert!(!backend
+            .file_system(&file_system_identifier)
+            .unwrap()
+            .is_mounted());
+
+        volume.load_key().unwrap();
+        assert!(volume.is_key_loaded().unwrap());
+        assert!(backend
+            .file_system(&file_system_identifier)
+            .unwrap()
+            .is_mounted());
+    }
+
+    #[test]
+    fn attaches_and_destroys_delegated_datasets() {
+        let backend = backend();
+        let zone = zone();
+
+        ZoneZfsVolume::create(
+            &zone,
+            &ZoneConfigurationVersion1ZfsPropertiesDirective::default(),
+            &ZoneConfigurationVersion1ZfsVolumeDirective::default(),
+        )
+        .unwrap();
+
+        let volume = ZoneZfsVolume::open(&zone).unwrap().unwrap();
+        let names = vec![String::from("data"), String::from("data/poudriere")];
+
+        volume.attach_datasets(&names, JailId::from(7)).unwrap();
+
+        for name in &names {
+            let dataset = backend
+                .file_system(&volume.dataset_identifier(name).unwrap())
+                .unwrap();
+
+            assert_eq!(dataset.jail_id(), Some(7));
+            assert_eq!(
+                dataset.properties().get("jailed"),
+                Some(&String::from("on"))
+            );
+        }
+
+        volume.detach_datasets(&names, JailId::from(7)).unwrap();
+        assert_eq!(
+            backend
+                .file_system(&volume.dataset_identifier("data").unwrap())
+                .unwrap()
+                .jail_id(),
+            None
+        );
+
+        volume.destroy().unwrap();
+        assert!(ZoneZfsVolume::open(&zone).unwrap().is_none());
+    }
+
+    #[test]
+    fn sends_and_receives_full_and_incremental_snapshots() {
+        let backend = backend();
+        let source = zone();
+        let target = zone();
+
+        ZoneZfsVolume::create(
+            &source,
+            &ZoneConfigurationVersion1ZfsPropertiesDirective::default(),
+            &ZoneConfigurationVersion1ZfsVolumeDirective::default(),
+        )
+        .unwrap();
+
+        let volume = ZoneZfsVolume::open(&source).unwrap().unwrap();
+        volume.create_snapshot("first").unwrap();
+        volume.create_snapshot("second").unwrap();
+
+        let transmit = |snapshot: &str, base: Option<&str>| {
+            let header = ZoneTransmissionVersion2Header::new(
+                *target.identifier().uuid(),
+                Some(snapshot.to_string()),
+                base.map(String::from),
+            );
+            let mut file = tempfile().unwrap();
+
+            volume
+                .send(
+                    &mut ZoneTransmissionWriter::new(file.as_raw_fd()),
+                    &header,
+                    &ZoneSendOptions::default(),
+                )
+                .unwrap();
+            file.seek(SeekFrom::Start(0)).unwrap();
+
+            ZoneZfsVolume::receive(
+                &target,
+                &mut ZoneTransmissionReader::new(file.as_raw_fd()),
+                &header,
+            )
+            .unwrap();
+        };
+
+        let target_identifier =
+            FileSystemIdentifier::try_from(target.identifier().clone()).unwrap();
+
+        transmit("first", None);
+        assert_eq!(
+            backend.file_system(&target_identifier).unwrap().snapshots(),
+            &vec![String::from("first")]
+        );
+
+        transmit("second", Some("first"));
+        assert_eq!(
+            backend.file_system(&target_identifier).unwrap().snapshots(),
+            &vec![String::from("first"), String::from("second")]
+        );
+        assert!(ZoneZfsVolume::open(&target).unwrap().is_some());
+    }
 }